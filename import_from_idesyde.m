@@ -0,0 +1,25 @@
+function import_from_idesyde(sourceModel,reversedModel)
+%IMPORT_FROM_IDESYDE annotates a simulink model with an IDeSyDe solution
+%
+% This function reads a model reversed by IDeSyDe (the JSON files in the
+% output path of a run) and writes the solution back into the blocks:
+%  - the block priority is set to the priority found by IDeSyDe;
+%  - the processor and memory are added to the block description.
+s = load_system(sourceModel);
+jo = jsondecode(fileread(reversedModel));
+blocks = cellstr(jo.mapped_blocks);
+processors = cellstr(jo.mapped_processors);
+memories = cellstr(jo.mapped_memories);
+for i = 1:numel(blocks)
+    block = blocks{i};
+    if getSimulinkBlockHandle(block) == -1
+        warning("Block %s is not in the model. Skipping it.", block);
+        continue;
+    end
+    set_param(block, "Priority", num2str(jo.mapped_priorities(i)));
+    description = sprintf("IDeSyDe: processor=%s, memory=%s", processors{i}, memories{i});
+    set_param(block, "Description", description);
+end
+save_system(s);
+close_system(s);
+end
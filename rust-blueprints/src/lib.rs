@@ -1,11 +1,24 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
 use idesyde_core::{
-    headers::load_decision_model_headers_from_binary, write_decision_model_to_path,
-    write_design_model_header_to_path, DecisionModel, DesignModel, IdentificationModule,
+    headers::{
+        load_decision_model_header_from_path, load_decision_model_headers_from_binary,
+        ExplorationBid,
+    },
+    protocol::{
+        read_message, write_message, ModuleDescription, ModuleKind, ModuleRequest, ModuleResponse,
+        PROTOCOL_VERSION,
+    },
+    write_decision_model_to_path, write_design_model_header_to_path, DecisionModel, DesignModel,
+    ExplorationModule, IdentificationModule, StandaloneExplorationModule,
     StandaloneIdentificationModule,
 };
+use log::{error, warn};
 
 #[derive(Parser, Debug)]
 #[command(author = "Rodolfo Jordao")]
@@ -48,94 +61,614 @@ pub struct IdentificationModuleArgs {
         default_value = "false"
     )]
     print_schema: bool,
+    #[arg(
+        long = "server",
+        help = "Keeps the module running, answering the requests of the orchestrator on the standard input.",
+        default_value = "false"
+    )]
+    server: bool,
+    #[arg(
+        long = "describe",
+        help = "Prints the description of this module as JSON, for the orchestrator to check its protocol version.",
+        default_value = "false"
+    )]
+    describe: bool,
+}
+
+/// Reads all the design models in `design_path` that `module` understands, writing their headers next to them.
+fn read_design_models(
+    module: &StandaloneIdentificationModule,
+    design_path: &Path,
+) -> Vec<Box<dyn DesignModel>> {
+    std::fs::create_dir_all(design_path)
+        .expect("Failed to create the design path during reverse identification.");
+    let mut design_models: Vec<Box<dyn DesignModel>> = Vec::new();
+    for pres in std::fs::read_dir(design_path).expect("Failed to read design path during start-up.")
+    {
+        let p = pres.expect("Failed to read directory entry during start-up");
+        if let Some(m) = module.read_design_model(&p.path()) {
+            let mut h = m.header();
+            h.model_paths.push(
+                p.path()
+                    .to_str()
+                    .expect("Failed to get OS string during start-up")
+                    .to_string(),
+            );
+            // named after the input, so that the headers of different inputs do not overwrite each other
+            if let Err(e) = write_design_model_header_to_path(
+                &h,
+                design_path,
+                &p.path()
+                    .file_stem()
+                    .map(|f| f.to_string_lossy())
+                    .unwrap_or_default(),
+                &module.unique_identifier(),
+            ) {
+                warn!("{}", e);
+            }
+            design_models.push(m);
+        }
+    }
+    design_models
+}
+
+/// Runs one identification step with the decision models in `identified_path`, returning the paths of
+/// the headers of the newly identified ones.
+fn identify(
+    module: &StandaloneIdentificationModule,
+    design_models: &Vec<Box<dyn DesignModel>>,
+    identified_path: &Path,
+    ident_step: i32,
+) -> Vec<String> {
+    std::fs::create_dir_all(identified_path)
+        .expect("Failed to create the identified path during reverse identification.");
+    let decision_models: Vec<Box<dyn DecisionModel>> =
+        load_decision_model_headers_from_binary(identified_path)
+            .iter()
+            .flat_map(|(_, x)| module.decision_header_to_model(x))
+            .collect();
+    let identified = module.identification_step(ident_step, design_models, &decision_models);
+    let mut header_paths = Vec::new();
+    for m in identified {
+        let prefix = format!("{:0>16}", ident_step);
+        let h = match write_decision_model_to_path(
+            &m,
+            identified_path,
+            prefix.as_str(),
+            module.unique_identifier().as_str(),
+        ) {
+            Ok(h) => h,
+            Err(e) => {
+                warn!("{}", e);
+                continue;
+            }
+        };
+        header_paths.push(
+            identified_path
+                .join(format!(
+                    "header_{}_{}_{}.msgpack",
+                    prefix,
+                    h.category,
+                    module.unique_identifier()
+                ))
+                .display()
+                .to_string(),
+        );
+    }
+    header_paths
+}
+
+/// The paths of the (MsgPack) headers of the solved decision models in `solved_path`.
+fn solution_header_paths(solved_path: &Path) -> Vec<PathBuf> {
+    std::fs::create_dir_all(solved_path)
+        .expect("Failed to create the solved path during reverse identification.");
+    let mut paths: Vec<PathBuf> = load_decision_model_headers_from_binary(solved_path)
+        .into_iter()
+        .map(|(p, _)| p)
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|x| x.eq_ignore_ascii_case("msgpack"))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Reverse identifies each of the solved decision models whose headers are in `solution_paths`,
+/// returning the paths of the headers of the design models written to `reverse_path`.
+///
+/// The solutions are reverse identified one at a time and the headers written for a solution are named
+/// after the header of that solution, so that reverse identifying later solutions does not overwrite them.
+fn reverse_identify(
+    module: &StandaloneIdentificationModule,
+    design_models: &Vec<Box<dyn DesignModel>>,
+    solution_paths: &[PathBuf],
+    reverse_path: &Path,
+    output_path_opt: Option<&Path>,
+) -> Vec<String> {
+    std::fs::create_dir_all(reverse_path)
+        .expect("Failed to create the reverse path during reverse identification.");
+    let mut header_paths = Vec::new();
+    for solution_path in solution_paths {
+        let Some(solved) = load_decision_model_header_from_path(solution_path)
+            .and_then(|h| module.decision_header_to_model(&h))
+        else {
+            continue;
+        };
+        let solution_name = solution_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.trim_start_matches("header_"))
+            .unwrap_or_default()
+            .to_string();
+        let reverse_identified = module.reverse_identification(&vec![solved], design_models);
+        for (i, m) in reverse_identified.iter().enumerate() {
+            let mut h = m.header();
+            if let Some(out_path) = output_path_opt {
+                if let Some(written) = module.write_design_model(m.as_ref(), out_path) {
+                    h.model_paths.push(written.display().to_string());
+                };
+            }
+            let prefix = format!("{}_{}", solution_name, i);
+            if let Err(e) = write_design_model_header_to_path(
+                &h,
+                reverse_path,
+                &prefix,
+                module.unique_identifier().as_str(),
+            ) {
+                warn!("{}", e);
+                continue;
+            }
+            header_paths.push(
+                reverse_path
+                    .join(format!(
+                        "header_{}_{}_{}.msgpack",
+                        prefix,
+                        h.category,
+                        module.unique_identifier()
+                    ))
+                    .display()
+                    .to_string(),
+            );
+        }
+    }
+    header_paths
+}
+
+/// Answers the requests of the orchestrator until it asks for a shutdown or closes the standard input.
+///
+/// The design models are only read once, when the server starts, and the decision models are read at
+/// every identification step, as other modules may have identified new ones in between.
+fn serve_identification_module(
+    module: &StandaloneIdentificationModule,
+    args: &IdentificationModuleArgs,
+) {
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    write_message(
+        &mut stdout,
+        &ModuleResponse::Ready {
+            unique_identifier: module.unique_identifier(),
+        },
+    )
+    .expect("Failed to answer the orchestrator during start-up.");
+    let design_models = args
+        .design_path_opt
+        .as_ref()
+        .map(|p| read_design_models(module, p))
+        .unwrap_or_default();
+    loop {
+        let request = match read_message::<_, ModuleRequest>(&mut stdin) {
+            Ok(Some(r)) => r,
+            Ok(None) => break,
+            Err(e) => {
+                error!("Failed to read a request from the orchestrator: {}", e);
+                break;
+            }
+        };
+        let response = match (request, args) {
+            (ModuleRequest::Shutdown, _) => break,
+            (
+                ModuleRequest::Identification { iteration },
+                IdentificationModuleArgs {
+                    identified_path_opt: Some(identified_path),
+                    ..
+                },
+            ) => ModuleResponse::Headers(identify(
+                module,
+                &design_models,
+                identified_path,
+                iteration,
+            )),
+            (
+                ModuleRequest::ReverseIdentification { solution_paths },
+                IdentificationModuleArgs {
+                    reverse_path_opt: Some(reverse_path),
+                    ..
+                },
+            ) => ModuleResponse::Headers(reverse_identify(
+                module,
+                &design_models,
+                &solution_paths
+                    .iter()
+                    .map(PathBuf::from)
+                    .collect::<Vec<PathBuf>>(),
+                reverse_path,
+                args.output_path_opt.as_deref(),
+            )),
+            (r, _) => ModuleResponse::Error(format!(
+                "{:?} cannot be answered by an identification module started with these paths",
+                r
+            )),
+        };
+        if let Err(e) = write_message(&mut stdout, &response) {
+            error!("Failed to answer the orchestrator: {}", e);
+            break;
+        }
+    }
+}
+
+/// Prints the description of a module, whose decision model categories are the titles of its schemas.
+fn print_description(unique_identifier: &str, kind: ModuleKind, schemas: &HashSet<String>) {
+    let description = ModuleDescription {
+        unique_identifier: unique_identifier.to_string(),
+        kind,
+        protocol_version: PROTOCOL_VERSION.to_string(),
+        decision_model_categories: schemas
+            .iter()
+            .flat_map(|s| serde_json::from_str::<serde_json::Value>(s).ok())
+            .flat_map(|v| {
+                v.get("title")
+                    .and_then(|t| t.as_str())
+                    .map(|t| t.to_string())
+            })
+            .collect(),
+        decision_model_schemas: schemas.iter().cloned().collect(),
+        server: true,
+    };
+    match serde_json::to_string(&description) {
+        Ok(s) => println!("{}", s),
+        Err(e) => error!(
+            "Failed to encode the description of {}: {}",
+            unique_identifier, e
+        ),
+    }
+}
+
+/// Logs to the standard error as `LEVEL: message` lines, which the orchestrator forwards to its own log
+/// with the level kept.
+fn init_module_logger() {
+    let _ = env_logger::Builder::new()
+        .target(env_logger::Target::Stderr)
+        .filter(None, log::LevelFilter::Debug)
+        .format(|buf, record| writeln!(buf, "{}: {}", record.level(), record.args()))
+        .try_init();
 }
 
 pub fn execute_standalone_identification_module(module: StandaloneIdentificationModule) {
     let args = IdentificationModuleArgs::parse();
-    if args.print_schema {
+    init_module_logger();
+    if args.describe {
+        print_description(
+            &module.unique_identifier(),
+            ModuleKind::Identification,
+            &module.decision_model_schemas,
+        );
+    } else if args.print_schema {
         for schema in &module.decision_model_schemas {
             println!("{}", schema);
         }
-    } else {
-        if let Some(design_path) = args.design_path_opt {
-            std::fs::create_dir_all(&design_path)
-                .expect("Failed to create the design path during reverse identification.");
-            let mut design_models: Vec<Box<dyn DesignModel>> = Vec::new();
-            for pres in std::fs::read_dir(&design_path)
-                .expect("Failed to read design path during start-up.")
-            {
-                let p = pres.expect("Failed to read directory entry during start-up");
-                if let Some(m) = module.read_design_model(&p.path()) {
-                    let mut h = m.header();
-                    h.model_paths.push(
-                        p.path()
-                            .to_str()
-                            .expect("Failed to get OS string during start-up")
-                            .to_string(),
-                    );
-                    write_design_model_header_to_path(
-                        &h,
-                        &design_path,
-                        "",
-                        &module.unique_identifier(),
-                    );
-                    design_models.push(m);
-                }
+    } else if args.server {
+        serve_identification_module(&module, &args);
+    } else if let Some(design_path) = &args.design_path_opt {
+        let design_models = read_design_models(&module, design_path);
+        let header_paths = match (
+            &args.identified_path_opt,
+            &args.solved_path_opt,
+            &args.reverse_path_opt,
+            args.identification_step,
+        ) {
+            (_, Some(solved_path), Some(reverse_path), _) => reverse_identify(
+                &module,
+                &design_models,
+                &solution_header_paths(solved_path),
+                reverse_path,
+                args.output_path_opt.as_deref(),
+            ),
+            (Some(identified_path), None, None, Some(ident_step)) => {
+                identify(&module, &design_models, identified_path, ident_step)
             }
-            match (
-                args.identified_path_opt,
-                args.solved_path_opt,
-                args.reverse_path_opt,
-                args.identification_step,
-            ) {
-                (_, Some(solved_path), Some(reverse_path), _) => {
-                    std::fs::create_dir_all(&solved_path)
-                        .expect("Failed to create the solved path during reverse identification.");
-                    std::fs::create_dir_all(&reverse_path)
-                        .expect("Failed to create the reverse path during reverse identification.");
-                    let solved: Vec<Box<dyn DecisionModel>> =
-                        load_decision_model_headers_from_binary(&solved_path)
-                            .iter()
-                            .flat_map(|(_, x)| module.decision_header_to_model(x))
-                            .collect();
-                    let reverse_identified = module.reverse_identification(&solved, &design_models);
-                    for m in reverse_identified {
-                        let mut h = m.header();
-                        if let Some(out_path) = &args.output_path_opt {
-                            if module.write_design_model(&m, out_path) {
-                                h.model_paths.push(out_path.to_str().expect("Failed to get a string out of the output path during reverse identification").to_string());
-                            };
-                        }
-                        write_design_model_header_to_path(
-                            &h,
-                            &reverse_path,
-                            "",
-                            module.unique_identifier().as_str(),
-                        );
-                    }
-                }
-                (Some(identified_path), None, None, Some(ident_step)) => {
-                    std::fs::create_dir_all(&identified_path).expect(
-                        "Failed to create the identified path during reverse identification.",
-                    );
-                    let decision_models: Vec<Box<dyn DecisionModel>> =
-                        load_decision_model_headers_from_binary(&identified_path)
-                            .iter()
-                            .flat_map(|(_, x)| module.decision_header_to_model(x))
-                            .collect();
-                    let identified =
-                        module.identification_step(ident_step, &design_models, &decision_models);
-                    for m in identified {
-                        write_decision_model_to_path(
-                            &m,
-                            &identified_path,
-                            format!("{:0>16}", ident_step).as_str(),
-                            module.unique_identifier().as_str(),
-                        );
-                    }
+            _ => Vec::new(),
+        };
+        for p in header_paths {
+            println!("{}", p);
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(author = "Rodolfo Jordao")]
+pub struct ExplorationModuleArgs {
+    #[arg(
+        short = 'c',
+        long,
+        help = "The header of the decision model to bid for. The bid is printed as JSON."
+    )]
+    bid_path_opt: Option<PathBuf>,
+    #[arg(
+        short = 'e',
+        long,
+        help = "The header of the decision model to explore. The headers of the solutions are printed as they are found."
+    )]
+    explore_path_opt: Option<PathBuf>,
+    #[arg(
+        short = 'i',
+        long,
+        help = "The path where identified decision models (and headers) are stored."
+    )]
+    identified_path_opt: Option<PathBuf>,
+    #[arg(
+        short = 'o',
+        long,
+        help = "The path where explored decision models (and headers) are stored."
+    )]
+    solved_path_opt: Option<PathBuf>,
+    #[arg(
+        long = "maximum-solutions",
+        allow_negative_numbers = true,
+        help = "The maximum number of solutions. If non-positive, there is no limit.",
+        default_value = "0"
+    )]
+    maximum_solutions: i64,
+    #[arg(
+        long = "total-timeout",
+        allow_negative_numbers = true,
+        help = "The exploration time-out in seconds. If non-positive, there is no time-out.",
+        default_value = "0"
+    )]
+    total_timeout: i64,
+    #[arg(
+        long = "time-resolution",
+        allow_negative_numbers = true,
+        help = "The time upsizing resolution, for explorers with mandatory discretization.",
+        default_value = "0"
+    )]
+    time_resolution: i64,
+    #[arg(
+        long = "memory-resolution",
+        allow_negative_numbers = true,
+        help = "The memory downsizing resolution, for explorers with mandatory discretization.",
+        default_value = "0"
+    )]
+    memory_resolution: i64,
+    #[arg(
+        long = "schemas",
+        help = "Prints decision model schemas from this module.",
+        default_value = "false"
+    )]
+    print_schema: bool,
+    #[arg(
+        long = "server",
+        help = "Keeps the module running, answering the requests of the orchestrator on the standard input.",
+        default_value = "false"
+    )]
+    server: bool,
+    #[arg(
+        long = "describe",
+        help = "Prints the description of this module as JSON, for the orchestrator to check its protocol version.",
+        default_value = "false"
+    )]
+    describe: bool,
+}
+
+/// Reads the decision model whose header is at `header_path`, if `module` can make sense of it.
+fn read_explored_decision_model(
+    module: &StandaloneExplorationModule,
+    header_path: &Path,
+) -> Option<Box<dyn DecisionModel>> {
+    load_decision_model_header_from_path(header_path)
+        .and_then(|h| module.decision_header_to_model(&h))
+}
+
+fn bid(module: &StandaloneExplorationModule, header_path: &Path) -> ExplorationBid {
+    match read_explored_decision_model(module, header_path) {
+        Some(m) => module.bid(&m),
+        None => ExplorationBid {
+            unique_identifier: module.unique_identifier(),
+            can_explore: false,
+            properties: std::collections::HashMap::new(),
+        },
+    }
+}
+
+/// The number after which the solutions of `module` in `solved_path` are numbered, so that the ones of
+/// previous explorations are not overwritten.
+fn next_solution_index(module: &StandaloneExplorationModule, solved_path: &Path) -> u64 {
+    let suffix = format!("_{}.msgpack", module.unique_identifier());
+    std::fs::read_dir(solved_path)
+        .map(|ls| {
+            ls.flatten()
+                .flat_map(|e| e.file_name().into_string().ok())
+                .filter(|f| f.starts_with("header_") && f.ends_with(&suffix))
+                .flat_map(|f| {
+                    f.trim_start_matches("header_")
+                        .split('_')
+                        .next()
+                        .and_then(|n| n.parse::<u64>().ok())
+                })
+                .map(|n| n + 1)
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0)
+}
+
+/// Explores the decision model whose header is at `header_path`, calling `on_solution` with the path of
+/// the header of every solution as soon as it is written to `solved_path`.
+#[allow(clippy::too_many_arguments)]
+fn explore<F: FnMut(String)>(
+    module: &StandaloneExplorationModule,
+    header_path: &Path,
+    solved_path: &Path,
+    max_sols: i64,
+    total_timeout: i64,
+    time_resolution: i64,
+    memory_resolution: i64,
+    mut on_solution: F,
+) {
+    std::fs::create_dir_all(solved_path)
+        .expect("Failed to create the solved path during exploration.");
+    let Some(m) = read_explored_decision_model(module, header_path) else {
+        warn!(
+            "The decision model at {} cannot be explored by {}",
+            header_path.display(),
+            module.unique_identifier()
+        );
+        return;
+    };
+    let mut index = next_solution_index(module, solved_path);
+    for sol in module.explore(
+        &m,
+        max_sols,
+        total_timeout,
+        time_resolution,
+        memory_resolution,
+    ) {
+        let prefix = format!("{:0>16}", index);
+        let h = match write_decision_model_to_path(
+            &sol,
+            solved_path,
+            prefix.as_str(),
+            module.unique_identifier().as_str(),
+        ) {
+            Ok(h) => h,
+            Err(e) => {
+                warn!("{}", e);
+                continue;
+            }
+        };
+        index += 1;
+        on_solution(
+            solved_path
+                .join(format!(
+                    "header_{}_{}_{}.msgpack",
+                    prefix,
+                    h.category,
+                    module.unique_identifier()
+                ))
+                .display()
+                .to_string(),
+        );
+    }
+}
+
+/// Answers the bidding and exploration requests of the orchestrator until it asks for a shutdown or closes
+/// the standard input.
+fn serve_exploration_module(module: &StandaloneExplorationModule, args: &ExplorationModuleArgs) {
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    write_message(
+        &mut stdout,
+        &ModuleResponse::Ready {
+            unique_identifier: module.unique_identifier(),
+        },
+    )
+    .expect("Failed to answer the orchestrator during start-up.");
+    loop {
+        let request = match read_message::<_, ModuleRequest>(&mut stdin) {
+            Ok(Some(r)) => r,
+            Ok(None) => break,
+            Err(e) => {
+                error!("Failed to read a request from the orchestrator: {}", e);
+                break;
+            }
+        };
+        let response = match (request, &args.solved_path_opt) {
+            (ModuleRequest::Shutdown, _) => break,
+            (
+                ModuleRequest::Bid {
+                    decision_model_path,
+                },
+                _,
+            ) => ModuleResponse::Bid(bid(module, Path::new(&decision_model_path))),
+            (
+                ModuleRequest::Explore {
+                    decision_model_path,
+                    maximum_solutions,
+                    total_timeout,
+                    time_resolution,
+                    memory_resolution,
+                },
+                Some(solved_path),
+            ) => {
+                let mut answered = true;
+                explore(
+                    module,
+                    Path::new(&decision_model_path),
+                    solved_path,
+                    maximum_solutions,
+                    total_timeout,
+                    time_resolution,
+                    memory_resolution,
+                    |p| {
+                        answered = answered
+                            && write_message(&mut stdout, &ModuleResponse::Solution(p)).is_ok();
+                    },
+                );
+                if !answered {
+                    error!("Failed to send a solution to the orchestrator");
+                    break;
                 }
-                _ => (),
+                ModuleResponse::Done
             }
+            (r, _) => ModuleResponse::Error(format!(
+                "{:?} cannot be answered by an exploration module started with these paths",
+                r
+            )),
+        };
+        if let Err(e) = write_message(&mut stdout, &response) {
+            error!("Failed to answer the orchestrator: {}", e);
+            break;
+        }
+    }
+}
+
+pub fn execute_standalone_exploration_module(module: StandaloneExplorationModule) {
+    let args = ExplorationModuleArgs::parse();
+    init_module_logger();
+    if args.describe {
+        print_description(
+            &module.unique_identifier(),
+            ModuleKind::Exploration,
+            &module.decision_model_schemas,
+        );
+    } else if args.print_schema {
+        for schema in &module.decision_model_schemas {
+            println!("{}", schema);
+        }
+    } else if args.server {
+        serve_exploration_module(&module, &args);
+    } else if let Some(bid_path) = &args.bid_path_opt {
+        match serde_json::to_string(&bid(&module, bid_path)) {
+            Ok(s) => println!("{}", s),
+            Err(e) => error!(
+                "Failed to encode the bid of {}: {}",
+                module.unique_identifier(),
+                e
+            ),
         }
+    } else if let (Some(explore_path), Some(solved_path)) =
+        (&args.explore_path_opt, &args.solved_path_opt)
+    {
+        explore(
+            &module,
+            explore_path,
+            solved_path,
+            args.maximum_solutions,
+            args.total_timeout,
+            args.time_resolution,
+            args.memory_resolution,
+            |p| println!("{}", p),
+        );
     }
 }
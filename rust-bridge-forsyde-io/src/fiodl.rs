@@ -0,0 +1,766 @@
+use std::{collections::BTreeMap, fmt::Display, iter::Peekable, str::Chars};
+
+use crate::{ForSyDeDesignModel, ForSyDeEdge, ForSyDeVertex, VertexProperty};
+
+/// An error found while reading or writing a `.fiodl` file, with the (1-based) position where it happened.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FiodlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for FiodlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for FiodlError {}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Identifier(String),
+    StringLiteral(String),
+    /// a number together with its type suffix, e.g. `1_l` or `0.5_64`.
+    NumberLiteral(String, Option<String>),
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    Comma,
+    Colon,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Identifier(s) => write!(f, "identifier '{}'", s),
+            Token::StringLiteral(s) => write!(f, "string \"{}\"", s),
+            Token::NumberLiteral(n, Some(suffix)) => write!(f, "number '{}_{}'", n, suffix),
+            Token::NumberLiteral(n, None) => write!(f, "number '{}'", n),
+            Token::LeftBrace => write!(f, "'{{'"),
+            Token::RightBrace => write!(f, "'}}'"),
+            Token::LeftBracket => write!(f, "'['"),
+            Token::RightBracket => write!(f, "']'"),
+            Token::LeftParen => write!(f, "'('"),
+            Token::RightParen => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+            Token::Colon => write!(f, "':'"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Lexer<'a> {
+        Lexer {
+            chars: input.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else if c.is_some() {
+            self.column += 1;
+        }
+        c
+    }
+
+    fn error(&self, message: String) -> FiodlError {
+        FiodlError {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') => {
+                    let mut ahead = self.chars.clone();
+                    ahead.next();
+                    if ahead.peek() == Some(&'/') {
+                        while !matches!(self.chars.peek(), Some('\n') | None) {
+                            self.bump();
+                        }
+                    } else {
+                        return;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Returns the tokens of the input, each with the line and column where it starts.
+    fn tokenize(mut self) -> Result<Vec<(Token, usize, usize)>, FiodlError> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments();
+            let (line, column) = (self.line, self.column);
+            let token = match self.chars.peek().copied() {
+                None => return Ok(tokens),
+                Some('{') => self.single(Token::LeftBrace),
+                Some('}') => self.single(Token::RightBrace),
+                Some('[') => self.single(Token::LeftBracket),
+                Some(']') => self.single(Token::RightBracket),
+                Some('(') => self.single(Token::LeftParen),
+                Some(')') => self.single(Token::RightParen),
+                Some(',') => self.single(Token::Comma),
+                Some(':') => self.single(Token::Colon),
+                Some('"') => self.string()?,
+                Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                    self.number()?
+                }
+                Some(c) if c.is_alphabetic() || c == '_' => self.identifier(),
+                Some(c) => return Err(self.error(format!("unexpected character '{}'", c))),
+            };
+            tokens.push((token, line, column));
+        }
+    }
+
+    fn single(&mut self, t: Token) -> Token {
+        self.bump();
+        t
+    }
+
+    fn string(&mut self) -> Result<Token, FiodlError> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(Token::StringLiteral(s)),
+                Some('\\') => match self.bump() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some(c) => s.push(c),
+                    None => return Err(self.error("unterminated string".to_string())),
+                },
+                Some(c) => s.push(c),
+                None => return Err(self.error("unterminated string".to_string())),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Token, FiodlError> {
+        let mut n = String::new();
+        if let Some(c) = self.chars.peek().copied() {
+            if c == '-' || c == '+' {
+                n.push(c);
+                self.bump();
+            }
+        }
+        while let Some(c) = self.chars.peek().copied() {
+            if c.is_ascii_digit() || c == '.' {
+                n.push(c);
+                self.bump();
+            } else if c == 'e' || c == 'E' {
+                n.push(c);
+                self.bump();
+                if let Some(sign) = self.chars.peek().copied() {
+                    if sign == '-' || sign == '+' {
+                        n.push(sign);
+                        self.bump();
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+        if !n.chars().any(|c| c.is_ascii_digit()) {
+            return Err(self.error(format!("malformed number '{}'", n)));
+        }
+        if self.chars.peek() == Some(&'_') {
+            self.bump();
+            let mut suffix = String::new();
+            while let Some(c) = self.chars.peek().copied() {
+                if c.is_alphanumeric() {
+                    suffix.push(c);
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+            Ok(Token::NumberLiteral(n, Some(suffix)))
+        } else {
+            Ok(Token::NumberLiteral(n, None))
+        }
+    }
+
+    fn identifier(&mut self) -> Token {
+        let mut s = String::new();
+        loop {
+            match self.chars.peek().copied() {
+                Some(c) if c.is_alphanumeric() || c == '_' || c == '.' => {
+                    s.push(c);
+                    self.bump();
+                }
+                Some(':') => {
+                    // only consume the qualifying '::' and leave map colons untouched
+                    let mut ahead = self.chars.clone();
+                    ahead.next();
+                    if ahead.peek() == Some(&':') {
+                        self.bump();
+                        self.bump();
+                        s.push_str("::");
+                    } else {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+        Token::Identifier(s)
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _, _)| t)
+    }
+
+    fn error(&self, message: String) -> FiodlError {
+        let (line, column) = self
+            .tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|(_, l, c)| (*l, *c))
+            .unwrap_or((1, 1));
+        FiodlError {
+            line,
+            column,
+            message,
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, FiodlError> {
+        match self.tokens.get(self.pos) {
+            Some((t, _, _)) => {
+                self.pos += 1;
+                Ok(t.to_owned())
+            }
+            None => Err(self.error("unexpected end of input".to_string())),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), FiodlError> {
+        let t = self.next()?;
+        if t == expected {
+            Ok(())
+        } else {
+            self.pos -= 1;
+            Err(self.error(format!("expected {} but found {}", expected, t)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), FiodlError> {
+        self.expect(Token::Identifier(keyword.to_string()))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Identifier(s)) if s == keyword)
+    }
+
+    fn string(&mut self) -> Result<String, FiodlError> {
+        match self.next()? {
+            Token::StringLiteral(s) => Ok(s),
+            t => {
+                self.pos -= 1;
+                Err(self.error(format!("expected a string but found {}", t)))
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, FiodlError> {
+        match self.next()? {
+            Token::StringLiteral(s) | Token::Identifier(s) => Ok(s),
+            t => {
+                self.pos -= 1;
+                Err(self.error(format!("expected a name but found {}", t)))
+            }
+        }
+    }
+
+    /// Parses a comma-separated list of names between the given delimiters, allowing a trailing comma.
+    fn names(&mut self, open: Token, close: Token) -> Result<Vec<String>, FiodlError> {
+        self.expect(open)?;
+        let mut names = Vec::new();
+        while self.peek() != Some(&close) {
+            names.push(self.name()?);
+            if self.peek() == Some(&Token::Comma) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.expect(close)?;
+        Ok(names)
+    }
+
+    fn system_graph(&mut self) -> Result<ForSyDeDesignModel, FiodlError> {
+        self.expect_keyword("systemgraph")?;
+        self.expect(Token::LeftBrace)?;
+        let mut model = ForSyDeDesignModel::new();
+        loop {
+            if self.is_keyword("vertex") {
+                let v = self.vertex()?;
+                model.add_vertex(v);
+            } else if self.is_keyword("edge") {
+                let e = self.edge()?;
+                model.edges.push(e);
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RightBrace)?;
+        if self.pos < self.tokens.len() {
+            return Err(self.error("unexpected content after the system graph".to_string()));
+        }
+        Ok(model)
+    }
+
+    fn vertex(&mut self) -> Result<ForSyDeVertex, FiodlError> {
+        self.expect_keyword("vertex")?;
+        let identifier = self.string()?;
+        let traits = self.names(Token::LeftBracket, Token::RightBracket)?;
+        let ports = self.names(Token::LeftParen, Token::RightParen)?;
+        let properties = match self.map()? {
+            VertexProperty::Map(m) => m,
+            _ => BTreeMap::new(),
+        };
+        Ok(ForSyDeVertex {
+            identifier,
+            traits,
+            ports,
+            properties,
+        })
+    }
+
+    fn edge(&mut self) -> Result<ForSyDeEdge, FiodlError> {
+        self.expect_keyword("edge")?;
+        let traits = self.names(Token::LeftBracket, Token::RightBracket)?;
+        self.expect_keyword("from")?;
+        let source = self.string()?;
+        let source_port = if self.is_keyword("port") {
+            self.pos += 1;
+            Some(self.string()?)
+        } else {
+            None
+        };
+        self.expect_keyword("to")?;
+        let target = self.string()?;
+        let target_port = if self.is_keyword("port") {
+            self.pos += 1;
+            Some(self.string()?)
+        } else {
+            None
+        };
+        Ok(ForSyDeEdge {
+            source,
+            source_port,
+            target,
+            target_port,
+            traits,
+        })
+    }
+
+    fn map(&mut self) -> Result<VertexProperty, FiodlError> {
+        self.expect(Token::LeftBrace)?;
+        let mut m = BTreeMap::new();
+        while self.peek() != Some(&Token::RightBrace) {
+            let key = self.string()?;
+            self.expect(Token::Colon)?;
+            let value = self.value()?;
+            m.insert(key, value);
+            if self.peek() == Some(&Token::Comma) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RightBrace)?;
+        Ok(VertexProperty::Map(m))
+    }
+
+    fn array(&mut self) -> Result<VertexProperty, FiodlError> {
+        self.expect(Token::LeftBracket)?;
+        let mut values = Vec::new();
+        while self.peek() != Some(&Token::RightBracket) {
+            values.push(self.value()?);
+            if self.peek() == Some(&Token::Comma) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RightBracket)?;
+        Ok(VertexProperty::Array(values))
+    }
+
+    fn value(&mut self) -> Result<VertexProperty, FiodlError> {
+        match self.peek().cloned() {
+            Some(Token::LeftBrace) => self.map(),
+            Some(Token::LeftBracket) => self.array(),
+            Some(Token::NumberLiteral(n, suffix)) => {
+                // the number is checked before advancing so that errors point at it
+                let v = self.number(&n, suffix.as_deref())?;
+                self.pos += 1;
+                Ok(v)
+            }
+            _ => match self.next()? {
+                Token::StringLiteral(s) => Ok(VertexProperty::String(s)),
+                Token::Identifier(s) if s == "true" => Ok(VertexProperty::Boolean(true)),
+                Token::Identifier(s) if s == "false" => Ok(VertexProperty::Boolean(false)),
+                t => {
+                    self.pos -= 1;
+                    Err(self.error(format!("expected a property value but found {}", t)))
+                }
+            },
+        }
+    }
+
+    fn number(&self, n: &str, suffix: Option<&str>) -> Result<VertexProperty, FiodlError> {
+        let is_integral = !n.contains(['.', 'e', 'E']);
+        let parsed = match suffix {
+            Some("i") => n.parse().ok().map(VertexProperty::Integer),
+            Some("l") => n.parse().ok().map(VertexProperty::Long),
+            Some("32") => n.parse().ok().map(VertexProperty::Float),
+            Some("64") => n.parse().ok().map(VertexProperty::Double),
+            Some("b") => n
+                .parse::<i64>()
+                .ok()
+                .map(|b| VertexProperty::Boolean(b != 0)),
+            None if is_integral => n.parse().ok().map(VertexProperty::Long),
+            None => n.parse().ok().map(VertexProperty::Double),
+            Some(s) => {
+                return Err(self.error(format!("unknown number suffix '_{}'", s)));
+            }
+        };
+        parsed.ok_or_else(|| self.error(format!("malformed number '{}'", n)))
+    }
+}
+
+/// Parses the contents of a `.fiodl` file.
+pub fn parse_fiodl(input: &str) -> Result<ForSyDeDesignModel, FiodlError> {
+    let tokens = Lexer::new(input).tokenize()?;
+    let mut parser = Parser { tokens, pos: 0 };
+    parser.system_graph()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
+
+fn is_bare_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|c| c.is_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && s != "true"
+        && s != "false"
+}
+
+fn write_name(s: &str) -> String {
+    if is_bare_name(s) {
+        s.to_string()
+    } else {
+        format!("\"{}\"", escape(s))
+    }
+}
+
+/// Points at the end of the written output, where the next value would start.
+fn write_error(out: &str, message: String) -> FiodlError {
+    let last_line = out.rsplit('\n').next().unwrap_or("");
+    FiodlError {
+        line: out.matches('\n').count() + 1,
+        column: last_line.chars().count() + 1,
+        message,
+    }
+}
+
+fn write_value(out: &mut String, value: &VertexProperty, indent: usize) -> Result<(), FiodlError> {
+    match value {
+        VertexProperty::Integer(i) => out.push_str(&format!("{}_i", i)),
+        VertexProperty::Long(l) => out.push_str(&format!("{}_l", l)),
+        // numbers like NaN or inf have no syntax in .fiodl files and could not be read back
+        VertexProperty::Float(f) if !f.is_finite() => {
+            return Err(write_error(out, format!("non-finite number '{}'", f)));
+        }
+        VertexProperty::Double(d) if !d.is_finite() => {
+            return Err(write_error(out, format!("non-finite number '{}'", d)));
+        }
+        VertexProperty::Float(f) => out.push_str(&format!("{:?}_32", f)),
+        VertexProperty::Double(d) => out.push_str(&format!("{:?}_64", d)),
+        VertexProperty::Boolean(b) => out.push_str(if *b { "1_b" } else { "0_b" }),
+        VertexProperty::String(s) => out.push_str(&format!("\"{}\"", escape(s))),
+        VertexProperty::Array(values) => {
+            out.push('[');
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, v, indent)?;
+            }
+            out.push(']');
+        }
+        VertexProperty::Map(m) => write_map(out, m, indent)?,
+    }
+    Ok(())
+}
+
+fn write_map(
+    out: &mut String,
+    m: &BTreeMap<String, VertexProperty>,
+    indent: usize,
+) -> Result<(), FiodlError> {
+    if m.is_empty() {
+        out.push_str("{}");
+        return Ok(());
+    }
+    out.push_str("{\n");
+    for (i, (k, v)) in m.iter().enumerate() {
+        out.push_str(&" ".repeat(indent + 2));
+        out.push_str(&format!("\"{}\": ", escape(k)));
+        write_value(out, v, indent + 2)?;
+        if i + 1 < m.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push_str(&" ".repeat(indent));
+    out.push('}');
+    Ok(())
+}
+
+/// Writes a design model in the `.fiodl` syntax, so that [parse_fiodl] gives back the same model.
+///
+/// Properties with non-finite numbers cannot be read back, so they are errors at the position they
+/// would have been written at.
+pub fn write_fiodl(model: &ForSyDeDesignModel) -> Result<String, FiodlError> {
+    let mut out = String::from("systemgraph {\n");
+    for v in &model.vertexes {
+        out.push_str(&format!("  vertex \"{}\"\n", escape(&v.identifier)));
+        out.push_str(&format!(
+            "  [{}]\n",
+            v.traits
+                .iter()
+                .map(|t| t.to_owned())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+        out.push_str(&format!(
+            "  ({})\n",
+            v.ports
+                .iter()
+                .map(|p| write_name(p))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+        out.push_str("  ");
+        write_map(&mut out, &v.properties, 2)?;
+        out.push('\n');
+    }
+    for e in &model.edges {
+        out.push_str(&format!("  edge [{}]", e.traits.join(",")));
+        out.push_str(&format!(" from \"{}\"", escape(&e.source)));
+        if let Some(p) = &e.source_port {
+            out.push_str(&format!(" port \"{}\"", escape(p)));
+        }
+        out.push_str(&format!(" to \"{}\"", escape(&e.target)));
+        if let Some(p) = &e.target_port {
+            out.push_str(&format!(" port \"{}\"", escape(p)));
+        }
+        out.push('\n');
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = r#"systemgraph {
+  vertex "a"
+  [moc::sdf::SDFActor, impl::InstrumentedExecutable]
+  (combFunctions, p0, "port with spaces")
+  {
+    "production": {
+      "p0": 2_i
+    },
+    "sizeInBits": 64_l,
+    "ratio": 0.5_32,
+    "period": 1.25e-3_64,
+    "enabled": 1_b,
+    "untyped": 3,
+    "flag": false,
+    "name": "quoted \"a\"\n",
+    "requirements": [1_l, [], {}]
+  }
+  vertex "b"
+  [moc::sdf::SDFActor]
+  ()
+  {}
+  edge [moc::sdf::SDFDataEdge] from "a" port "p0" to "b"
+  edge [] from "b" to "a"
+}
+"#;
+
+    #[test]
+    fn small_models_are_read_with_their_typed_properties() {
+        let model = parse_fiodl(SMALL).expect("the small model should be read");
+        assert_eq!(model.vertexes.len(), 2);
+        let a = model.vertex("a").expect("'a' should be a vertex");
+        assert_eq!(
+            a.traits,
+            vec!["moc::sdf::SDFActor", "impl::InstrumentedExecutable"]
+        );
+        assert_eq!(a.ports, vec!["combFunctions", "p0", "port with spaces"]);
+        assert_eq!(a.property("sizeInBits"), Some(&VertexProperty::Long(64)));
+        assert_eq!(a.property("ratio"), Some(&VertexProperty::Float(0.5)));
+        assert_eq!(a.property("period"), Some(&VertexProperty::Double(1.25e-3)));
+        assert_eq!(a.property("enabled"), Some(&VertexProperty::Boolean(true)));
+        assert_eq!(a.property("untyped"), Some(&VertexProperty::Long(3)));
+        assert_eq!(a.property("flag"), Some(&VertexProperty::Boolean(false)));
+        assert_eq!(
+            a.property("name"),
+            Some(&VertexProperty::String("quoted \"a\"\n".to_string()))
+        );
+        assert_eq!(
+            a.property("production"),
+            Some(&VertexProperty::Map(BTreeMap::from([(
+                "p0".to_string(),
+                VertexProperty::Integer(2)
+            )])))
+        );
+        assert!(model.vertex("b").is_some_and(|b| b.ports.is_empty()));
+        assert_eq!(
+            model.edges,
+            vec![
+                ForSyDeEdge {
+                    source: "a".to_string(),
+                    source_port: Some("p0".to_string()),
+                    target: "b".to_string(),
+                    target_port: None,
+                    traits: vec!["moc::sdf::SDFDataEdge".to_string()],
+                },
+                ForSyDeEdge {
+                    source: "b".to_string(),
+                    source_port: None,
+                    target: "a".to_string(),
+                    target_port: None,
+                    traits: vec![],
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn written_models_are_read_back_the_same() {
+        let mut model = parse_fiodl(SMALL).expect("the small model should be read");
+        let written = write_fiodl(&model).expect("the small model should be written");
+        assert_eq!(parse_fiodl(&written), Ok(model.clone()));
+        let b_line = written
+            .lines()
+            .position(|l| l.contains("vertex \"b\""))
+            .expect("'b' should be written")
+            + 1;
+        // non-finite numbers could not be read back, so they are not written
+        for value in [
+            VertexProperty::Float(f32::NAN),
+            VertexProperty::Double(f64::INFINITY),
+            VertexProperty::Array(vec![VertexProperty::Double(f64::NEG_INFINITY)]),
+        ] {
+            model.vertexes[1]
+                .properties
+                .insert("bad".to_string(), value);
+            let e = write_fiodl(&model).expect_err("non-finite numbers should not be written");
+            assert!(e.message.starts_with("non-finite number"));
+            // "bad" is the first property of "b", after its traits, ports and opening brace
+            assert_eq!(e.line, b_line + 4);
+        }
+    }
+
+    #[test]
+    fn repeated_vertexes_are_merged() {
+        let model = parse_fiodl(
+            r#"systemgraph {
+  vertex "a" [A] (p) {"x": 1_l}
+  vertex "a" [B] (p, q) {"y": 2_l}
+}"#,
+        )
+        .expect("the model should be read");
+        assert_eq!(model.vertexes.len(), 1);
+        let a = model.vertex("a").expect("'a' should be a vertex");
+        assert_eq!(a.traits, vec!["A", "B"]);
+        assert_eq!(a.ports, vec!["p", "q"]);
+        assert_eq!(a.properties.len(), 2);
+    }
+
+    fn error_of(input: &str) -> FiodlError {
+        parse_fiodl(input).expect_err("the input should be rejected")
+    }
+
+    #[test]
+    fn unterminated_strings_are_errors() {
+        let e = error_of("systemgraph {\n  vertex \"a");
+        assert_eq!(e.message, "unterminated string");
+        assert_eq!(e.line, 2);
+    }
+
+    #[test]
+    fn unexpected_characters_are_errors_at_their_position() {
+        let e = error_of("systemgraph {\n  vertex \"a\" [A] () { \"x\": 1_l; }\n}");
+        assert_eq!(e.message, "unexpected character ';'");
+        assert_eq!(e.line, 2);
+    }
+
+    #[test]
+    fn unknown_number_suffixes_are_errors() {
+        let e = error_of("systemgraph { vertex \"a\" [] () { \"x\": 1_q } }");
+        assert_eq!(e.message, "unknown number suffix '_q'");
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_errors() {
+        let e = error_of("systemgraph { vertex \"a\" [] () { \"x\": 3000000000_i } }");
+        assert_eq!(e.message, "malformed number '3000000000'");
+    }
+
+    #[test]
+    fn unclosed_and_trailing_contents_are_errors() {
+        assert!(parse_fiodl("systemgraph { vertex \"a\" [] () {}").is_err());
+        assert!(parse_fiodl("systemgraph {} systemgraph {}").is_err());
+        assert!(parse_fiodl("").is_err());
+        assert!(parse_fiodl("graph {}").is_err());
+    }
+}
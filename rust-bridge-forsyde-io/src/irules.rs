@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use idesyde_common::{RuntimesAndProcessors, TiledMultiCore};
+use idesyde_core::{DecisionModel, DesignModel};
+
+use crate::{ForSyDeDesignModel, ForSyDeVertex};
+
+/// Merges all ForSyDe IO design models into a single system graph, or `None` if there are none.
+fn merged_forsyde_model(design_models: &Vec<Box<dyn DesignModel>>) -> Option<ForSyDeDesignModel> {
+    design_models
+        .iter()
+        .flat_map(|m| m.downcast_ref::<ForSyDeDesignModel>())
+        .fold(None, |acc, m| {
+            let mut merged = acc.unwrap_or_default();
+            merged.merge(m);
+            Some(merged)
+        })
+}
+
+fn has_connection(model: &ForSyDeDesignModel, a: &str, b: &str) -> bool {
+    model
+        .edges
+        .iter()
+        .any(|e| (e.source == a && e.target == b) || (e.source == b && e.target == a))
+}
+
+/// Finds the shortest path from `src` to `dst` that only goes through the given communication elements.
+///
+/// The path does not include `src` and `dst` themselves, so that directly connected elements have an empty path.
+fn shortest_path_through(
+    src: &str,
+    dst: &str,
+    successors: &HashMap<&str, Vec<&str>>,
+    communication_elements: &HashSet<&str>,
+) -> Option<Vec<String>> {
+    let mut previous: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([src]);
+    while let Some(v) = queue.pop_front() {
+        for n in successors.get(v).into_iter().flatten() {
+            if *n == dst {
+                let mut path = Vec::new();
+                let mut cur = v;
+                while cur != src {
+                    path.push(cur.to_string());
+                    cur = previous[cur];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if *n != src && communication_elements.contains(n) && !previous.contains_key(n) {
+                previous.insert(n, v);
+                queue.push_back(n);
+            }
+        }
+    }
+    None
+}
+
+/// Identifies a [TiledMultiCore] from the digital modules of the ForSyDe IO models.
+///
+/// Every processing module must be in a tile, i.e. connected to a memory and to a communication
+/// element that is also connected to this memory. Communication elements connected to processing
+/// modules are the network interfaces of the tiles and the remaining ones are routers. Memories and
+/// network interfaces are ordered by the processing module of their tiles.
+pub fn identify_tiled_multicore(
+    design_models: &Vec<Box<dyn DesignModel>>,
+    _decision_models: &Vec<Box<dyn DecisionModel>>,
+) -> Vec<Box<dyn DecisionModel>> {
+    let mut new_models = Vec::new();
+    if let Some(model) = merged_forsyde_model(design_models) {
+        let processing_elements: Vec<&str> = model
+            .vertexes_with_trait("GenericProcessingModule")
+            .map(|v| v.identifier.as_str())
+            .collect();
+        let memory_elements: Vec<&str> = model
+            .vertexes_with_trait("GenericMemoryModule")
+            .map(|v| v.identifier.as_str())
+            .collect();
+        let communication_elements: Vec<&str> = model
+            .vertexes_with_trait("GenericCommunicationModule")
+            .map(|v| v.identifier.as_str())
+            .collect();
+        if processing_elements.is_empty()
+            || processing_elements.len() > memory_elements.len()
+            || processing_elements.len() > communication_elements.len()
+        {
+            return new_models;
+        }
+        let is_digital = |id: &str| {
+            model
+                .vertex(id)
+                .map(|v| v.has_trait("DigitalModule"))
+                .unwrap_or(false)
+        };
+        let neighbours = |id: &str| -> Vec<String> {
+            model
+                .outgoing(id)
+                .map(|e| e.target.to_owned())
+                .chain(model.incoming(id).map(|e| e.source.to_owned()))
+                .filter(|v| is_digital(v))
+                .collect()
+        };
+        // processing modules and memories may only connect to each other and to communication elements
+        let processing_only_valid_links = processing_elements.iter().all(|pe| {
+            neighbours(pe).iter().all(|v| {
+                communication_elements.contains(&v.as_str())
+                    || memory_elements.contains(&v.as_str())
+            })
+        });
+        let memory_only_valid_links = memory_elements.iter().all(|me| {
+            neighbours(me).iter().all(|v| {
+                communication_elements.contains(&v.as_str())
+                    || processing_elements.contains(&v.as_str())
+            })
+        });
+        let tiles_exist = processing_elements.iter().all(|pe| {
+            memory_elements
+                .iter()
+                .find(|me| has_connection(&model, pe, me))
+                .map(|me| {
+                    communication_elements
+                        .iter()
+                        .any(|ce| has_connection(&model, ce, pe) && has_connection(&model, ce, me))
+                })
+                .unwrap_or(false)
+        });
+        if !processing_only_valid_links || !memory_only_valid_links || !tiles_exist {
+            return new_models;
+        }
+        let tile_index = |id: &str| {
+            processing_elements
+                .iter()
+                .position(|pe| has_connection(&model, pe, id))
+                .unwrap_or(processing_elements.len())
+        };
+        let mut memories = memory_elements.clone();
+        memories.sort_by_key(|me| tile_index(me));
+        let mut network_interfaces: Vec<&str> = communication_elements
+            .iter()
+            .filter(|ce| {
+                processing_elements
+                    .iter()
+                    .any(|pe| has_connection(&model, pe, ce))
+            })
+            .copied()
+            .collect();
+        network_interfaces.sort_by_key(|ce| tile_index(ce));
+        let routers: Vec<&str> = communication_elements
+            .iter()
+            .filter(|ce| !network_interfaces.contains(ce))
+            .copied()
+            .collect();
+        let platform_elements: HashSet<&str> = processing_elements
+            .iter()
+            .chain(memory_elements.iter())
+            .chain(communication_elements.iter())
+            .copied()
+            .collect();
+        let mut interconnect_topology_srcs = Vec::new();
+        let mut interconnect_topology_dsts = Vec::new();
+        let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+        for e in &model.edges {
+            let (src, dst) = (e.source.as_str(), e.target.as_str());
+            if platform_elements.contains(src)
+                && platform_elements.contains(dst)
+                && !successors
+                    .get(src)
+                    .map(|s| s.contains(&dst))
+                    .unwrap_or(false)
+            {
+                successors.entry(src).or_default().push(dst);
+                interconnect_topology_srcs.push(src.to_string());
+                interconnect_topology_dsts.push(dst.to_string());
+            }
+        }
+        let mut processors_provisions = HashMap::new();
+        let mut processors_frequency = HashMap::new();
+        for pe in &processing_elements {
+            let v = model.vertex(pe).unwrap();
+            let mut provisions: HashMap<String, HashMap<String, f64>> = HashMap::new();
+            if let Some(modes) = v
+                .property("modalInstructionsPerCycle")
+                .and_then(|p| p.as_map())
+            {
+                for (mode, ipc) in modes {
+                    if let Some(ops) = ipc.as_map() {
+                        provisions.insert(
+                            mode.to_owned(),
+                            ops.iter()
+                                .flat_map(|(op, x)| x.as_f64().map(|f| (op.to_owned(), f)))
+                                .collect(),
+                        );
+                    }
+                }
+            }
+            processors_provisions.insert(pe.to_string(), provisions);
+            processors_frequency.insert(
+                pe.to_string(),
+                v.property("operatingFrequencyInHertz")
+                    .and_then(|p| p.as_i64())
+                    .unwrap_or(0) as u64,
+            );
+        }
+        let tile_memory_sizes = memories
+            .iter()
+            .map(|me| {
+                (
+                    me.to_string(),
+                    model
+                        .vertex(me)
+                        .and_then(|v| v.property("spaceInBits"))
+                        .and_then(|p| p.as_i64())
+                        .unwrap_or(0) as u64,
+                )
+            })
+            .collect();
+        let mut communication_elements_max_channels = HashMap::new();
+        let mut communication_elements_bit_per_sec_per_channel = HashMap::new();
+        for ce in &communication_elements {
+            let v = model.vertex(ce).unwrap();
+            let instrumented = v.has_trait("InstrumentedCommunicationModule");
+            let prop = |name: &str| v.property(name).and_then(|p| p.as_f64());
+            communication_elements_max_channels.insert(
+                ce.to_string(),
+                prop("maxConcurrentFlits")
+                    .filter(|_| instrumented)
+                    .map(|f| f as u32)
+                    .unwrap_or(1),
+            );
+            // every channel transmits one flit every max cycles per flit
+            let bit_per_sec = match (
+                prop("flitSizeInBits"),
+                prop("maxCyclesPerFlit"),
+                prop("operatingFrequencyInHertz"),
+            ) {
+                (Some(flit), Some(cycles), Some(freq)) if instrumented && cycles > 0.0 => {
+                    flit * freq / cycles
+                }
+                _ => 0.0,
+            };
+            communication_elements_bit_per_sec_per_channel.insert(ce.to_string(), bit_per_sec);
+        }
+        let comm_set: HashSet<&str> = communication_elements.iter().copied().collect();
+        let mut pre_computed_paths: HashMap<String, HashMap<String, Vec<String>>> = HashMap::new();
+        for src in processing_elements.iter().chain(memories.iter()) {
+            for dst in processing_elements.iter().chain(memories.iter()) {
+                if src != dst {
+                    if let Some(path) = shortest_path_through(src, dst, &successors, &comm_set) {
+                        pre_computed_paths
+                            .entry(src.to_string())
+                            .or_default()
+                            .insert(dst.to_string(), path);
+                    }
+                }
+            }
+        }
+        new_models.push(Box::new(TiledMultiCore {
+            processors: processing_elements.iter().map(|x| x.to_string()).collect(),
+            memories: memories.iter().map(|x| x.to_string()).collect(),
+            network_interfaces: network_interfaces.iter().map(|x| x.to_string()).collect(),
+            routers: routers.iter().map(|x| x.to_string()).collect(),
+            interconnect_topology_srcs,
+            interconnect_topology_dsts,
+            processors_provisions,
+            processors_frequency,
+            tile_memory_sizes,
+            communication_elements_max_channels,
+            communication_elements_bit_per_sec_per_channel,
+            pre_computed_paths,
+        }) as Box<dyn DecisionModel>);
+    }
+    new_models
+}
+
+/// Identifies [RuntimesAndProcessors] from the schedulers and processing modules of the ForSyDe IO models.
+///
+/// Every processing module must have a scheduler connected to it, which becomes its runtime. Schedulers
+/// connected to other elements, e.g. communication slot schedulers, are not part of the decision model.
+/// A scheduler connected to more than one processing module cannot be the runtime of any of them, so
+/// nothing is identified if a processing module only has such shared schedulers.
+pub fn identify_runtimes_and_processors(
+    design_models: &Vec<Box<dyn DesignModel>>,
+    _decision_models: &Vec<Box<dyn DecisionModel>>,
+) -> Vec<Box<dyn DecisionModel>> {
+    let mut new_models = Vec::new();
+    if let Some(model) = merged_forsyde_model(design_models) {
+        let processors: Vec<String> = model
+            .vertexes_with_trait("GenericProcessingModule")
+            .map(|v| v.identifier.to_owned())
+            .collect();
+        let allocated: Vec<Option<&ForSyDeVertex>> = processors
+            .iter()
+            .map(|pe| {
+                model
+                    .vertexes_with_trait("AbstractScheduler")
+                    .filter(|s| has_connection(&model, &s.identifier, pe))
+                    .find(|s| {
+                        processors
+                            .iter()
+                            .filter(|other| has_connection(&model, &s.identifier, other))
+                            .count()
+                            == 1
+                    })
+            })
+            .collect();
+        if processors.is_empty() || allocated.iter().any(|s| s.is_none()) {
+            return new_models;
+        }
+        let runtimes: Vec<&ForSyDeVertex> = allocated.into_iter().flatten().collect();
+        let is_fixed_priority: Vec<bool> = runtimes
+            .iter()
+            .map(|s| s.has_trait("FixedPriorityScheduler"))
+            .collect();
+        let is_cyclic_executive: Vec<bool> = runtimes
+            .iter()
+            .map(|s| s.has_trait("StaticCyclicScheduler"))
+            .collect();
+        let is_earliest_deadline_first: Vec<bool> = runtimes
+            .iter()
+            .map(|s| s.has_trait("EarliestDeadlineFirstScheduler"))
+            .collect();
+        let is_bare_metal = (0..runtimes.len())
+            .map(|i| {
+                !is_fixed_priority[i] && !is_cyclic_executive[i] && !is_earliest_deadline_first[i]
+            })
+            .collect();
+        new_models.push(Box::new(RuntimesAndProcessors {
+            runtimes: runtimes.iter().map(|s| s.identifier.to_owned()).collect(),
+            processors: processors.to_owned(),
+            runtime_host: runtimes
+                .iter()
+                .zip(processors.iter())
+                .map(|(s, pe)| (s.identifier.to_owned(), pe.to_owned()))
+                .collect(),
+            processor_affinities: processors
+                .iter()
+                .zip(runtimes.iter())
+                .map(|(pe, s)| (pe.to_owned(), s.identifier.to_owned()))
+                .collect(),
+            is_bare_metal,
+            is_fixed_priority,
+            is_earliest_deadline_first,
+            is_cyclic_executive,
+        }) as Box<dyn DecisionModel>);
+    }
+    new_models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ForSyDeEdge;
+
+    const TWO_TILES: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../examples_and_benchmarks/small_and_explainable/yuhan_zhang_thesis/bus_small_platform.fiodl"
+    ));
+
+    const EIGHT_TILES: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../examples_and_benchmarks/small_and_explainable/sobel_to_bus_multicore/bus_small_platform.fiodl"
+    ));
+
+    fn design_models(model: ForSyDeDesignModel) -> Vec<Box<dyn DesignModel>> {
+        vec![Box::new(model) as Box<dyn DesignModel>]
+    }
+
+    #[test]
+    fn tiles_are_identified_from_the_bus_platform() {
+        let model = ForSyDeDesignModel::from_fiodl(TWO_TILES).unwrap();
+        let identified = identify_tiled_multicore(&design_models(model), &vec![]);
+        assert_eq!(identified.len(), 1);
+        let tiled = identified[0].downcast_ref::<TiledMultiCore>().unwrap();
+        assert_eq!(tiled.processors, vec!["micro_blaze_0", "micro_blaze_1"]);
+        assert_eq!(tiled.memories, vec!["micro_blaze_mem0", "micro_blaze_mem1"]);
+        assert_eq!(
+            tiled.network_interfaces,
+            vec!["micro_blaze_ni0", "micro_blaze_ni1"]
+        );
+        assert_eq!(tiled.routers, vec!["TDMBus"]);
+        let paths = &tiled.pre_computed_paths["micro_blaze_0"];
+        assert!(paths["micro_blaze_mem0"].is_empty());
+        assert_eq!(
+            paths["micro_blaze_mem1"],
+            vec!["micro_blaze_ni0", "TDMBus", "micro_blaze_ni1"]
+        );
+        assert_eq!(
+            tiled.pre_computed_paths["micro_blaze_1"]["micro_blaze_0"],
+            vec!["micro_blaze_ni1", "TDMBus", "micro_blaze_ni0"]
+        );
+        assert_eq!(tiled.processors_frequency["micro_blaze_0"], 1);
+        assert_eq!(
+            tiled.processors_provisions["micro_blaze_0"]["eco"]["p_1"],
+            1.0
+        );
+    }
+
+    #[test]
+    fn processor_schedulers_become_their_runtimes() {
+        let model = ForSyDeDesignModel::from_fiodl(TWO_TILES).unwrap();
+        let identified = identify_runtimes_and_processors(&design_models(model), &vec![]);
+        assert_eq!(identified.len(), 1);
+        let runtimes = identified[0]
+            .downcast_ref::<RuntimesAndProcessors>()
+            .unwrap();
+        assert_eq!(
+            runtimes.runtimes,
+            vec!["micro_blaze_os0", "micro_blaze_os1"]
+        );
+        assert_eq!(
+            runtimes.processor_affinities["micro_blaze_1"],
+            "micro_blaze_os1"
+        );
+        assert_eq!(runtimes.is_fixed_priority, vec![true, true]);
+        assert_eq!(runtimes.is_cyclic_executive, vec![false, false]);
+        assert_eq!(runtimes.is_earliest_deadline_first, vec![false, false]);
+        assert_eq!(runtimes.is_bare_metal, vec![false, false]);
+
+        let model = ForSyDeDesignModel::from_fiodl(EIGHT_TILES).unwrap();
+        let identified = identify_runtimes_and_processors(&design_models(model), &vec![]);
+        let runtimes = identified[0]
+            .downcast_ref::<RuntimesAndProcessors>()
+            .unwrap();
+        assert_eq!(runtimes.processors.len(), 8);
+        assert!(runtimes
+            .runtimes
+            .iter()
+            .all(|s| s.starts_with("micro_blaze_os")));
+        assert!(runtimes.is_cyclic_executive.iter().all(|x| *x));
+        assert!(!runtimes.is_fixed_priority.iter().any(|x| *x));
+    }
+
+    #[test]
+    fn schedulers_shared_by_processors_are_not_runtimes() {
+        let mut model = ForSyDeDesignModel::from_fiodl(TWO_TILES).unwrap();
+        model.edges.push(ForSyDeEdge {
+            source: "micro_blaze_os0".to_string(),
+            source_port: Some("allocationHosts".to_string()),
+            target: "micro_blaze_1".to_string(),
+            target_port: None,
+            traits: vec!["decision::AbstractAllocation".to_string()],
+        });
+        let identified = identify_runtimes_and_processors(&design_models(model), &vec![]);
+        assert!(identified.is_empty());
+    }
+}
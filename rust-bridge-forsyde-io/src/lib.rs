@@ -0,0 +1,322 @@
+pub mod fiodl;
+pub mod irules;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use idesyde_common::{RuntimesAndProcessors, TiledMultiCore};
+use idesyde_core::{
+    headers::{DecisionModelHeader, DesignModelHeader, LabelledArcWithPorts},
+    load_decision_model, DecisionModel, DesignModel, MarkedIdentificationRule,
+    StandaloneIdentificationModule,
+};
+use irules::{identify_runtimes_and_processors, identify_tiled_multicore};
+use log::warn;
+use schemars::schema_for;
+
+/// A typed property value of a ForSyDe IO vertex, as given by the literal suffixes of the `.fiodl` syntax,
+/// e.g. `1_i`, `1_l`, `1.0_32`, `1.0_64` and `1_b`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum VertexProperty {
+    Integer(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Boolean(bool),
+    String(String),
+    Array(Vec<VertexProperty>),
+    Map(BTreeMap<String, VertexProperty>),
+}
+
+impl VertexProperty {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            VertexProperty::Integer(i) => Some(*i as f64),
+            VertexProperty::Long(l) => Some(*l as f64),
+            VertexProperty::Float(f) => Some(*f as f64),
+            VertexProperty::Double(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            VertexProperty::Integer(i) => Some(*i as i64),
+            VertexProperty::Long(l) => Some(*l),
+            VertexProperty::Boolean(b) => Some(*b as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            VertexProperty::Boolean(b) => Some(*b),
+            VertexProperty::Integer(i) => Some(*i != 0),
+            VertexProperty::Long(l) => Some(*l != 0),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VertexProperty::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&BTreeMap<String, VertexProperty>> {
+        match self {
+            VertexProperty::Map(m) => Some(m),
+            _ => None,
+        }
+    }
+}
+
+/// The refinements between the ForSyDe IO traits used in identification, given by the last segment of their names.
+///
+/// A vertex with a trait on the left also has, implicitly, the trait on the right.
+const TRAIT_REFINEMENTS: &[(&str, &str)] = &[
+    ("GenericProcessingModule", "DigitalModule"),
+    ("InstrumentedProcessingModule", "GenericProcessingModule"),
+    ("GenericMemoryModule", "DigitalModule"),
+    ("GenericCommunicationModule", "DigitalModule"),
+    (
+        "InstrumentedCommunicationModule",
+        "GenericCommunicationModule",
+    ),
+    (
+        "RoundRobinCommunicationModule",
+        "GenericCommunicationModule",
+    ),
+    ("FixedPriorityScheduler", "AbstractScheduler"),
+    ("StaticCyclicScheduler", "AbstractScheduler"),
+    ("RoundRobinScheduler", "AbstractScheduler"),
+    ("EarliestDeadlineFirstScheduler", "AbstractScheduler"),
+];
+
+fn trait_refines(t: &str, name: &str) -> bool {
+    let short = t.rsplit("::").next().unwrap_or(t);
+    t == name
+        || short == name
+        || TRAIT_REFINEMENTS
+            .iter()
+            .any(|(sub, sup)| *sub == short && trait_refines(sup, name))
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForSyDeVertex {
+    pub identifier: String,
+    pub traits: Vec<String>,
+    pub ports: Vec<String>,
+    pub properties: BTreeMap<String, VertexProperty>,
+}
+
+impl ForSyDeVertex {
+    /// Checks if the vertex has the trait, either fully qualified (`platform::GenericMemoryModule`)
+    /// or by its last segment (`GenericMemoryModule`). Traits refined by the ones the vertex has also count.
+    pub fn has_trait(&self, name: &str) -> bool {
+        self.traits.iter().any(|t| trait_refines(t, name))
+    }
+
+    pub fn property(&self, name: &str) -> Option<&VertexProperty> {
+        self.properties.get(name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ForSyDeEdge {
+    pub source: String,
+    pub source_port: Option<String>,
+    pub target: String,
+    pub target_port: Option<String>,
+    pub traits: Vec<String>,
+}
+
+impl ForSyDeEdge {
+    pub fn has_trait(&self, name: &str) -> bool {
+        self.traits
+            .iter()
+            .any(|t| t == name || t.rsplit("::").next() == Some(name))
+    }
+}
+
+/// Design model for ForSyDe IO system graphs, as read from `.fiodl` files.
+///
+/// The system graph is kept as given, with vertexes in the order they were added. Vertexes added with an already
+/// existing identifier are merged into the existing one, which is also what happens when many models are merged together.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ForSyDeDesignModel {
+    pub vertexes: Vec<ForSyDeVertex>,
+    pub edges: Vec<ForSyDeEdge>,
+    vertex_index: HashMap<String, usize>,
+}
+
+impl ForSyDeDesignModel {
+    pub fn new() -> ForSyDeDesignModel {
+        ForSyDeDesignModel::default()
+    }
+
+    pub fn add_vertex(&mut self, v: ForSyDeVertex) {
+        if let Some(i) = self.vertex_index.get(&v.identifier) {
+            let existing = &mut self.vertexes[*i];
+            for t in v.traits {
+                if !existing.traits.contains(&t) {
+                    existing.traits.push(t);
+                }
+            }
+            for p in v.ports {
+                if !existing.ports.contains(&p) {
+                    existing.ports.push(p);
+                }
+            }
+            existing.properties.extend(v.properties);
+        } else {
+            self.vertex_index
+                .insert(v.identifier.to_owned(), self.vertexes.len());
+            self.vertexes.push(v);
+        }
+    }
+
+    pub fn merge(&mut self, other: &ForSyDeDesignModel) {
+        for v in &other.vertexes {
+            self.add_vertex(v.to_owned());
+        }
+        for e in &other.edges {
+            if !self.edges.contains(e) {
+                self.edges.push(e.to_owned());
+            }
+        }
+    }
+
+    pub fn vertex(&self, identifier: &str) -> Option<&ForSyDeVertex> {
+        self.vertex_index
+            .get(identifier)
+            .map(|i| &self.vertexes[*i])
+    }
+
+    pub fn vertexes_with_trait<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a ForSyDeVertex> + 'a {
+        self.vertexes.iter().filter(move |v| v.has_trait(name))
+    }
+
+    pub fn outgoing<'a>(&'a self, identifier: &'a str) -> impl Iterator<Item = &'a ForSyDeEdge> {
+        self.edges.iter().filter(move |e| e.source == identifier)
+    }
+
+    pub fn incoming<'a>(&'a self, identifier: &'a str) -> impl Iterator<Item = &'a ForSyDeEdge> {
+        self.edges.iter().filter(move |e| e.target == identifier)
+    }
+
+    pub fn from_fiodl(contents: &str) -> Result<ForSyDeDesignModel, fiodl::FiodlError> {
+        fiodl::parse_fiodl(contents)
+    }
+
+    pub fn to_fiodl(&self) -> Result<String, fiodl::FiodlError> {
+        fiodl::write_fiodl(self)
+    }
+}
+
+impl DesignModel for ForSyDeDesignModel {
+    fn unique_identifier(&self) -> String {
+        "ForSyDeDesignModel".to_string()
+    }
+
+    fn header(&self) -> DesignModelHeader {
+        let mut elems: HashSet<String> = HashSet::new();
+        let mut rels: HashSet<LabelledArcWithPorts> = HashSet::new();
+        elems.extend(self.vertexes.iter().map(|v| v.identifier.to_owned()));
+        for e in &self.edges {
+            rels.insert(LabelledArcWithPorts::new(
+                e.traits.join(","),
+                &e.source,
+                e.source_port.as_deref().unwrap_or(""),
+                &e.target,
+                e.target_port.as_deref().unwrap_or(""),
+            ));
+        }
+        DesignModelHeader {
+            category: self.unique_identifier(),
+            model_paths: Vec::new(),
+            elements: elems.into_iter().collect(),
+            relations: rels.into_iter().collect(),
+        }
+    }
+}
+
+fn read_design_model(path: &std::path::Path) -> Option<Box<dyn DesignModel>> {
+    let is_fiodl = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("fiodl"))
+        .unwrap_or(false);
+    if is_fiodl {
+        if let Ok(s) = std::fs::read_to_string(path) {
+            return ForSyDeDesignModel::from_fiodl(&s)
+                .map_err(|e| warn!("Could not read {}: {}", path.display(), e))
+                .ok()
+                .map(|m| Box::new(m) as Box<dyn DesignModel>);
+        }
+    }
+    None
+}
+
+fn write_design_model(
+    design_model: &dyn DesignModel,
+    dest: &std::path::Path,
+) -> Option<std::path::PathBuf> {
+    if let Some(m) = design_model.downcast_ref::<ForSyDeDesignModel>() {
+        let contents = m
+            .to_fiodl()
+            .map_err(|e| warn!("Could not write {}: {}", dest.display(), e))
+            .ok()?;
+        if dest.is_dir() {
+            let mut target_idx = 0;
+            let mut target = dest.join(format!("reversed_{}_ForSyDeIOModule.fiodl", target_idx));
+            while target.exists() {
+                target_idx += 1;
+                target = dest.join(format!("reversed_{}_ForSyDeIOModule.fiodl", target_idx));
+            }
+            return std::fs::write(&target, contents).ok().map(|_| target);
+        } else if dest
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("fiodl"))
+            .unwrap_or(false)
+        {
+            return std::fs::write(dest, contents)
+                .ok()
+                .map(|_| dest.to_path_buf());
+        }
+    }
+    None
+}
+
+fn decision_header_to_model(header: &DecisionModelHeader) -> Option<Box<dyn DecisionModel>> {
+    match header.category.as_str() {
+        "TiledMultiCore" => load_decision_model::<TiledMultiCore>(header)
+            .map(|m| Box::new(m) as Box<dyn DecisionModel>),
+        "RuntimesAndProcessors" => load_decision_model::<RuntimesAndProcessors>(header)
+            .map(|m| Box::new(m) as Box<dyn DecisionModel>),
+        _ => None,
+    }
+}
+
+/// The identification module of ForSyDe IO models, to be run standalone or linked into the orchestrator.
+pub fn forsyde_io_identification_module() -> StandaloneIdentificationModule {
+    StandaloneIdentificationModule::new(
+        "ForSyDeIOModule",
+        vec![
+            MarkedIdentificationRule::DesignModelOnlyIdentificationRule(identify_tiled_multicore),
+            MarkedIdentificationRule::DesignModelOnlyIdentificationRule(
+                identify_runtimes_and_processors,
+            ),
+        ],
+        vec![],
+        read_design_model,
+        write_design_model,
+        decision_header_to_model,
+        HashSet::from([
+            serde_json::to_string_pretty(&schema_for!(TiledMultiCore)).unwrap(),
+            serde_json::to_string_pretty(&schema_for!(RuntimesAndProcessors)).unwrap(),
+        ]),
+    )
+}
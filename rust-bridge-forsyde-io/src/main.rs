@@ -0,0 +1,6 @@
+use idesyde_blueprints::execute_standalone_identification_module;
+use idesyde_bridge_forsyde_io::forsyde_io_identification_module;
+
+fn main() {
+    execute_standalone_identification_module(forsyde_io_identification_module());
+}
@@ -1,13 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
 use idesyde_blueprints::execute_standalone_identification_module;
+use idesyde_common::CommunicatingAndTriggeredReactiveWorkload;
 use idesyde_core::{
-    headers::{self, DesignModelHeader},
-    DecisionModel, DesignModel, MarkedIdentificationRule, StandaloneIdentificationModule,
+    headers::{self, DecisionModelHeader, DesignModelHeader, LabelledArcWithPorts},
+    load_decision_model, DecisionModel, DesignModel, MarkedIdentificationRule,
+    StandaloneIdentificationModule,
 };
+use log::warn;
+use schemars::schema_for;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Design model for the discrete subset of Simulink models exported by `export_to_idesyde.m`.
+///
+/// The `mapped_*` vectors are empty for exported models and are only filled by reverse identification,
+/// where each mapped block records the processor it runs on, its priority (lower runs first, as in
+/// Simulink) and the memory it is stored in. They are parallel vectors, like the links, so that
+/// `import_from_idesyde.m` can read them back without relying on block names as keys.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct SimulinkReactiveDesignModel {
     pub processes: HashSet<String>,
     pub processes_sizes: HashMap<String, u32>,
@@ -29,6 +39,14 @@ pub struct SimulinkReactiveDesignModel {
     pub links_src_port: Vec<String>,
     pub links_dst_port: Vec<String>,
     pub links_size: Vec<u32>,
+    #[serde(default)]
+    pub mapped_blocks: Vec<String>,
+    #[serde(default)]
+    pub mapped_processors: Vec<String>,
+    #[serde(default)]
+    pub mapped_priorities: Vec<u32>,
+    #[serde(default)]
+    pub mapped_memories: Vec<String>,
 }
 
 impl DesignModel for SimulinkReactiveDesignModel {
@@ -38,43 +56,321 @@ impl DesignModel for SimulinkReactiveDesignModel {
 
     fn header(&self) -> headers::DesignModelHeader {
         let mut elems: HashSet<String> = HashSet::new();
+        let mut rels: HashSet<LabelledArcWithPorts> = HashSet::new();
         elems.extend(self.processes.iter().map(|x| x.to_owned()));
         elems.extend(self.delays.iter().map(|x| x.to_owned()));
         elems.extend(self.sources.iter().map(|x| x.to_owned()));
         elems.extend(self.constants.iter().map(|x| x.to_owned()));
         elems.extend(self.sinks.iter().map(|x| x.to_owned()));
         for i in 0..self.links_src.len() {
-            elems.insert(format!(
-                "{}={}:{}-{}:{}",
+            rels.insert(LabelledArcWithPorts::new(
                 self.links_size[i],
-                self.links_src[i],
-                self.links_src_port[i],
-                self.links_dst[i],
-                self.links_dst_port[i]
+                &self.links_src[i],
+                &self.links_src_port[i],
+                &self.links_dst[i],
+                &self.links_dst_port[i],
+            ));
+        }
+        for i in 0..self.mapped_blocks.len() {
+            rels.insert(LabelledArcWithPorts::new(
+                "processor",
+                &self.mapped_blocks[i],
+                "",
+                &self.mapped_processors[i],
+                "",
+            ));
+            rels.insert(LabelledArcWithPorts::new(
+                "memory",
+                &self.mapped_blocks[i],
+                "",
+                &self.mapped_memories[i],
+                "",
             ));
         }
         DesignModelHeader {
             category: self.unique_identifier(),
             model_paths: Vec::new(),
             elements: elems.into_iter().collect(),
+            relations: rels.into_iter().collect(),
         }
     }
 }
 
+/// Identifies a [CommunicatingAndTriggeredReactiveWorkload] from the Simulink models.
+///
+/// Processes and delays become tasks, while the links between them, the sources and the sinks
+/// become data channels. Constants are not tasks, so all links from or to constants are dropped.
+/// Links between the same pair of blocks are grouped into one data edge whose message size is
+/// the sum of the links, and each of these edges also triggers the destination.
 fn partially_identify_wokload_model(
     design_models: &Vec<Box<dyn DesignModel>>,
     _decision_models: &Vec<Box<dyn DecisionModel>>,
 ) -> Vec<Box<dyn DecisionModel>> {
-    let mut _identified = Vec::new();
-    // let mut procs: HashSet<String> = HashSet::new();
-    // let mut delays: HashSet<String> = HashSet::new();
-    // let mut linksWithoutConstants: HashMap<String, HashMap<String, u32>> = HashMap::new();
-    // let mut allLinks: HashMap<String, HashMap<String, u32>> = HashMap::new();
-    for design_model in design_models {
-        if design_model.unique_identifier() == "SimulinkReactiveDesignModel" {}
-    }
-    _identified
+    let mut identified = Vec::new();
+    let mut workload = CommunicatingAndTriggeredReactiveWorkload {
+        tasks: Vec::new(),
+        task_sizes: Vec::new(),
+        task_computational_needs: Vec::new(),
+        data_channels: Vec::new(),
+        data_channel_sizes: Vec::new(),
+        data_graph_src: Vec::new(),
+        data_graph_dst: Vec::new(),
+        data_graph_message_size: Vec::new(),
+        periodic_sources: Vec::new(),
+        periods_numerator: Vec::new(),
+        periods_denominator: Vec::new(),
+        offsets_numerator: Vec::new(),
+        offsets_denominator: Vec::new(),
+        upsamples: Vec::new(),
+        upsample_repetitive_holds: Vec::new(),
+        upsample_initial_holds: Vec::new(),
+        downsamples: Vec::new(),
+        downample_repetitive_skips: Vec::new(),
+        downample_initial_skips: Vec::new(),
+        trigger_graph_src: Vec::new(),
+        trigger_graph_dst: Vec::new(),
+        has_or_trigger_semantics: HashSet::new(),
+    };
+    let mut found = false;
+    for m in design_models
+        .iter()
+        .flat_map(|x| x.downcast_ref::<SimulinkReactiveDesignModel>())
+    {
+        found = true;
+        let mut procs: Vec<&String> = m.processes.iter().collect();
+        procs.sort();
+        let mut delays: Vec<&String> = m.delays.iter().collect();
+        delays.sort();
+        let mut sources: Vec<&String> = m.sources.iter().collect();
+        sources.sort();
+        let mut sinks: Vec<&String> = m.sinks.iter().collect();
+        sinks.sort();
+        for p in &procs {
+            workload.tasks.push(p.to_string());
+            workload
+                .task_sizes
+                .push(m.processes_sizes.get(*p).copied().unwrap_or(0));
+            workload
+                .task_computational_needs
+                .push(m.processes_operations.get(*p).cloned().unwrap_or_default());
+        }
+        for d in &delays {
+            workload.tasks.push(d.to_string());
+            workload
+                .task_sizes
+                .push(m.delays_sizes.get(*d).copied().unwrap_or(0));
+            workload
+                .task_computational_needs
+                .push(m.delays_operations.get(*d).cloned().unwrap_or_default());
+        }
+        // the links are grouped by their source and destination blocks, keeping their first appearance order
+        let mut grouped: Vec<(&String, &String, u32)> = Vec::new();
+        for i in 0..m.links_src.len() {
+            let (src, dst) = (&m.links_src[i], &m.links_dst[i]);
+            if m.constants.contains(src) || m.constants.contains(dst) {
+                continue;
+            }
+            workload.data_channels.push(format!(
+                "{}:{}--{}:{}",
+                src, m.links_src_port[i], dst, m.links_dst_port[i]
+            ));
+            workload.data_channel_sizes.push(m.links_size[i]);
+            match grouped.iter_mut().find(|(s, t, _)| *s == src && *t == dst) {
+                Some((_, _, size)) => *size += m.links_size[i],
+                None => grouped.push((src, dst, m.links_size[i])),
+            }
+        }
+        for s in &sources {
+            workload.data_channels.push(s.to_string());
+            workload
+                .data_channel_sizes
+                .push(m.sources_sizes.get(*s).copied().unwrap_or(0));
+        }
+        for s in &sinks {
+            workload.data_channels.push(s.to_string());
+            workload
+                .data_channel_sizes
+                .push(m.sinks_sizes.get(*s).copied().unwrap_or(0));
+        }
+        for (src, dst, size) in grouped {
+            workload.data_graph_src.push(src.to_owned());
+            workload.data_graph_dst.push(dst.to_owned());
+            workload.data_graph_message_size.push(size);
+            workload.trigger_graph_src.push(src.to_owned());
+            workload.trigger_graph_dst.push(dst.to_owned());
+        }
+        for s in &sources {
+            workload.periodic_sources.push(s.to_string());
+            workload
+                .periods_numerator
+                .push(m.sources_periods_numen.get(*s).copied().unwrap_or(0) as u32);
+            workload
+                .periods_denominator
+                .push(m.sources_periods_denom.get(*s).copied().unwrap_or(1) as u32);
+            workload.offsets_numerator.push(0);
+            workload.offsets_denominator.push(1);
+        }
+    }
+    if found {
+        identified.push(Box::new(workload) as Box<dyn DecisionModel>);
+    }
+    identified
+}
+
+/// The parts of an explored `PeriodicWorkloadToPartitionedSharedMultiCore` that are needed to
+/// annotate the Simulink models back.
+///
+/// The body is written by the exploration modules with their own field names, so only the task
+/// mappings and the scheduler hosts are read and everything else is ignored.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+struct PeriodicWorkloadToPartitionedSharedMultiCore {
+    platform: PartitionedSharedMemoryMultiCoreView,
+    #[serde(rename = "processMappings")]
+    process_mappings: Vec<(String, String)>,
+    #[serde(rename = "processSchedulings")]
+    process_schedulings: Vec<(String, String)>,
+    #[serde(skip)]
+    header: Option<DecisionModelHeader>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+struct PartitionedSharedMemoryMultiCoreView {
+    runtimes: PartitionedCoresWithRuntimesView,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+struct PartitionedCoresWithRuntimesView {
+    processors: Vec<String>,
+    schedulers: Vec<String>,
+}
+
+impl DecisionModel for PeriodicWorkloadToPartitionedSharedMultiCore {
+    fn unique_identifier(&self) -> String {
+        "PeriodicWorkloadToPartitionedSharedMultiCore".to_string()
+    }
+
+    fn header(&self) -> DecisionModelHeader {
+        self.header
+            .to_owned()
+            .unwrap_or_else(|| DecisionModelHeader {
+                category: self.unique_identifier(),
+                body_path: None,
+                body_paths: HashMap::new(),
+                covered_elements: BTreeSet::new(),
+                objectives: HashMap::new(),
+                covered_relations: BTreeSet::new(),
+            })
+    }
+}
+
+/// Ranks the blocks by their dependencies in the Simulink model, so that every block comes after
+/// the blocks it reads from. Blocks in feedback loops are ranked after the rest, in name order.
+fn dependency_ranks(m: &SimulinkReactiveDesignModel) -> HashMap<String, u32> {
+    let mut blocks: Vec<&String> = m.processes.iter().chain(m.delays.iter()).collect();
+    blocks.sort();
+    let mut in_degree: HashMap<&String, u32> = blocks.iter().map(|b| (*b, 0)).collect();
+    for (src, dst) in m.links_src.iter().zip(m.links_dst.iter()) {
+        // delays break the dependency, as they output their previous input
+        if in_degree.contains_key(src) && !m.delays.contains(src) {
+            if let Some(d) = in_degree.get_mut(dst) {
+                *d += 1;
+            }
+        }
+    }
+    let mut ranks = HashMap::new();
+    let mut ready: VecDeque<&String> = blocks
+        .iter()
+        .filter(|b| in_degree[*b] == 0)
+        .copied()
+        .collect();
+    while let Some(b) = ready.pop_front() {
+        ranks.insert(b.to_owned(), ranks.len() as u32);
+        if m.delays.contains(b) {
+            continue;
+        }
+        for (src, dst) in m.links_src.iter().zip(m.links_dst.iter()) {
+            if src == b {
+                if let Some(d) = in_degree.get_mut(dst) {
+                    *d -= 1;
+                    if *d == 0 {
+                        ready.push_back(dst);
+                    }
+                }
+            }
+        }
+    }
+    for b in blocks {
+        if !ranks.contains_key(b) {
+            ranks.insert(b.to_owned(), ranks.len() as u32);
+        }
+    }
+    ranks
+}
+
+/// Annotates the Simulink models with the mappings and schedulings found during exploration.
+///
+/// Blocks scheduled by the same runtime get priorities following their dependencies, starting from 0.
+fn reverse_identify_simulink_mappings(
+    solved_decision_models: &Vec<Box<dyn DecisionModel>>,
+    design_models: &Vec<Box<dyn DesignModel>>,
+) -> Vec<Box<dyn DesignModel>> {
+    let mut reversed = Vec::new();
+    for solution in solved_decision_models
+        .iter()
+        .flat_map(|x| x.downcast_ref::<PeriodicWorkloadToPartitionedSharedMultiCore>())
+    {
+        let runtimes = &solution.platform.runtimes;
+        let memory_of: HashMap<&String, &String> = solution
+            .process_mappings
+            .iter()
+            .map(|(t, mem)| (t, mem))
+            .collect();
+        for m in design_models
+            .iter()
+            .flat_map(|x| x.downcast_ref::<SimulinkReactiveDesignModel>())
+        {
+            let ranks = dependency_ranks(m);
+            let mut scheduled: Vec<(&String, &String)> = solution
+                .process_schedulings
+                .iter()
+                .filter(|(t, _)| ranks.contains_key(t))
+                .map(|(t, sched)| (t, sched))
+                .collect();
+            if scheduled.is_empty() {
+                continue;
+            }
+            scheduled.sort_by_key(|(t, sched)| (sched.to_owned(), ranks[*t]));
+            let mut annotated = m.to_owned();
+            annotated.mapped_blocks.clear();
+            annotated.mapped_processors.clear();
+            annotated.mapped_priorities.clear();
+            annotated.mapped_memories.clear();
+            let mut priority = 0;
+            for (i, (t, sched)) in scheduled.iter().enumerate() {
+                if i > 0 && scheduled[i - 1].1 != *sched {
+                    priority = 0;
+                }
+                let processor = runtimes
+                    .schedulers
+                    .iter()
+                    .position(|s| s == *sched)
+                    .and_then(|idx| runtimes.processors.get(idx))
+                    .cloned()
+                    .unwrap_or_else(|| sched.to_string());
+                annotated.mapped_blocks.push(t.to_string());
+                annotated.mapped_processors.push(processor);
+                annotated.mapped_priorities.push(priority);
+                annotated
+                    .mapped_memories
+                    .push(memory_of.get(t).map(|x| x.to_string()).unwrap_or_default());
+                priority += 1;
+            }
+            reversed.push(Box::new(annotated) as Box<dyn DesignModel>);
+        }
+    }
+    reversed
 }
+
 // struct MatlabIdentificationModule {}
 
 // impl StandaloneIdentificationModule for MatlabIdentificationModule {
@@ -91,10 +387,17 @@ fn read_design_model(path: &std::path::Path) -> Option<Box<dyn idesyde_core::Des
                 .map(|x| !x.starts_with("header"))
                 .unwrap_or(false)
             {
-                if let Ok(s) = std::fs::read_to_string(&path) {
-                    let m: SimulinkReactiveDesignModel =
-                        serde_json::from_str(s.as_str()).expect("something");
-                    Some(Box::new(m) as Box<dyn DesignModel>)
+                if let Ok(s) = std::fs::read_to_string(path) {
+                    serde_json::from_str::<SimulinkReactiveDesignModel>(s.as_str())
+                        .map_err(|e| {
+                            warn!(
+                                "Could not read {} as a Simulink model: {}",
+                                path.display(),
+                                e
+                            )
+                        })
+                        .ok()
+                        .map(|m| Box::new(m) as Box<dyn DesignModel>)
                 } else {
                     None
                 }
@@ -107,16 +410,54 @@ fn read_design_model(path: &std::path::Path) -> Option<Box<dyn idesyde_core::Des
 }
 
 fn write_design_model(
-    _design_model: &Box<dyn idesyde_core::DesignModel>,
-    _dest: &std::path::Path,
-) -> bool {
-    false
+    design_model: &dyn idesyde_core::DesignModel,
+    dest: &std::path::Path,
+) -> Option<std::path::PathBuf> {
+    if let Some(m) = design_model.downcast_ref::<SimulinkReactiveDesignModel>() {
+        if let Ok(s) = serde_json::to_string(m) {
+            if dest.is_dir() {
+                let mut target_idx = 0;
+                let mut target = dest.join(format!(
+                    "reversed_{}_MatlabIdentificationModule.json",
+                    target_idx
+                ));
+                while target.exists() {
+                    target_idx += 1;
+                    target = dest.join(format!(
+                        "reversed_{}_MatlabIdentificationModule.json",
+                        target_idx
+                    ));
+                }
+                return std::fs::write(&target, s).ok().map(|_| target);
+            } else if dest
+                .extension()
+                .map(|ext| ext.eq_ignore_ascii_case("json"))
+                .unwrap_or(false)
+            {
+                return std::fs::write(dest, s).ok().map(|_| dest.to_path_buf());
+            }
+        }
+    }
+    None
 }
 
 fn decision_header_to_model(
-    _header: &headers::DecisionModelHeader,
+    header: &headers::DecisionModelHeader,
 ) -> Option<Box<dyn idesyde_core::DecisionModel>> {
-    None
+    match header.category.as_str() {
+        "CommunicatingAndTriggeredReactiveWorkload" => {
+            load_decision_model::<CommunicatingAndTriggeredReactiveWorkload>(header)
+                .map(|m| Box::new(m) as Box<dyn DecisionModel>)
+        }
+        "PeriodicWorkloadToPartitionedSharedMultiCore" => load_decision_model::<
+            PeriodicWorkloadToPartitionedSharedMultiCore,
+        >(header)
+        .map(|mut m| {
+            m.header = Some(header.to_owned());
+            Box::new(m) as Box<dyn DecisionModel>
+        }),
+        _ => None,
+    }
 }
 
 //     fn identification_rules(&self) -> Vec<idesyde_core::MarkedIdentificationRule> {
@@ -132,10 +473,168 @@ fn main() {
         vec![MarkedIdentificationRule::DesignModelOnlyIdentificationRule(
             partially_identify_wokload_model,
         )],
-        vec![],
+        vec![reverse_identify_simulink_mappings],
         read_design_model,
         write_design_model,
         decision_header_to_model,
-        HashSet::new(),
+        HashSet::from([serde_json::to_string_pretty(&schema_for!(
+            CommunicatingAndTriggeredReactiveWorkload
+        ))
+        .unwrap()]),
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A source feeding a process twice, a constant feeding the same process and the process
+    /// feeding a sink through a delay.
+    fn simulink_model() -> SimulinkReactiveDesignModel {
+        let ops = HashMap::from([(
+            "default".to_string(),
+            HashMap::from([("add".to_string(), 2)]),
+        )]);
+        SimulinkReactiveDesignModel {
+            processes: HashSet::from(["p".to_string()]),
+            processes_sizes: HashMap::from([("p".to_string(), 64)]),
+            delays: HashSet::from(["d".to_string()]),
+            delays_sizes: HashMap::from([("d".to_string(), 8)]),
+            sources: HashSet::from(["src".to_string()]),
+            sources_sizes: HashMap::from([("src".to_string(), 16)]),
+            sources_periods_numen: HashMap::from([("src".to_string(), 1)]),
+            sources_periods_denom: HashMap::from([("src".to_string(), 50)]),
+            constants: HashSet::from(["c".to_string()]),
+            sinks: HashSet::from(["snk".to_string()]),
+            sinks_sizes: HashMap::from([("snk".to_string(), 16)]),
+            sinks_deadlines_numen: HashMap::from([("snk".to_string(), 1)]),
+            sinks_deadlines_denom: HashMap::from([("snk".to_string(), 50)]),
+            processes_operations: HashMap::from([("p".to_string(), ops.clone())]),
+            delays_operations: HashMap::from([("d".to_string(), ops)]),
+            links_src: vec!["src", "src", "c", "p", "d"]
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            links_dst: vec!["p", "p", "p", "d", "snk"]
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            links_src_port: vec!["1", "2", "1", "1", "1"]
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            links_dst_port: vec!["1", "2", "3", "1", "1"]
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            links_size: vec![16, 32, 8, 64, 8],
+            mapped_blocks: Vec::new(),
+            mapped_processors: Vec::new(),
+            mapped_priorities: Vec::new(),
+            mapped_memories: Vec::new(),
+        }
+    }
+
+    fn identified_workload() -> CommunicatingAndTriggeredReactiveWorkload {
+        let design_models = vec![Box::new(simulink_model()) as Box<dyn DesignModel>];
+        let identified = partially_identify_wokload_model(&design_models, &Vec::new());
+        assert_eq!(identified.len(), 1);
+        identified[0]
+            .downcast_ref::<CommunicatingAndTriggeredReactiveWorkload>()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn constants_are_not_part_of_the_workload() {
+        let workload = identified_workload();
+        assert_eq!(workload.tasks, vec!["p", "d"]);
+        assert_eq!(workload.task_sizes, vec![64, 8]);
+        assert!(!workload.data_channels.iter().any(|c| c.starts_with("c:")));
+        assert!(!workload.data_graph_src.contains(&"c".to_string()));
+        assert!(!workload.trigger_graph_src.contains(&"c".to_string()));
+    }
+
+    #[test]
+    fn links_between_the_same_blocks_are_grouped() {
+        let workload = identified_workload();
+        assert_eq!(
+            workload.data_channels,
+            vec![
+                "src:1--p:1",
+                "src:2--p:2",
+                "p:1--d:1",
+                "d:1--snk:1",
+                "src",
+                "snk"
+            ]
+        );
+        assert_eq!(workload.data_graph_src, vec!["src", "p", "d"]);
+        assert_eq!(workload.data_graph_dst, vec!["p", "d", "snk"]);
+        assert_eq!(workload.data_graph_message_size, vec![48, 64, 8]);
+        assert_eq!(workload.trigger_graph_src, workload.data_graph_src);
+        assert_eq!(workload.trigger_graph_dst, workload.data_graph_dst);
+    }
+
+    #[test]
+    fn source_periods_become_unsigned_fractions() {
+        let workload = identified_workload();
+        assert_eq!(workload.periodic_sources, vec!["src"]);
+        assert_eq!(workload.periods_numerator, vec![1]);
+        assert_eq!(workload.periods_denominator, vec![50]);
+        assert_eq!(workload.offsets_numerator, vec![0]);
+        assert_eq!(workload.offsets_denominator, vec![1]);
+    }
+
+    #[test]
+    fn other_json_files_are_not_read_as_simulink_models() {
+        let dir =
+            std::env::temp_dir().join(format!("idesyde-simulink-read-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let other = dir.join("other.json");
+        std::fs::write(&other, r#"{"vertexes": []}"#).unwrap();
+        assert!(read_design_model(&other).is_none());
+        let simulink = dir.join("model.json");
+        std::fs::write(&simulink, serde_json::to_string(&simulink_model()).unwrap()).unwrap();
+        let read = read_design_model(&simulink).unwrap();
+        assert_eq!(
+            read.downcast_ref::<SimulinkReactiveDesignModel>(),
+            Some(&simulink_model())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn explored_mappings_are_written_back_to_the_simulink_model() {
+        let body = r#"{
+            "platform": {
+                "runtimes": {
+                    "processors": ["pe0", "pe1"],
+                    "schedulers": ["os0", "os1"],
+                    "isFixedPriority": [true, true]
+                },
+                "hardware": {}
+            },
+            "workload": {"tasks": ["p", "d", "other"]},
+            "processMappings": [["d", "mem0"], ["p", "mem1"], ["other", "mem1"]],
+            "processSchedulings": [["d", "os1"], ["p", "os1"], ["other", "os0"]],
+            "channelMappings": []
+        }"#;
+        let solution: PeriodicWorkloadToPartitionedSharedMultiCore =
+            serde_json::from_str(body).unwrap();
+        let solved = vec![Box::new(solution) as Box<dyn DecisionModel>];
+        let design_models = vec![Box::new(simulink_model()) as Box<dyn DesignModel>];
+        let reversed = reverse_identify_simulink_mappings(&solved, &design_models);
+        assert_eq!(reversed.len(), 1);
+        let annotated = reversed[0]
+            .downcast_ref::<SimulinkReactiveDesignModel>()
+            .unwrap();
+        // d reads from p, so p runs first on the shared runtime
+        assert_eq!(annotated.mapped_blocks, vec!["p", "d"]);
+        assert_eq!(annotated.mapped_processors, vec!["pe1", "pe1"]);
+        assert_eq!(annotated.mapped_priorities, vec![0, 1]);
+        assert_eq!(annotated.mapped_memories, vec!["mem1", "mem0"]);
+        assert_eq!(annotated.processes, simulink_model().processes);
+        assert_eq!(annotated.links_src, simulink_model().links_src);
+    }
+}
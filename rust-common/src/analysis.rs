@@ -0,0 +1,498 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use num_integer::Integer;
+use num_rational::Rational64;
+use num_traits::CheckedMul;
+
+/// The outcome of solving the balance equations of a synchronous dataflow graph.
+///
+/// If the graph is consistent, the rational solution is given so that the first actor (in the order given)
+/// of each connected component fires once. Otherwise, one cycle of actors and channels
+/// that cannot be balanced is given, always starting and ending with the same actor.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SDFConsistency {
+    Consistent(HashMap<String, Rational64>),
+    Inconsistent(Vec<String>),
+}
+
+/// For every actor, the actors it shares a channel with, that channel and the rate of the other actor divided by its own.
+type BalanceEdges = HashMap<String, Vec<(String, String, Rational64)>>;
+
+/// The balance equations are built over actors: every channel that is produced by an actor `a` with rate `p` and
+/// consumed by an actor `b` with rate `c` results in `q[a] * p = q[b] * c`.
+///
+/// The topology follows the same encoding as [crate::SDFApplication], so that edges go either from actors to
+/// channels or from channels to actors. Returns `None` if a rate has no absolute value in 64 bits.
+fn balance_edges(
+    actors: &[String],
+    topology_srcs: &[String],
+    topology_dsts: &[String],
+    topology_edge_value: &[i64],
+) -> Option<BalanceEdges> {
+    let actor_set: HashSet<&String> = actors.iter().collect();
+    let mut producers: HashMap<&String, Vec<(&String, i64)>> = HashMap::new();
+    let mut consumers: HashMap<&String, Vec<(&String, i64)>> = HashMap::new();
+    for ((src, dst), v) in topology_srcs
+        .iter()
+        .zip(topology_dsts.iter())
+        .zip(topology_edge_value.iter())
+    {
+        if actor_set.contains(src) && !actor_set.contains(dst) {
+            producers
+                .entry(dst)
+                .or_default()
+                .push((src, v.checked_abs()?));
+        } else if actor_set.contains(dst) && !actor_set.contains(src) {
+            consumers
+                .entry(src)
+                .or_default()
+                .push((dst, v.checked_abs()?));
+        }
+    }
+    let mut edges: BalanceEdges = actors.iter().map(|a| (a.to_owned(), Vec::new())).collect();
+    for (channel, prods) in &producers {
+        if let Some(conss) = consumers.get(channel) {
+            for (p, prate) in prods {
+                for (c, crate_) in conss {
+                    if *prate > 0 && *crate_ > 0 {
+                        if let Some(es) = edges.get_mut(*p) {
+                            es.push((
+                                c.to_string(),
+                                channel.to_string(),
+                                Rational64::new(*prate, *crate_),
+                            ));
+                        }
+                        if let Some(es) = edges.get_mut(*c) {
+                            es.push((
+                                p.to_string(),
+                                channel.to_string(),
+                                Rational64::new(*crate_, *prate),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Some(edges)
+}
+
+/// Splits the actors into the connected components of the SDF graph, keeping the order of `actors`
+/// for the first actor of each component.
+fn connected_components(actors: &[String], edges: &BalanceEdges) -> Vec<Vec<String>> {
+    let mut visited: HashSet<&String> = HashSet::new();
+    let mut components = Vec::new();
+    for a in actors {
+        if visited.insert(a) {
+            let mut component = vec![a.to_owned()];
+            let mut queue = VecDeque::from([a]);
+            while let Some(v) = queue.pop_front() {
+                for (n, _, _) in edges.get(v).into_iter().flatten() {
+                    if visited.insert(n) {
+                        component.push(n.to_owned());
+                        queue.push_back(n);
+                    }
+                }
+            }
+            components.push(component);
+        }
+    }
+    components
+}
+
+/// Solves the balance equations of the SDF graph encoded by the topology.
+///
+/// The equations are solved by traversing the graph and propagating the firing ratios. Whenever an actor
+/// is reached again with a different ratio, the graph is inconsistent and the cycle formed by the traversal
+/// tree and the conflicting channel is returned.
+///
+/// Returns `None` if the firing ratios do not fit in 64-bit rationals.
+pub fn check_sdf_consistency(
+    actors: &[String],
+    topology_srcs: &[String],
+    topology_dsts: &[String],
+    topology_edge_value: &[i64],
+) -> Option<SDFConsistency> {
+    let edges = balance_edges(actors, topology_srcs, topology_dsts, topology_edge_value)?;
+    let mut rates: HashMap<String, Rational64> = HashMap::new();
+    // the parent actor and the channel used to reach each actor in the traversal
+    let mut parents: HashMap<String, (String, String)> = HashMap::new();
+    for root in actors {
+        if rates.contains_key(root) {
+            continue;
+        }
+        rates.insert(root.to_owned(), Rational64::from_integer(1));
+        let mut queue = VecDeque::from([root.to_owned()]);
+        while let Some(v) = queue.pop_front() {
+            let v_rate = rates[&v];
+            for (n, channel, ratio) in edges.get(&v).into_iter().flatten() {
+                let expected = v_rate.checked_mul(ratio)?;
+                match rates.get(n) {
+                    None => {
+                        rates.insert(n.to_owned(), expected);
+                        parents.insert(n.to_owned(), (v.to_owned(), channel.to_owned()));
+                        queue.push_back(n.to_owned());
+                    }
+                    Some(r) if *r != expected => {
+                        return Some(SDFConsistency::Inconsistent(offending_cycle(
+                            &parents, &v, n, channel,
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Some(SDFConsistency::Consistent(rates))
+}
+
+fn offending_cycle(
+    parents: &HashMap<String, (String, String)>,
+    from: &str,
+    to: &str,
+    closing_channel: &str,
+) -> Vec<String> {
+    let ancestors = |start: &str| {
+        let mut path = vec![start.to_string()];
+        let mut cur = start.to_string();
+        while let Some((p, _)) = parents.get(&cur) {
+            path.push(p.to_owned());
+            cur = p.to_owned();
+        }
+        path
+    };
+    let from_ancestors = ancestors(from);
+    let to_ancestors = ancestors(to);
+    let common = from_ancestors
+        .iter()
+        .find(|a| to_ancestors.contains(a))
+        .cloned()
+        .unwrap_or_else(|| from.to_string());
+    let mut cycle = Vec::new();
+    let mut cur = from.to_string();
+    while cur != common {
+        let (p, c) = &parents[&cur];
+        cycle.push(cur.to_owned());
+        cycle.push(c.to_owned());
+        cur = p.to_owned();
+    }
+    cycle.push(common.to_owned());
+    let mut down = Vec::new();
+    let mut cur = to.to_string();
+    while cur != common {
+        let (p, c) = &parents[&cur];
+        down.push(cur.to_owned());
+        down.push(c.to_owned());
+        cur = p.to_owned();
+    }
+    cycle.extend(down.into_iter().rev());
+    cycle.push(closing_channel.to_string());
+    cycle.push(from.to_string());
+    cycle
+}
+
+/// Computes the smallest integer solution of the balance equations for every connected component
+/// of the SDF graph, i.e. its repetition vector.
+///
+/// Returns the offending cycle if the graph is inconsistent, and `None` if the repetition vector
+/// does not fit in 64-bit integers.
+pub fn compute_repetition_vector(
+    actors: &[String],
+    topology_srcs: &[String],
+    topology_dsts: &[String],
+    topology_edge_value: &[i64],
+) -> Option<Result<HashMap<String, u64>, Vec<String>>> {
+    match check_sdf_consistency(actors, topology_srcs, topology_dsts, topology_edge_value)? {
+        SDFConsistency::Inconsistent(cycle) => Some(Err(cycle)),
+        SDFConsistency::Consistent(rates) => {
+            let edges = balance_edges(actors, topology_srcs, topology_dsts, topology_edge_value)?;
+            let mut repetition_vector = HashMap::new();
+            for component in connected_components(actors, &edges) {
+                let mut denom_lcm: i64 = 1;
+                for a in &component {
+                    let d = rates[a].denom();
+                    denom_lcm = denom_lcm.checked_mul(d / denom_lcm.gcd(d))?;
+                }
+                let scaled = component
+                    .iter()
+                    .map(|a| {
+                        rates[a]
+                            .checked_mul(&Rational64::from_integer(denom_lcm))
+                            .map(|q| q.to_integer())
+                    })
+                    .collect::<Option<Vec<i64>>>()?;
+                let numer_gcd = scaled.iter().fold(0, |acc: i64, q| acc.gcd(q)).max(1);
+                for (a, q) in component.iter().zip(scaled.iter()) {
+                    repetition_vector.insert(a.to_owned(), (q / numer_gcd) as u64);
+                }
+            }
+            Some(Ok(repetition_vector))
+        }
+    }
+}
+
+/// Simulates one full iteration of the SDF graph, as given by the repetition vector, starting from the initial tokens.
+///
+/// In every round, all actors that still have firings left and have enough tokens in all their input channels
+/// fire once. The firings of the same round are ordered by the decreasing amount of data (in bits) each actor consumes,
+/// so that heavier jobs come first, which gives the topological and heavy job ordering used by
+/// [crate::SDFApplication]. Returns `Err` with the actors that could not complete their firings if the graph deadlocks.
+/// Token counts and amounts of data saturate at their largest value instead of overflowing.
+pub fn compute_topological_and_heavy_job_ordering(
+    actors: &[String],
+    topology_srcs: &[String],
+    topology_dsts: &[String],
+    topology_edge_value: &[i64],
+    channel_num_initial_tokens: &HashMap<String, i64>,
+    channel_token_sizes: &HashMap<String, u64>,
+    repetition_vector: &HashMap<String, u64>,
+) -> Result<Vec<String>, Vec<String>> {
+    let actor_set: HashSet<&String> = actors.iter().collect();
+    let mut inputs: HashMap<&String, Vec<(&String, i64)>> = HashMap::new();
+    let mut outputs: HashMap<&String, Vec<(&String, i64)>> = HashMap::new();
+    for ((src, dst), v) in topology_srcs
+        .iter()
+        .zip(topology_dsts.iter())
+        .zip(topology_edge_value.iter())
+    {
+        if actor_set.contains(src) && !actor_set.contains(dst) {
+            outputs
+                .entry(src)
+                .or_default()
+                .push((dst, v.saturating_abs()));
+        } else if actor_set.contains(dst) && !actor_set.contains(src) {
+            inputs
+                .entry(dst)
+                .or_default()
+                .push((src, v.saturating_abs()));
+        }
+    }
+    let consumption: HashMap<&String, u64> = actors
+        .iter()
+        .map(|a| {
+            (
+                a,
+                inputs
+                    .get(a)
+                    .into_iter()
+                    .flatten()
+                    .map(|(c, v)| {
+                        channel_token_sizes
+                            .get(*c)
+                            .copied()
+                            .unwrap_or(0)
+                            .saturating_mul(*v as u64)
+                    })
+                    .fold(0, u64::saturating_add),
+            )
+        })
+        .collect();
+    let mut heavy_order: Vec<&String> = actors.iter().collect();
+    heavy_order.sort_by(|a, b| consumption[b].cmp(&consumption[a]));
+    let mut tokens: HashMap<&String, i64> = HashMap::new();
+    for (c, v) in channel_num_initial_tokens {
+        tokens.insert(c, *v);
+    }
+    let mut remaining: HashMap<&String, u64> = actors
+        .iter()
+        .map(|a| (a, repetition_vector.get(a).copied().unwrap_or(0)))
+        .collect();
+    let mut ordering = Vec::new();
+    while remaining.values().any(|r| *r > 0) {
+        let enabled: Vec<&String> = heavy_order
+            .iter()
+            .filter(|a| remaining[*a] > 0)
+            .filter(|a| {
+                inputs
+                    .get(*a)
+                    .into_iter()
+                    .flatten()
+                    .all(|(c, v)| tokens.get(c).copied().unwrap_or(0) >= *v)
+            })
+            .copied()
+            .collect();
+        if enabled.is_empty() {
+            return Err(actors
+                .iter()
+                .filter(|a| remaining[a] > 0)
+                .map(|a| a.to_owned())
+                .collect());
+        }
+        for a in enabled {
+            for (c, v) in inputs.get(a).into_iter().flatten() {
+                let t = tokens.entry(c).or_insert(0);
+                *t = t.saturating_sub(*v);
+            }
+            for (c, v) in outputs.get(a).into_iter().flatten() {
+                let t = tokens.entry(c).or_insert(0);
+                *t = t.saturating_add(*v);
+            }
+            if let Some(r) = remaining.get_mut(a) {
+                *r -= 1;
+            }
+            ordering.push(a.to_owned());
+        }
+    }
+    Ok(ordering)
+}
+
+/// Checks if the SDF graph deadlocks within one iteration, given its initial tokens.
+///
+/// Returns the actors that cannot complete their firings, or `None` if the graph is live.
+pub fn check_sdf_deadlock(
+    actors: &[String],
+    topology_srcs: &[String],
+    topology_dsts: &[String],
+    topology_edge_value: &[i64],
+    channel_num_initial_tokens: &HashMap<String, i64>,
+    repetition_vector: &HashMap<String, u64>,
+) -> Option<Vec<String>> {
+    compute_topological_and_heavy_job_ordering(
+        actors,
+        topology_srcs,
+        topology_dsts,
+        topology_edge_value,
+        channel_num_initial_tokens,
+        &HashMap::new(),
+        repetition_vector,
+    )
+    .err()
+}
+
+/// Checks, with a Bellman-Ford relaxation from every node at once, if the graph has a cycle whose
+/// sum of `weight - lambda * tokens` is positive.
+fn has_positive_cycle(num_nodes: usize, edges: &[(usize, usize, f64, u64)], lambda: f64) -> bool {
+    let mut dist = vec![0.0; num_nodes];
+    for _ in 0..=num_nodes {
+        let mut relaxed = false;
+        for (src, dst, weight, tokens) in edges {
+            let candidate = dist[*src] + weight - lambda * (*tokens as f64);
+            if candidate > dist[*dst] + f64::EPSILON * candidate.abs().max(1.0) {
+                dist[*dst] = candidate;
+                relaxed = true;
+            }
+        }
+        if !relaxed {
+            return false;
+        }
+    }
+    true
+}
+
+/// Computes the maximum cycle mean of a graph whose edges have non-negative weights and a number of tokens,
+/// i.e. the largest ratio between the sum of weights and the sum of tokens among all cycles of the graph.
+///
+/// For the graph of a self-timed schedule, where the weight of an edge is the time taken by its source and
+/// the tokens are the number of iterations it looks back, the maximum cycle mean is the iteration period.
+/// It is found by bisection, since a cycle with a larger mean than `lambda` exists if and only if the graph
+/// weighted with `weight - lambda * tokens` has a positive cycle, and the upper end of the final interval is returned.
+///
+/// Returns `None` if a cycle has no tokens, i.e. the schedule deadlocks, and `Some(0.0)` if there are no cycles.
+pub fn maximum_cycle_mean(num_nodes: usize, edges: &[(usize, usize, f64, u64)]) -> Option<f64> {
+    // a cycle without tokens exists iff the edges without tokens cannot be sorted topologically
+    let mut in_degree = vec![0usize; num_nodes];
+    for (_, dst, _, tokens) in edges {
+        if *tokens == 0 {
+            in_degree[*dst] += 1;
+        }
+    }
+    let mut queue: VecDeque<usize> = (0..num_nodes).filter(|v| in_degree[*v] == 0).collect();
+    let mut sorted = 0;
+    while let Some(v) = queue.pop_front() {
+        sorted += 1;
+        for (_, dst, _, _) in edges
+            .iter()
+            .filter(|(src, _, _, tokens)| *src == v && *tokens == 0)
+        {
+            in_degree[*dst] -= 1;
+            if in_degree[*dst] == 0 {
+                queue.push_back(*dst);
+            }
+        }
+    }
+    if sorted < num_nodes {
+        return None;
+    }
+    let mut lo = 0.0;
+    let mut hi: f64 = edges.iter().map(|(_, _, w, _)| w.max(0.0)).sum();
+    if hi <= 0.0 || !has_positive_cycle(num_nodes, edges, 0.0) {
+        return Some(0.0);
+    }
+    for _ in 0..128 {
+        if hi - lo <= 1e-12 * hi {
+            break;
+        }
+        let mid = (lo + hi) / 2.0;
+        if has_positive_cycle(num_nodes, edges, mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(found: Option<f64>, expected: f64) {
+        let found = found.expect("the maximum cycle mean should exist");
+        assert!(
+            (found - expected).abs() <= 1e-9 * expected.max(1.0),
+            "expected {} but found {}",
+            expected,
+            found
+        );
+    }
+
+    #[test]
+    fn graphs_without_nodes_or_cycles_have_no_cycle_mean() {
+        assert_eq!(maximum_cycle_mean(0, &[]), Some(0.0));
+        assert_eq!(
+            maximum_cycle_mean(3, &[(0, 1, 2.0, 0), (1, 2, 3.0, 0), (0, 2, 1.0, 1)]),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn self_loops_have_their_weight_over_their_tokens() {
+        assert_close(maximum_cycle_mean(1, &[(0, 0, 2.0, 1)]), 2.0);
+        assert_close(maximum_cycle_mean(1, &[(0, 0, 3.0, 2)]), 1.5);
+    }
+
+    #[test]
+    fn the_largest_cycle_mean_is_found() {
+        // the cycle 0 -> 1 -> 0 has mean (4 + 6) / 2 = 5, the cycle 1 -> 2 -> 1 has mean (1 + 2) / 1 = 3
+        // and the self loop on 2 has mean 4
+        let edges = [
+            (0, 1, 4.0, 1),
+            (1, 0, 6.0, 1),
+            (1, 2, 1.0, 0),
+            (2, 1, 2.0, 1),
+            (2, 2, 4.0, 1),
+        ];
+        assert_close(maximum_cycle_mean(3, &edges), 5.0);
+    }
+
+    #[test]
+    fn cycles_with_many_tokens_are_averaged() {
+        // the cycle 0 -> 1 -> 2 -> 0 has mean 9 / 3 = 3, which beats the self loop on 0
+        let edges = [
+            (0, 1, 3.0, 1),
+            (1, 2, 3.0, 1),
+            (2, 0, 3.0, 1),
+            (0, 0, 2.5, 1),
+        ];
+        assert_close(maximum_cycle_mean(3, &edges), 3.0);
+    }
+
+    #[test]
+    fn cycles_without_tokens_deadlock() {
+        assert_eq!(maximum_cycle_mean(1, &[(0, 0, 1.0, 0)]), None);
+        assert_eq!(
+            maximum_cycle_mean(3, &[(0, 1, 1.0, 0), (1, 2, 1.0, 0), (2, 0, 1.0, 0)]),
+            None
+        );
+    }
+}
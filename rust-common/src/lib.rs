@@ -1,27 +1,42 @@
-use std::collections::{HashMap, HashSet};
+pub mod analysis;
+pub mod sdf3;
 
-use idesyde_core::{headers::DecisionModelHeader, DecisionModel, DesignModel};
-use schemars::JsonSchema;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use idesyde_core::{
+    headers::{DecisionModelHeader, LabelledArcWithPorts},
+    load_decision_model, DecisionModel, DesignModel, MarkedIdentificationRule,
+    StandaloneIdentificationModule,
+};
+use log::warn;
+use schemars::{schema_for, JsonSchema};
+use sdf3::Sdf3DesignModel;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, JsonSchema, DecisionModel)]
+#[relations(data_and_trigger_graphs)]
 pub struct CommunicatingAndTriggeredReactiveWorkload {
+    #[elems]
     pub tasks: Vec<String>,
     pub task_sizes: Vec<u32>,
     pub task_computational_needs: Vec<HashMap<String, HashMap<String, u32>>>,
+    #[elems]
     pub data_channels: Vec<String>,
     pub data_channel_sizes: Vec<u32>,
     pub data_graph_src: Vec<String>,
     pub data_graph_dst: Vec<String>,
     pub data_graph_message_size: Vec<u32>,
+    #[elems]
     pub periodic_sources: Vec<String>,
     pub periods_numerator: Vec<u32>,
     pub periods_denominator: Vec<u32>,
     pub offsets_numerator: Vec<u32>,
     pub offsets_denominator: Vec<u32>,
+    #[elems]
     pub upsamples: Vec<String>,
     pub upsample_repetitive_holds: Vec<u32>,
     pub upsample_initial_holds: Vec<u32>,
+    #[elems]
     pub downsamples: Vec<String>,
     pub downample_repetitive_skips: Vec<u32>,
     pub downample_initial_skips: Vec<u32>,
@@ -30,39 +45,28 @@ pub struct CommunicatingAndTriggeredReactiveWorkload {
     pub has_or_trigger_semantics: HashSet<String>,
 }
 
-impl DecisionModel for CommunicatingAndTriggeredReactiveWorkload {
-    fn unique_identifier(&self) -> String {
-        "CommunicatingAndTriggeredReactiveWorkload".to_string()
-    }
-
-    fn header(&self) -> idesyde_core::headers::DecisionModelHeader {
-        let mut elems: HashSet<String> = HashSet::new();
-        elems.extend(self.tasks.iter().map(|x| x.to_owned()));
-        elems.extend(self.data_channels.iter().map(|x| x.to_owned()));
-        elems.extend(self.periodic_sources.iter().map(|x| x.to_owned()));
-        elems.extend(self.upsamples.iter().map(|x| x.to_owned()));
-        elems.extend(self.downsamples.iter().map(|x| x.to_owned()));
+impl CommunicatingAndTriggeredReactiveWorkload {
+    fn data_and_trigger_graphs(&self) -> Vec<LabelledArcWithPorts> {
+        let mut rels = Vec::new();
         for i in 0..self.data_graph_src.len() {
-            elems.insert(format!(
-                "{}={}:{}-{}:{}",
+            rels.push(LabelledArcWithPorts::new(
                 self.data_graph_message_size[i],
-                self.data_graph_src[i],
+                &self.data_graph_src[i],
+                "",
+                &self.data_graph_dst[i],
                 "",
-                self.data_graph_dst[i],
-                ""
             ));
         }
         for i in 0..self.trigger_graph_src.len() {
-            elems.insert(format!(
-                "{}={}:{}-{}:{}",
-                "trigger", self.trigger_graph_src[i], "", self.trigger_graph_dst[i], ""
+            rels.push(LabelledArcWithPorts::new(
+                "trigger",
+                &self.trigger_graph_src[i],
+                "",
+                &self.trigger_graph_dst[i],
+                "",
             ));
         }
-        DecisionModelHeader {
-            category: self.unique_identifier(),
-            body_path: None,
-            covered_elements: elems.into_iter().collect(),
-        }
+        rels
     }
 }
 
@@ -76,11 +80,14 @@ impl DecisionModel for CommunicatingAndTriggeredReactiveWorkload {
 /// tokens from the actor a to channel c.
 ///
 /// This decision model is already analised, and provides the repetition vector for the SDF graphs contained as well
-/// as a schedule if these SDF graphs are consistent.
+/// as a schedule if these SDF graphs are consistent. See [analysis] for the functions used to compute them.
 ///
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema, DecisionModel)]
+#[relations(topology)]
 pub struct SDFApplication {
+    #[elems]
     pub actors_identifiers: Vec<String>,
+    #[elems]
     pub channels_identifiers: Vec<String>,
     pub topology_srcs: Vec<String>,
     pub topology_dsts: Vec<String>,
@@ -90,55 +97,36 @@ pub struct SDFApplication {
     pub channel_num_initial_tokens: HashMap<String, i64>,
     pub channel_token_sizes: HashMap<String, u64>,
     pub minimum_actor_throughputs: HashMap<String, f64>,
-    pub repetition_vector: Vec<String>,
+    pub repetition_vector: HashMap<String, u64>,
     pub topological_and_heavy_job_ordering: Vec<String>,
 }
 
-impl DecisionModel for SDFApplication {
-    fn body_as_json(&self) -> Option<String> {
-        serde_json::to_string(self).ok()
-    }
-
-    fn body_as_msgpack(&self) -> Option<Vec<u8>> {
-        rmp_serde::to_vec(self).ok()
-    }
-
-    fn body_as_cbor(&self) -> Option<Vec<u8>> {
-        let mut b: Vec<u8> = Vec::new();
-        if let Ok(_) = ciborium::into_writer(self, &mut b) {
-            Some(b)
-        } else {
-            None
-        }
-    }
-
-    fn unique_identifier(&self) -> String {
-        "SDFApplication".to_string()
-    }
-
-    fn header(&self) -> DecisionModelHeader {
-        let mut elems: HashSet<String> = HashSet::new();
-        elems.extend(self.actors_identifiers.iter().map(|x| x.to_owned()));
-        elems.extend(self.channels_identifiers.iter().map(|x| x.to_owned()));
-        for i in 0..self.topology_srcs.len() {
-            elems.insert(format!(
-                "{}={}:{}-{}:{}",
-                self.topology_edge_value[i], self.topology_srcs[i], "", self.topology_dsts[i], ""
-            ));
-        }
-        DecisionModelHeader {
-            category: self.unique_identifier(),
-            body_path: None,
-            covered_elements: elems.into_iter().collect(),
-        }
+impl SDFApplication {
+    fn topology(&self) -> Vec<LabelledArcWithPorts> {
+        (0..self.topology_srcs.len())
+            .map(|i| {
+                LabelledArcWithPorts::new(
+                    self.topology_edge_value[i],
+                    &self.topology_srcs[i],
+                    "",
+                    &self.topology_dsts[i],
+                    "",
+                )
+            })
+            .collect()
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema, DecisionModel)]
+#[relations(interconnect)]
 pub struct TiledMultiCore {
+    #[elems]
     pub processors: Vec<String>,
+    #[elems]
     pub memories: Vec<String>,
+    #[elems]
     pub network_interfaces: Vec<String>,
+    #[elems]
     pub routers: Vec<String>,
     pub interconnect_topology_srcs: Vec<String>,
     pub interconnect_topology_dsts: Vec<String>,
@@ -150,57 +138,54 @@ pub struct TiledMultiCore {
     pub pre_computed_paths: HashMap<String, HashMap<String, Vec<String>>>,
 }
 
-impl DecisionModel for TiledMultiCore {
-    fn body_as_json(&self) -> Option<String> {
-        serde_json::to_string(self).ok()
-    }
-
-    fn body_as_msgpack(&self) -> Option<Vec<u8>> {
-        rmp_serde::to_vec(self).ok()
-    }
-
-    fn body_as_cbor(&self) -> Option<Vec<u8>> {
-        let mut b: Vec<u8> = Vec::new();
-        if let Ok(_) = ciborium::into_writer(self, &mut b) {
-            Some(b)
-        } else {
-            None
-        }
-    }
-
-    fn unique_identifier(&self) -> String {
-        "TiledMultiCore".to_string()
+impl TiledMultiCore {
+    fn interconnect(&self) -> Vec<LabelledArcWithPorts> {
+        (0..self.interconnect_topology_srcs.len())
+            .map(|i| {
+                LabelledArcWithPorts::new(
+                    "interconnect",
+                    &self.interconnect_topology_srcs[i],
+                    "",
+                    &self.interconnect_topology_dsts[i],
+                    "",
+                )
+            })
+            .collect()
     }
 
-    fn header(&self) -> DecisionModelHeader {
-        let mut elems: HashSet<String> = HashSet::new();
-        elems.extend(self.processors.iter().map(|x| x.to_owned()));
-        elems.extend(self.memories.iter().map(|x| x.to_owned()));
-        elems.extend(self.network_interfaces.iter().map(|x| x.to_owned()));
-        elems.extend(self.routers.iter().map(|x| x.to_owned()));
-        for i in 0..self.interconnect_topology_srcs.len() {
-            elems.insert(format!(
-                "{}={}:{}-{}:{}",
-                "interconnect",
-                self.interconnect_topology_srcs[i],
-                "",
-                self.interconnect_topology_dsts[i],
-                ""
-            ));
-        }
-        DecisionModelHeader {
-            category: self.unique_identifier(),
-            body_path: None,
-            covered_elements: elems.into_iter().collect(),
-        }
+    /// Finds the memory in the tile of the processor `pe`, i.e. the memory connected to it in the interconnect
+    /// or, if none is, the memory reached from it through the fewest communication elements.
+    pub fn tile_memory(&self, pe: &str) -> Option<&String> {
+        let connected = |mem: &String| {
+            self.interconnect_topology_srcs
+                .iter()
+                .zip(self.interconnect_topology_dsts.iter())
+                .any(|(src, dst)| (src == pe && dst == mem) || (src == mem && dst == pe))
+        };
+        self.memories.iter().find(|mem| connected(mem)).or_else(|| {
+            self.memories
+                .iter()
+                .flat_map(|mem| {
+                    self.pre_computed_paths
+                        .get(pe)
+                        .and_then(|ps| ps.get(mem))
+                        .map(|path| (path.len(), mem))
+                })
+                .min_by_key(|(hops, _)| *hops)
+                .map(|(_, mem)| mem)
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema, DecisionModel)]
 pub struct RuntimesAndProcessors {
+    #[elems]
     pub runtimes: Vec<String>,
+    #[elems]
     pub processors: Vec<String>,
+    #[relations("host")]
     pub runtime_host: HashMap<String, String>,
+    #[relations("scheduler")]
     pub processor_affinities: HashMap<String, String>,
     pub is_bare_metal: Vec<bool>,
     pub is_fixed_priority: Vec<bool>,
@@ -208,127 +193,411 @@ pub struct RuntimesAndProcessors {
     pub is_cyclic_executive: Vec<bool>,
 }
 
-impl DecisionModel for RuntimesAndProcessors {
-    fn body_as_json(&self) -> Option<String> {
-        serde_json::to_string(self).ok()
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema, DecisionModel)]
+#[elems(elements_of_parts)]
+#[relations(relations_of_parts)]
+pub struct PartitionedTiledMulticore {
+    pub hardware: TiledMultiCore,
+    pub runtimes: RuntimesAndProcessors,
+}
+
+impl PartitionedTiledMulticore {
+    fn elements_of_parts(&self) -> BTreeSet<String> {
+        let mut elems = self.hardware.header().covered_elements;
+        elems.extend(self.runtimes.header().covered_elements);
+        elems
     }
 
-    fn body_as_msgpack(&self) -> Option<Vec<u8>> {
-        rmp_serde::to_vec(self).ok()
+    fn relations_of_parts(&self) -> BTreeSet<LabelledArcWithPorts> {
+        let mut rels = self.hardware.header().covered_relations;
+        rels.extend(self.runtimes.header().covered_relations);
+        rels
     }
+}
 
-    fn body_as_cbor(&self) -> Option<Vec<u8>> {
-        let mut b: Vec<u8> = Vec::new();
-        if let Ok(_) = ciborium::into_writer(self, &mut b) {
-            Some(b)
-        } else {
-            None
+pub fn identify_partitioned_tiled_multicore(
+    _design_models: &Vec<Box<dyn DesignModel>>,
+    decision_models: &Vec<Box<dyn DecisionModel>>,
+) -> Vec<Box<dyn DecisionModel>> {
+    let mut new_models = Vec::new();
+    for m2 in decision_models {
+        if let Some(runt) = m2.downcast_ref::<RuntimesAndProcessors>() {
+            let same_number = runt.processors.len() == runt.runtimes.len();
+            let one_scheduler_per_proc = runt
+                .processors
+                .iter()
+                .all(|p| runt.runtime_host.values().find(|y| y == &p).is_some());
+            let one_proc_per_scheduler = runt.runtimes.iter().all(|s| {
+                runt.processor_affinities
+                    .values()
+                    .find(|y| y == &s)
+                    .is_some()
+            });
+            if same_number && one_proc_per_scheduler && one_scheduler_per_proc {
+                for m1 in decision_models {
+                    if let Some(plat) = m1.downcast_ref::<TiledMultiCore>() {
+                        let potential = Box::new(PartitionedTiledMulticore {
+                            hardware: plat.to_owned(),
+                            runtimes: runt.to_owned(),
+                        });
+                        let upcast = potential as Box<dyn DecisionModel>;
+                        if !decision_models.contains(&upcast) {
+                            new_models.push(upcast);
+                        }
+                    }
+                }
+            }
         }
     }
+    new_models
+}
 
-    fn unique_identifier(&self) -> String {
-        "RuntimesAndProcessors".to_string()
+/// Decision model for the mapping and scheduling of SDF applications onto tiled multicores.
+///
+/// Every actor is scheduled by one of the runtimes of the platform, which is hosted by a single processor,
+/// and is stored in one of the tile memories. Each runtime executes its actors following a super loop,
+/// i.e. a static order of firings that is repeated for every iteration of the SDF graphs.
+/// Every channel is stored in one of the tile memories, and the communication elements between the memory and
+/// the processors of its actors reserve slots for it: `channels_slot_allocations[c][ce][i]` is true if slot `i` of
+/// the communication element `ce` is reserved for the channel `c`.
+/// The decisions are empty when the model is identified and filled in by explorers.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SDFToTiledMultiCore {
+    pub sdf_application: SDFApplication,
+    pub platform: PartitionedTiledMulticore,
+    pub processes_to_runtime_scheduling: HashMap<String, String>,
+    pub processes_to_memory_mapping: HashMap<String, String>,
+    pub super_loop_schedules: HashMap<String, Vec<String>>,
+    pub channels_to_memory_mapping: HashMap<String, String>,
+    pub channels_slot_allocations: HashMap<String, HashMap<String, Vec<bool>>>,
+}
+
+impl SDFToTiledMultiCore {
+    /// Computes the worst-case execution time, in seconds, of every actor on every processor that can execute it.
+    ///
+    /// The computational needs of an actor can be matched to a processor mode whenever all the needed operations
+    /// are provided in that mode. The fastest match over all modes and groups of needs is taken.
+    pub fn wcets(&self) -> HashMap<String, HashMap<String, f64>> {
+        let hw = &self.platform.hardware;
+        let mut wcets = HashMap::new();
+        for a in &self.sdf_application.actors_identifiers {
+            let mut per_processor = HashMap::new();
+            if let Some(needs) = self.sdf_application.actor_computational_needs.get(a) {
+                for pe in &hw.processors {
+                    let freq = hw.processors_frequency.get(pe).copied().unwrap_or(0) as f64;
+                    let mut best: Option<f64> = None;
+                    for provisions in hw
+                        .processors_provisions
+                        .get(pe)
+                        .into_iter()
+                        .flat_map(|x| x.values())
+                    {
+                        for group in needs.values() {
+                            if freq > 0.0 && group.keys().all(|op| provisions.contains_key(op)) {
+                                let cycles: f64 =
+                                    group.iter().map(|(op, n)| *n as f64 / provisions[op]).sum();
+                                let t = cycles / freq;
+                                best = Some(best.map(|b| b.min(t)).unwrap_or(t));
+                            }
+                        }
+                    }
+                    if let Some(t) = best {
+                        per_processor.insert(pe.to_owned(), t);
+                    }
+                }
+            }
+            wcets.insert(a.to_owned(), per_processor);
+        }
+        wcets
     }
 
-    fn header(&self) -> DecisionModelHeader {
-        let mut elems: HashSet<String> = HashSet::new();
-        elems.extend(self.processors.iter().map(|x| x.to_owned()));
-        elems.extend(self.runtimes.iter().map(|x| x.to_owned()));
-        for (sched, pe) in &self.runtime_host {
-            elems.insert(format!("{}={}:{}-{}:{}", "host", sched, "", pe, ""));
+    /// Computes the time, in seconds, taken to send `bits` from `src` to `dst` on behalf of the channel `c`.
+    ///
+    /// The channel only gets the slots reserved for it in every communication element of the path, and slots
+    /// reserved for many channels are shared equally between them. The slowest element of the path dominates.
+    /// Returns `None` if there is an element in the path without slots for the channel.
+    fn transfer_time(&self, c: &str, src: &str, dst: &str, bits: f64) -> Option<f64> {
+        let hw = &self.platform.hardware;
+        let path = hw
+            .pre_computed_paths
+            .get(src)
+            .and_then(|ps| ps.get(dst))
+            .cloned()
+            .unwrap_or_default();
+        let mut time: f64 = 0.0;
+        for ce in &path {
+            let own = self.channels_slot_allocations.get(c)?.get(ce)?;
+            let mut bandwidth = 0.0;
+            for (i, reserved) in own.iter().enumerate() {
+                if *reserved {
+                    let sharing = self
+                        .channels_slot_allocations
+                        .values()
+                        .filter(|o| o.get(ce).and_then(|v| v.get(i)).copied().unwrap_or(false))
+                        .count();
+                    bandwidth += hw
+                        .communication_elements_bit_per_sec_per_channel
+                        .get(ce)
+                        .copied()
+                        .unwrap_or(0.0)
+                        / sharing as f64;
+                }
+            }
+            if bandwidth <= 0.0 {
+                return None;
+            }
+            time = time.max(bits / bandwidth);
         }
-        for (pe, sched) in &self.processor_affinities {
-            elems.insert(format!("{}={}:{}-{}:{}", "scheduler", pe, "", sched, ""));
+        Some(time)
+    }
+
+    /// Computes the iteration period, in seconds, of the self-timed execution of the decisions taken.
+    ///
+    /// The SDF application is expanded so that every firing in one iteration is a node, connected to the firings it
+    /// depends on by the channels and by the super loop of its runtime, which starts over after its last firing.
+    /// The time of a dependency is the execution time of the firing it comes from plus, for channels, the time
+    /// taken to write the produced tokens into the memory of the channel and to read them into the consumer processor.
+    /// The period is then the maximum cycle mean of the expanded graph.
+    ///
+    /// Returns `None` if the decisions are incomplete, an actor cannot run on its processor,
+    /// a channel has no slots to cross the platform or the schedule deadlocks.
+    pub fn self_timed_period(&self) -> Option<f64> {
+        let app = &self.sdf_application;
+        let runtimes = &self.platform.runtimes;
+        let wcets = self.wcets();
+        let mut processor_of: HashMap<&String, &String> = HashMap::new();
+        let mut durations: HashMap<&String, f64> = HashMap::new();
+        let mut first_node: HashMap<&String, usize> = HashMap::new();
+        let mut num_nodes = 0;
+        for a in &app.actors_identifiers {
+            let sched = self.processes_to_runtime_scheduling.get(a)?;
+            let pe = runtimes
+                .processor_affinities
+                .iter()
+                .find(|(_, s)| *s == sched)
+                .map(|(pe, _)| pe)
+                .or_else(|| runtimes.runtime_host.get(sched))?;
+            processor_of.insert(a, pe);
+            durations.insert(a, *wcets.get(a)?.get(pe)?);
+            first_node.insert(a, num_nodes);
+            num_nodes += app.repetition_vector.get(a).copied().unwrap_or(1) as usize;
         }
-        DecisionModelHeader {
-            category: self.unique_identifier(),
-            body_path: None,
-            covered_elements: elems.into_iter().collect(),
+        let firings = |a: &String| app.repetition_vector.get(a).copied().unwrap_or(1) as i64;
+        // parallel edges with different tokens are all kept, since one with fewer tokens can still have
+        // a smaller weight, but of those with the same tokens only the heaviest matters
+        let mut edges: HashMap<(usize, usize, u64), f64> = HashMap::new();
+        let mut add_edge = |src: usize, dst: usize, weight: f64, tokens: u64| {
+            let w = edges.entry((src, dst, tokens)).or_insert(weight);
+            *w = w.max(weight);
+        };
+        // the dependencies given by the channels
+        let mut producers: HashMap<&String, (&String, i64)> = HashMap::new();
+        let mut consumers: HashMap<&String, (&String, i64)> = HashMap::new();
+        for ((src, dst), v) in app
+            .topology_srcs
+            .iter()
+            .zip(app.topology_dsts.iter())
+            .zip(app.topology_edge_value.iter())
+        {
+            if first_node.contains_key(src) {
+                producers.insert(dst, (src, v.abs()));
+            } else if first_node.contains_key(dst) {
+                consumers.insert(src, (dst, v.abs()));
+            }
+        }
+        for c in &app.channels_identifiers {
+            if let (Some((src, prod)), Some((dst, cons))) = (producers.get(c), consumers.get(c)) {
+                if *prod == 0 || *cons == 0 {
+                    continue;
+                }
+                let bits =
+                    (*cons as u64 * app.channel_token_sizes.get(c).copied().unwrap_or(0)) as f64;
+                let communication = match self.channels_to_memory_mapping.get(c) {
+                    Some(mem) => {
+                        self.transfer_time(c, processor_of[src], mem, bits)?
+                            + self.transfer_time(c, mem, processor_of[dst], bits)?
+                    }
+                    None => self.transfer_time(c, processor_of[src], processor_of[dst], bits)?,
+                };
+                let initial = app.channel_num_initial_tokens.get(c).copied().unwrap_or(0);
+                let (q_src, q_dst) = (firings(src), firings(dst));
+                for j in 0..q_dst {
+                    for n in (j * cons)..((j + 1) * cons) {
+                        // the firing, counted from the start of this iteration, that produced the n-th consumed token
+                        let g = (n - initial).div_euclid(*prod);
+                        let back = -g.div_euclid(q_src);
+                        let k = g + back * q_src;
+                        add_edge(
+                            first_node[src] + k as usize,
+                            first_node[dst] + j as usize,
+                            durations[src] + communication,
+                            back as u64,
+                        );
+                    }
+                }
+            }
+        }
+        // the dependencies given by the super loops
+        for (sched, order) in &self.super_loop_schedules {
+            let mut seen: HashMap<&String, i64> = HashMap::new();
+            let mut nodes: Vec<(usize, f64)> = Vec::new();
+            for a in order {
+                let k = seen.entry(a).or_insert(0);
+                if *k < firings(a) {
+                    nodes.push((first_node.get(a)? + *k as usize, durations[a]));
+                }
+                *k += 1;
+            }
+            // firings missing from the super loop are executed at its end
+            for a in &app.actors_identifiers {
+                if self.processes_to_runtime_scheduling.get(a) == Some(sched) {
+                    for k in seen.get(a).copied().unwrap_or(0)..firings(a) {
+                        nodes.push((first_node[a] + k as usize, durations[a]));
+                    }
+                }
+            }
+            for (i, (v, w)) in nodes.iter().enumerate() {
+                let (next, tokens) = if i + 1 < nodes.len() {
+                    (nodes[i + 1].0, 0)
+                } else {
+                    (nodes[0].0, 1)
+                };
+                add_edge(*v, next, *w, tokens);
+            }
+        }
+        // every firing depends on the same firing of the previous iteration
+        for a in &app.actors_identifiers {
+            for k in 0..firings(a) as usize {
+                add_edge(first_node[a] + k, first_node[a] + k, durations[a], 1);
+            }
         }
+        let edges: Vec<(usize, usize, f64, u64)> = edges
+            .into_iter()
+            .map(|((src, dst, t), w)| (src, dst, w, t))
+            .collect();
+        analysis::maximum_cycle_mean(num_nodes, &edges)
     }
-}
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
-pub struct PartitionedTiledMulticore {
-    pub hardware: TiledMultiCore,
-    pub runtimes: RuntimesAndProcessors,
+    /// Computes the throughput, in firings per second, of every actor under the self-timed execution of the decisions taken.
+    ///
+    /// Returns an empty map if the period cannot be computed. See [SDFToTiledMultiCore::self_timed_period].
+    pub fn actor_throughputs(&self) -> HashMap<String, f64> {
+        match self.self_timed_period() {
+            Some(period) if period > 0.0 => self
+                .sdf_application
+                .actors_identifiers
+                .iter()
+                .map(|a| {
+                    (
+                        a.to_owned(),
+                        self.sdf_application
+                            .repetition_vector
+                            .get(a)
+                            .copied()
+                            .unwrap_or(1) as f64
+                            / period,
+                    )
+                })
+                .collect(),
+            _ => HashMap::new(),
+        }
+    }
 }
 
-impl DecisionModel for PartitionedTiledMulticore {
+impl DecisionModel for SDFToTiledMultiCore {
     fn body_as_json(&self) -> Option<String> {
-        serde_json::to_string(self).ok()
+        idesyde_core::encode_body_as_json(self)
     }
 
     fn body_as_msgpack(&self) -> Option<Vec<u8>> {
-        rmp_serde::to_vec(self).ok()
+        idesyde_core::encode_body_as_msgpack(self)
     }
 
     fn body_as_cbor(&self) -> Option<Vec<u8>> {
-        let mut b: Vec<u8> = Vec::new();
-        if let Ok(_) = ciborium::into_writer(self, &mut b) {
-            Some(b)
-        } else {
-            None
-        }
+        idesyde_core::encode_body_as_cbor(self)
     }
 
     fn unique_identifier(&self) -> String {
-        "PartitionedTiledMulticore".to_string()
+        "SDFToTiledMultiCore".to_string()
+    }
+
+    /// The self-timed period of the solution and the number of processors it uses, if the decisions are complete.
+    fn objectives(&self) -> HashMap<String, f64> {
+        match self.self_timed_period() {
+            Some(period) => {
+                let used: HashSet<&String> =
+                    self.processes_to_runtime_scheduling.values().collect();
+                HashMap::from([
+                    ("period".to_string(), period),
+                    ("used_processors".to_string(), used.len() as f64),
+                ])
+            }
+            None => HashMap::new(),
+        }
     }
 
     fn header(&self) -> DecisionModelHeader {
         let mut elems: HashSet<String> = HashSet::new();
-        elems.extend(
-            self.hardware
-                .header()
-                .covered_elements
-                .iter()
-                .map(|x| x.to_owned()),
-        );
-        elems.extend(
-            self.runtimes
-                .header()
-                .covered_elements
-                .iter()
-                .map(|x| x.to_owned()),
-        );
+        let mut rels: HashSet<LabelledArcWithPorts> = HashSet::new();
+        let sdf_header = self.sdf_application.header();
+        let platform_header = self.platform.header();
+        elems.extend(sdf_header.covered_elements);
+        elems.extend(platform_header.covered_elements);
+        rels.extend(sdf_header.covered_relations);
+        rels.extend(platform_header.covered_relations);
+        for (a, sched) in &self.processes_to_runtime_scheduling {
+            rels.insert(LabelledArcWithPorts::new("scheduling", a, "", sched, ""));
+        }
+        for (a, mem) in &self.processes_to_memory_mapping {
+            rels.insert(LabelledArcWithPorts::new("mapping", a, "", mem, ""));
+        }
+        for (c, mem) in &self.channels_to_memory_mapping {
+            rels.insert(LabelledArcWithPorts::new("mapping", c, "", mem, ""));
+        }
+        for (c, slots) in &self.channels_slot_allocations {
+            for (ce, _) in slots.iter().filter(|(_, v)| v.iter().any(|b| *b)) {
+                rels.insert(LabelledArcWithPorts::new("allocation", c, "", ce, ""));
+            }
+        }
         DecisionModelHeader {
             category: self.unique_identifier(),
             body_path: None,
+            body_paths: HashMap::new(),
             covered_elements: elems.into_iter().collect(),
+            objectives: HashMap::new(),
+            covered_relations: rels.into_iter().collect(),
         }
     }
 }
 
-pub fn identify_partitioned_tiled_multicore(
+/// Combines every analysed [SDFApplication] with every [PartitionedTiledMulticore] into a [SDFToTiledMultiCore]
+/// with no decisions taken yet.
+///
+/// Only SDF applications with a repetition vector are combined, as the others are inconsistent or deadlock.
+pub fn identify_sdf_to_tiled_multicore(
     _design_models: &Vec<Box<dyn DesignModel>>,
     decision_models: &Vec<Box<dyn DecisionModel>>,
 ) -> Vec<Box<dyn DecisionModel>> {
     let mut new_models = Vec::new();
-    for m2 in decision_models {
-        if let Some(runt) = m2.downcast_ref::<RuntimesAndProcessors>() {
-            let same_number = runt.processors.len() == runt.runtimes.len();
-            let one_scheduler_per_proc = runt
-                .processors
-                .iter()
-                .all(|p| runt.runtime_host.values().find(|y| y == &p).is_some());
-            let one_proc_per_scheduler = runt.runtimes.iter().all(|s| {
-                runt.processor_affinities
-                    .values()
-                    .find(|y| y == &s)
-                    .is_some()
-            });
-            if same_number && one_proc_per_scheduler && one_scheduler_per_proc {
-                for m1 in decision_models {
-                    if let Some(plat) = m1.downcast_ref::<TiledMultiCore>() {
-                        let potential = Box::new(PartitionedTiledMulticore {
-                            hardware: plat.to_owned(),
-                            runtimes: runt.to_owned(),
-                        });
-                        let upcast = potential as Box<dyn DecisionModel>;
-                        if !decision_models.contains(&upcast) {
-                            new_models.push(upcast);
-                        }
+    for m1 in decision_models {
+        if let Some(app) = m1.downcast_ref::<SDFApplication>() {
+            if app.repetition_vector.is_empty() {
+                continue;
+            }
+            for m2 in decision_models {
+                if let Some(plat) = m2.downcast_ref::<PartitionedTiledMulticore>() {
+                    let potential = Box::new(SDFToTiledMultiCore {
+                        sdf_application: app.to_owned(),
+                        platform: plat.to_owned(),
+                        processes_to_runtime_scheduling: HashMap::new(),
+                        processes_to_memory_mapping: HashMap::new(),
+                        super_loop_schedules: HashMap::new(),
+                        channels_to_memory_mapping: HashMap::new(),
+                        channels_slot_allocations: HashMap::new(),
+                    });
+                    let upcast = potential as Box<dyn DecisionModel>;
+                    if !decision_models.contains(&upcast) {
+                        new_models.push(upcast);
                     }
                 }
             }
@@ -336,3 +605,658 @@ pub fn identify_partitioned_tiled_multicore(
     }
     new_models
 }
+
+/// Identifies a [SDFApplication] out of all [Sdf3DesignModel]s given as inputs.
+///
+/// Every SDF3 channel becomes a channel of the decision model, connected to its source actor with the production
+/// rate of the source port and to its destination actor with the consumption rate of the destination port.
+/// The execution times of each processor type become the computational needs of the actor, under the
+/// processor type name and the generic `all` operation, so that they can be matched to platform provisions.
+///
+/// The throughput constraint of a SDF3 graph counts iterations of that graph, so every actor of the graph gets
+/// it multiplied by its own number of firings per iteration. Graphs reusing the name of an actor or channel of
+/// another graph are left out, since their elements could not be told apart; the same graph given twice is taken once.
+pub fn identify_sdf_from_sdf3(
+    design_models: &Vec<Box<dyn DesignModel>>,
+    _decision_models: &Vec<Box<dyn DecisionModel>>,
+) -> Vec<Box<dyn DecisionModel>> {
+    let sdf3_models: Vec<&Sdf3DesignModel> = design_models
+        .iter()
+        .flat_map(|m| m.downcast_ref::<Sdf3DesignModel>())
+        .collect();
+    if sdf3_models.is_empty() {
+        return Vec::new();
+    }
+    let mut sdf = SDFApplication {
+        actors_identifiers: Vec::new(),
+        channels_identifiers: Vec::new(),
+        topology_srcs: Vec::new(),
+        topology_dsts: Vec::new(),
+        topology_edge_value: Vec::new(),
+        actor_sizes: HashMap::new(),
+        actor_computational_needs: HashMap::new(),
+        channel_num_initial_tokens: HashMap::new(),
+        channel_token_sizes: HashMap::new(),
+        minimum_actor_throughputs: HashMap::new(),
+        repetition_vector: HashMap::new(),
+        topological_and_heavy_job_ordering: Vec::new(),
+    };
+    let mut merged: Vec<&Sdf3DesignModel> = Vec::new();
+    let mut names: HashSet<&String> = HashSet::new();
+    for m in sdf3_models {
+        if merged.contains(&m) {
+            continue;
+        }
+        let reused: Vec<&str> = m
+            .actors
+            .iter()
+            .chain(m.channels.iter())
+            .filter(|n| names.contains(n))
+            .map(|n| n.as_str())
+            .collect();
+        if !reused.is_empty() {
+            warn!(
+                "SDF3 graph {} is left out, since it reuses the actor or channel names {} of another graph.",
+                m.graph_name,
+                reused.join(", ")
+            );
+            continue;
+        }
+        names.extend(m.actors.iter().chain(m.channels.iter()));
+        merged.push(m);
+        for a in &m.actors {
+            sdf.actors_identifiers.push(a.to_owned());
+            sdf.actor_sizes.insert(a.to_owned(), m.actor_state_size(a));
+            let needs: HashMap<String, HashMap<String, u64>> = m
+                .actors_execution_times
+                .get(a)
+                .map(|times| {
+                    times
+                        .iter()
+                        .map(|(proc_type, t)| {
+                            (
+                                proc_type.to_owned(),
+                                HashMap::from([("all".to_string(), *t)]),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            sdf.actor_computational_needs.insert(a.to_owned(), needs);
+        }
+        for (i, c) in m.channels.iter().enumerate() {
+            sdf.channels_identifiers.push(c.to_owned());
+            sdf.topology_srcs.push(m.channels_src[i].to_owned());
+            sdf.topology_dsts.push(c.to_owned());
+            sdf.topology_edge_value
+                .push(m.port_rate(&m.channels_src[i], &m.channels_src_port[i]) as i64);
+            sdf.topology_srcs.push(c.to_owned());
+            sdf.topology_dsts.push(m.channels_dst[i].to_owned());
+            sdf.topology_edge_value
+                .push(m.port_rate(&m.channels_dst[i], &m.channels_dst_port[i]) as i64);
+            sdf.channel_num_initial_tokens.insert(
+                c.to_owned(),
+                m.channels_initial_tokens.get(c).copied().unwrap_or(0) as i64,
+            );
+            sdf.channel_token_sizes.insert(
+                c.to_owned(),
+                m.channels_token_sizes.get(c).copied().unwrap_or(0),
+            );
+        }
+    }
+    match analyse_sdf_application(sdf) {
+        Some(mut analysed) => {
+            for m in merged {
+                if let Some(th) = m.throughput_constraint {
+                    for a in &m.actors {
+                        let firings = analysed.repetition_vector.get(a).copied().unwrap_or(1);
+                        analysed
+                            .minimum_actor_throughputs
+                            .insert(a.to_owned(), th * firings as f64);
+                    }
+                }
+            }
+            vec![Box::new(analysed) as Box<dyn DecisionModel>]
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Fills in the repetition vector and the topological and heavy job ordering of a [SDFApplication].
+///
+/// Returns `None` if the SDF graphs are inconsistent, deadlock or have a repetition vector
+/// that does not fit in 64-bit integers.
+pub fn analyse_sdf_application(mut sdf: SDFApplication) -> Option<SDFApplication> {
+    match analysis::compute_repetition_vector(
+        &sdf.actors_identifiers,
+        &sdf.topology_srcs,
+        &sdf.topology_dsts,
+        &sdf.topology_edge_value,
+    ) {
+        None => {
+            warn!("SDF graphs have rates too large to compute their repetition vector.");
+            None
+        }
+        Some(Ok(repetition_vector)) => {
+            match analysis::compute_topological_and_heavy_job_ordering(
+                &sdf.actors_identifiers,
+                &sdf.topology_srcs,
+                &sdf.topology_dsts,
+                &sdf.topology_edge_value,
+                &sdf.channel_num_initial_tokens,
+                &sdf.channel_token_sizes,
+                &repetition_vector,
+            ) {
+                Ok(ordering) => {
+                    sdf.repetition_vector = repetition_vector;
+                    sdf.topological_and_heavy_job_ordering = ordering;
+                    Some(sdf)
+                }
+                Err(blocked) => {
+                    warn!(
+                        "SDF graphs deadlock with the given initial tokens. Blocked actors: {}",
+                        blocked.join(", ")
+                    );
+                    None
+                }
+            }
+        }
+        Some(Err(cycle)) => {
+            warn!(
+                "SDF graphs are inconsistent. Offending cycle: {}",
+                cycle.join(" -> ")
+            );
+            None
+        }
+    }
+}
+
+fn read_design_model(path: &std::path::Path) -> Option<Box<dyn DesignModel>> {
+    let is_xml = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("xml"))
+        .unwrap_or(false);
+    if is_xml {
+        if let Ok(s) = std::fs::read_to_string(path) {
+            return Sdf3DesignModel::from_xml_str(&s).map(|m| Box::new(m) as Box<dyn DesignModel>);
+        }
+    }
+    None
+}
+
+fn decision_header_to_model(header: &DecisionModelHeader) -> Option<Box<dyn DecisionModel>> {
+    match header.category.as_str() {
+        "SDFApplication" => load_decision_model::<SDFApplication>(header)
+            .map(|m| Box::new(m) as Box<dyn DecisionModel>),
+        "TiledMultiCore" => load_decision_model::<TiledMultiCore>(header)
+            .map(|m| Box::new(m) as Box<dyn DecisionModel>),
+        "RuntimesAndProcessors" => load_decision_model::<RuntimesAndProcessors>(header)
+            .map(|m| Box::new(m) as Box<dyn DecisionModel>),
+        "PartitionedTiledMulticore" => load_decision_model::<PartitionedTiledMulticore>(header)
+            .map(|m| Box::new(m) as Box<dyn DecisionModel>),
+        "SDFToTiledMultiCore" => load_decision_model::<SDFToTiledMultiCore>(header)
+            .map(|m| Box::new(m) as Box<dyn DecisionModel>),
+        _ => None,
+    }
+}
+
+/// The identification module of the common decision models, to be run standalone or linked into the orchestrator.
+pub fn common_identification_module() -> StandaloneIdentificationModule {
+    StandaloneIdentificationModule::new(
+        "CommonIdentificationModule",
+        vec![
+            MarkedIdentificationRule::DesignModelOnlyIdentificationRule(identify_sdf_from_sdf3),
+            MarkedIdentificationRule::DecisionModelOnlyIdentificationRule(
+                identify_partitioned_tiled_multicore,
+            ),
+            MarkedIdentificationRule::DecisionModelOnlyIdentificationRule(
+                identify_sdf_to_tiled_multicore,
+            ),
+        ],
+        Vec::new(),
+        read_design_model,
+        |_x, _p| None,
+        decision_header_to_model,
+        HashSet::from([
+            serde_json::to_string_pretty(&schema_for!(SDFApplication)).unwrap(),
+            serde_json::to_string_pretty(&schema_for!(TiledMultiCore)).unwrap(),
+            serde_json::to_string_pretty(&schema_for!(RuntimesAndProcessors)).unwrap(),
+            serde_json::to_string_pretty(&schema_for!(PartitionedTiledMulticore)).unwrap(),
+            serde_json::to_string_pretty(&schema_for!(SDFToTiledMultiCore)).unwrap(),
+        ]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A SDF application, yet to be analysed, whose channels are given as
+    /// `(source, production, channel, destination, consumption, initial tokens)`.
+    /// Every actor needs `cycles` of the generic `all` operation.
+    fn sdf_graph(
+        actors: &[(&str, u64)],
+        channels: &[(&str, i64, &str, &str, i64, i64)],
+    ) -> SDFApplication {
+        let mut sdf = SDFApplication {
+            actors_identifiers: actors.iter().map(|(a, _)| a.to_string()).collect(),
+            channels_identifiers: channels.iter().map(|c| c.2.to_string()).collect(),
+            topology_srcs: Vec::new(),
+            topology_dsts: Vec::new(),
+            topology_edge_value: Vec::new(),
+            actor_sizes: HashMap::new(),
+            actor_computational_needs: actors
+                .iter()
+                .map(|(a, cycles)| {
+                    (
+                        a.to_string(),
+                        HashMap::from([(
+                            "group".to_string(),
+                            HashMap::from([("all".to_string(), *cycles)]),
+                        )]),
+                    )
+                })
+                .collect(),
+            channel_num_initial_tokens: channels.iter().map(|c| (c.2.to_string(), c.5)).collect(),
+            channel_token_sizes: channels.iter().map(|c| (c.2.to_string(), 8)).collect(),
+            minimum_actor_throughputs: HashMap::new(),
+            repetition_vector: HashMap::new(),
+            topological_and_heavy_job_ordering: Vec::new(),
+        };
+        for (src, prod, c, dst, cons, _) in channels {
+            sdf.topology_srcs.push(src.to_string());
+            sdf.topology_dsts.push(c.to_string());
+            sdf.topology_edge_value.push(*prod);
+            sdf.topology_srcs.push(c.to_string());
+            sdf.topology_dsts.push(dst.to_string());
+            sdf.topology_edge_value.push(*cons);
+        }
+        sdf
+    }
+
+    fn sdf_application(
+        actors: &[(&str, u64)],
+        channels: &[(&str, i64, &str, &str, i64, i64)],
+    ) -> SDFApplication {
+        analyse_sdf_application(sdf_graph(actors, channels))
+            .expect("the test graphs should be consistent and live")
+    }
+
+    #[test]
+    fn consistent_graphs_get_their_repetition_vector_and_ordering() {
+        let mut sdf = sdf_graph(
+            &[("a", 1), ("b", 1), ("c", 1)],
+            &[("a", 2, "ab", "b", 3, 0), ("b", 1, "bc", "c", 2, 0)],
+        );
+        sdf.minimum_actor_throughputs.insert("c".to_string(), 1.0);
+        let analysed = analyse_sdf_application(sdf).expect("the graph should be analysed");
+        assert_eq!(
+            analysed.repetition_vector,
+            HashMap::from([
+                ("a".to_string(), 3),
+                ("b".to_string(), 2),
+                ("c".to_string(), 1)
+            ])
+        );
+        assert_eq!(
+            analysed.topological_and_heavy_job_ordering,
+            vec!["a", "a", "b", "a", "b", "c"]
+        );
+        assert_eq!(analysed.minimum_actor_throughputs.get("c"), Some(&1.0));
+    }
+
+    #[test]
+    fn disconnected_graphs_are_balanced_separately() {
+        let analysed = sdf_application(
+            &[("a", 1), ("b", 1), ("c", 1), ("d", 1)],
+            &[("a", 4, "ab", "b", 2, 0), ("c", 3, "cd", "d", 9, 0)],
+        );
+        assert_eq!(
+            analysed.repetition_vector,
+            HashMap::from([
+                ("a".to_string(), 1),
+                ("b".to_string(), 2),
+                ("c".to_string(), 3),
+                ("d".to_string(), 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn inconsistent_graphs_are_not_analysed() {
+        let sdf = sdf_graph(
+            &[("a", 1), ("b", 1)],
+            &[("a", 1, "ab", "b", 1, 0), ("b", 2, "ba", "a", 1, 1)],
+        );
+        assert!(matches!(
+            analysis::compute_repetition_vector(
+                &sdf.actors_identifiers,
+                &sdf.topology_srcs,
+                &sdf.topology_dsts,
+                &sdf.topology_edge_value,
+            ),
+            Some(Err(cycle)) if cycle.first() == cycle.last() && cycle.len() == 5
+        ));
+        assert_eq!(analyse_sdf_application(sdf), None);
+    }
+
+    #[test]
+    fn deadlocking_graphs_are_not_analysed() {
+        let sdf = sdf_graph(
+            &[("a", 1), ("b", 1)],
+            &[("a", 1, "ab", "b", 1, 0), ("b", 1, "ba", "a", 1, 0)],
+        );
+        assert_eq!(analyse_sdf_application(sdf), None);
+        let live = sdf_graph(
+            &[("a", 1), ("b", 1)],
+            &[("a", 1, "ab", "b", 1, 0), ("b", 1, "ba", "a", 1, 1)],
+        );
+        assert!(analyse_sdf_application(live).is_some());
+    }
+
+    #[test]
+    fn overflowing_rates_are_not_analysed() {
+        // the rates of the chain multiply to more than what fits in 64 bits
+        let primes = [1_000_000_007, 998_244_353, 1_000_000_009];
+        let actors: Vec<(String, u64)> =
+            (0..=primes.len()).map(|i| (format!("a{}", i), 1)).collect();
+        let channels: Vec<(String, String, String)> = (0..primes.len())
+            .map(|i| (format!("a{}", i), format!("c{}", i), format!("a{}", i + 1)))
+            .collect();
+        let sdf = sdf_graph(
+            &actors
+                .iter()
+                .map(|(a, n)| (a.as_str(), *n))
+                .collect::<Vec<(&str, u64)>>(),
+            &channels
+                .iter()
+                .zip(primes)
+                .map(|((src, c, dst), p)| (src.as_str(), p, c.as_str(), dst.as_str(), 1, 0))
+                .collect::<Vec<(&str, i64, &str, &str, i64, i64)>>(),
+        );
+        assert_eq!(analyse_sdf_application(sdf), None);
+        let extreme = sdf_graph(&[("a", 1), ("b", 1)], &[("a", i64::MIN, "ab", "b", 1, 0)]);
+        assert_eq!(analyse_sdf_application(extreme), None);
+    }
+
+    /// Two processors `p1` and `p2` at 1 kHz, each with its own runtime `r1` and `r2`, and a `bus` going from
+    /// `p1` to `p2` with 1 kbit/s per slot. Data going from `p2` to `p1` is taken to cross the platform instantly.
+    fn two_processors() -> PartitionedTiledMulticore {
+        let processors = vec!["p1".to_string(), "p2".to_string()];
+        PartitionedTiledMulticore {
+            hardware: TiledMultiCore {
+                processors: processors.clone(),
+                memories: Vec::new(),
+                network_interfaces: Vec::new(),
+                routers: vec!["bus".to_string()],
+                interconnect_topology_srcs: Vec::new(),
+                interconnect_topology_dsts: Vec::new(),
+                processors_provisions: processors
+                    .iter()
+                    .map(|pe| {
+                        (
+                            pe.to_owned(),
+                            HashMap::from([(
+                                "mode".to_string(),
+                                HashMap::from([("all".to_string(), 1.0)]),
+                            )]),
+                        )
+                    })
+                    .collect(),
+                processors_frequency: processors.iter().map(|pe| (pe.to_owned(), 1000)).collect(),
+                tile_memory_sizes: HashMap::new(),
+                communication_elements_max_channels: HashMap::from([("bus".to_string(), 1)]),
+                communication_elements_bit_per_sec_per_channel: HashMap::from([(
+                    "bus".to_string(),
+                    1000.0,
+                )]),
+                pre_computed_paths: HashMap::from([(
+                    "p1".to_string(),
+                    HashMap::from([("p2".to_string(), vec!["bus".to_string()])]),
+                )]),
+            },
+            runtimes: RuntimesAndProcessors {
+                runtimes: vec!["r1".to_string(), "r2".to_string()],
+                processors,
+                runtime_host: HashMap::from([
+                    ("r1".to_string(), "p1".to_string()),
+                    ("r2".to_string(), "p2".to_string()),
+                ]),
+                processor_affinities: HashMap::from([
+                    ("p1".to_string(), "r1".to_string()),
+                    ("p2".to_string(), "r2".to_string()),
+                ]),
+                is_bare_metal: vec![true, true],
+                is_fixed_priority: vec![false, false],
+                is_earliest_deadline_first: vec![false, false],
+                is_cyclic_executive: vec![true, true],
+            },
+        }
+    }
+
+    fn mapped(
+        sdf_application: SDFApplication,
+        scheduling: &[(&str, &str)],
+        super_loops: &[(&str, &[&str])],
+    ) -> SDFToTiledMultiCore {
+        SDFToTiledMultiCore {
+            channels_slot_allocations: sdf_application
+                .channels_identifiers
+                .iter()
+                .map(|c| {
+                    (
+                        c.to_owned(),
+                        HashMap::from([("bus".to_string(), vec![true])]),
+                    )
+                })
+                .collect(),
+            sdf_application,
+            platform: two_processors(),
+            processes_to_runtime_scheduling: scheduling
+                .iter()
+                .map(|(a, r)| (a.to_string(), r.to_string()))
+                .collect(),
+            processes_to_memory_mapping: HashMap::new(),
+            super_loop_schedules: super_loops
+                .iter()
+                .map(|(r, order)| (r.to_string(), order.iter().map(|a| a.to_string()).collect()))
+                .collect(),
+            channels_to_memory_mapping: HashMap::new(),
+        }
+    }
+
+    fn assert_period(model: &SDFToTiledMultiCore, expected: f64) {
+        let period = model
+            .self_timed_period()
+            .expect("the period should be computed");
+        assert!(
+            (period - expected).abs() <= 1e-9,
+            "expected a period of {} but found {}",
+            expected,
+            period
+        );
+    }
+
+    #[test]
+    fn a_single_actor_is_as_fast_as_its_execution() {
+        let model = mapped(
+            sdf_application(&[("a", 100)], &[]),
+            &[("a", "r1")],
+            &[("r1", &["a"])],
+        );
+        assert_period(&model, 0.1);
+        assert_eq!(model.actor_throughputs().get("a").copied(), Some(10.0));
+    }
+
+    #[test]
+    fn actors_sharing_a_processor_run_one_after_the_other() {
+        let model = mapped(
+            sdf_application(&[("a", 100), ("b", 200)], &[("a", 1, "ab", "b", 1, 0)]),
+            &[("a", "r1"), ("b", "r1")],
+            &[("r1", &["a", "b"])],
+        );
+        assert_period(&model, 0.3);
+    }
+
+    #[test]
+    fn every_firing_of_a_multirate_actor_is_scheduled() {
+        let model = mapped(
+            sdf_application(&[("a", 100), ("b", 200)], &[("a", 2, "ab", "b", 1, 0)]),
+            &[("a", "r1"), ("b", "r1")],
+            &[("r1", &["a", "b"])],
+        );
+        // the second firing of b is missing from the super loop, so it runs at its end
+        assert_period(&model, 0.5);
+    }
+
+    #[test]
+    fn pipelined_actors_are_as_fast_as_the_slowest() {
+        let model = mapped(
+            sdf_application(&[("a", 100), ("b", 200)], &[("a", 1, "ab", "b", 1, 0)]),
+            &[("a", "r1"), ("b", "r2")],
+            &[("r1", &["a"]), ("r2", &["b"])],
+        );
+        assert_period(&model, 0.2);
+    }
+
+    #[test]
+    fn feedback_loops_include_the_communication_time() {
+        let model = mapped(
+            sdf_application(
+                &[("a", 100), ("b", 200)],
+                &[("a", 1, "ab", "b", 1, 0), ("b", 1, "ba", "a", 1, 1)],
+            ),
+            &[("a", "r1"), ("b", "r2")],
+            &[("r1", &["a"]), ("r2", &["b"])],
+        );
+        // both channels reserve the only slot of the bus, so a token of 8 bits takes 16 ms from p1 to p2
+        assert_period(&model, 0.316);
+    }
+
+    #[test]
+    fn channels_parallel_to_the_super_loop_are_kept() {
+        let mut sdf = sdf_application(&[("a", 100), ("b", 100)], &[("a", 1, "ab", "b", 1, 2)]);
+        sdf.channel_token_sizes.insert("ab".to_string(), 800);
+        let mut model = mapped(sdf, &[("a", "r1"), ("b", "r1")], &[("r1", &["b", "a"])]);
+        // the tokens of ab go through the memory of p2 and take 800 ms to cross the bus, and although
+        // the super loop also goes from a to b looking back fewer iterations, it does not hide that time
+        model
+            .channels_to_memory_mapping
+            .insert("ab".to_string(), "p2".to_string());
+        assert_period(&model, 0.5);
+    }
+
+    #[test]
+    fn feedback_loops_without_tokens_deadlock() {
+        let mut sdf = sdf_graph(
+            &[("a", 100), ("b", 200)],
+            &[("a", 1, "ab", "b", 1, 0), ("b", 1, "ba", "a", 1, 0)],
+        );
+        sdf.repetition_vector = HashMap::from([("a".to_string(), 1), ("b".to_string(), 1)]);
+        let model = mapped(
+            sdf,
+            &[("a", "r1"), ("b", "r2")],
+            &[("r1", &["a"]), ("r2", &["b"])],
+        );
+        assert_eq!(model.self_timed_period(), None);
+        assert!(model.actor_throughputs().is_empty());
+    }
+
+    #[test]
+    fn incomplete_decisions_have_no_period() {
+        let sdf = sdf_application(&[("a", 100), ("b", 200)], &[("a", 1, "ab", "b", 1, 0)]);
+        let unscheduled = mapped(sdf.clone(), &[("a", "r1")], &[("r1", &["a"])]);
+        assert_eq!(unscheduled.self_timed_period(), None);
+        let mut without_slots = mapped(
+            sdf,
+            &[("a", "r1"), ("b", "r2")],
+            &[("r1", &["a"]), ("r2", &["b"])],
+        );
+        without_slots.channels_slot_allocations.clear();
+        assert_eq!(without_slots.self_timed_period(), None);
+    }
+
+    /// A SDF3 graph where `src` produces 2 tokens into `channel` for every token `dst` consumes.
+    fn sdf3_graph(
+        name: &str,
+        src: &str,
+        channel: &str,
+        dst: &str,
+        throughput: Option<f64>,
+    ) -> Box<dyn DesignModel> {
+        let time_constraints = throughput
+            .map(|th| {
+                format!(
+                    "<timeConstraints><throughput>{}</throughput></timeConstraints>",
+                    th
+                )
+            })
+            .unwrap_or_default();
+        let xml = format!(
+            r#"<sdf3 type="sdf" version="1.0">
+  <applicationGraph name="{name}">
+    <sdf name="{name}" type="G">
+      <actor name="{src}" type="A"><port name="out" type="out" rate="2"/></actor>
+      <actor name="{dst}" type="B"><port name="in" type="in" rate="1"/></actor>
+      <channel name="{channel}" srcActor="{src}" srcPort="out" dstActor="{dst}" dstPort="in"/>
+    </sdf>
+    <sdfProperties>
+      <graphProperties>{time_constraints}</graphProperties>
+    </sdfProperties>
+  </applicationGraph>
+</sdf3>"#
+        );
+        Box::new(Sdf3DesignModel::from_xml_str(&xml).expect("the SDF3 graph should be read"))
+    }
+
+    fn identified_sdf(design_models: &Vec<Box<dyn DesignModel>>) -> SDFApplication {
+        let identified = identify_sdf_from_sdf3(design_models, &Vec::new());
+        assert_eq!(identified.len(), 1);
+        identified[0]
+            .downcast_ref::<SDFApplication>()
+            .expect("a SDF application should be identified")
+            .to_owned()
+    }
+
+    #[test]
+    fn sdf3_throughputs_are_kept_per_graph() {
+        let sdf = identified_sdf(&vec![
+            sdf3_graph("g1", "a1", "c1", "b1", Some(0.5)),
+            sdf3_graph("g2", "a2", "c2", "b2", None),
+            sdf3_graph("g3", "a3", "c3", "b3", Some(4.0)),
+        ]);
+        assert_eq!(
+            sdf.minimum_actor_throughputs,
+            HashMap::from([
+                ("a1".to_string(), 0.5),
+                ("b1".to_string(), 1.0),
+                ("a3".to_string(), 4.0),
+                ("b3".to_string(), 8.0),
+            ])
+        );
+    }
+
+    #[test]
+    fn sdf3_graphs_reusing_names_are_left_out() {
+        let sdf = identified_sdf(&vec![
+            sdf3_graph("g1", "a", "c", "b", Some(1.0)),
+            sdf3_graph("g2", "a", "d", "e", Some(2.0)),
+            sdf3_graph("g3", "f", "c", "g", Some(3.0)),
+            sdf3_graph("g4", "h", "i", "j", None),
+        ]);
+        assert_eq!(sdf.actors_identifiers, vec!["a", "b", "h", "j"]);
+        assert_eq!(sdf.channels_identifiers, vec!["c", "i"]);
+        assert_eq!(sdf.minimum_actor_throughputs.get("a"), Some(&1.0));
+        assert_eq!(sdf.topology_srcs.len(), 4);
+    }
+
+    #[test]
+    fn sdf3_graphs_given_twice_are_taken_once() {
+        let sdf = identified_sdf(&vec![
+            sdf3_graph("g1", "a", "c", "b", None),
+            sdf3_graph("g1", "a", "c", "b", None),
+        ]);
+        assert_eq!(sdf.actors_identifiers, vec!["a", "b"]);
+        assert_eq!(sdf.channels_identifiers, vec!["c"]);
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::{HashMap, HashSet};
+
+use idesyde_core::{
+    headers::{DesignModelHeader, LabelledArcWithPorts},
+    DesignModel,
+};
+use serde::{Deserialize, Serialize};
+
+/// Design model for synchronous dataflow graphs described in the SDF3 XML format.
+///
+/// The model keeps the information of one `applicationGraph` as found in the file: actors, their ports and rates,
+/// channels connecting these ports and their initial tokens, and the instrumentation data contained in the
+/// `sdfProperties` section. The properties are kept per processor type, just like in the SDF3 file, so that
+/// identification rules can decide how to interpret them.
+///
+/// See <https://www.es.ele.tue.nl/sdf3/> for the format description.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Sdf3DesignModel {
+    pub graph_name: String,
+    pub actors: Vec<String>,
+    pub actors_types: HashMap<String, String>,
+    pub actors_ports_rates: HashMap<String, HashMap<String, u64>>,
+    pub actors_ports_directions: HashMap<String, HashMap<String, String>>,
+    pub channels: Vec<String>,
+    pub channels_src: Vec<String>,
+    pub channels_src_port: Vec<String>,
+    pub channels_dst: Vec<String>,
+    pub channels_dst_port: Vec<String>,
+    pub channels_initial_tokens: HashMap<String, u64>,
+    pub channels_token_sizes: HashMap<String, u64>,
+    pub actors_execution_times: HashMap<String, HashMap<String, u64>>,
+    pub actors_state_sizes: HashMap<String, HashMap<String, u64>>,
+    pub actors_default_processor: HashMap<String, String>,
+    pub throughput_constraint: Option<f64>,
+}
+
+impl Sdf3DesignModel {
+    /// Parses the contents of a SDF3 XML file.
+    ///
+    /// Returns `None` if the contents are not a SDF3 application graph, or if any of the channels refer
+    /// to actors or ports that are not declared.
+    pub fn from_xml_str(contents: &str) -> Option<Sdf3DesignModel> {
+        let doc: Sdf3Xml = quick_xml::de::from_str(contents).ok()?;
+        let graph = doc.application_graph;
+        let mut model = Sdf3DesignModel {
+            graph_name: graph.sdf.name.unwrap_or(graph.name),
+            actors: Vec::new(),
+            actors_types: HashMap::new(),
+            actors_ports_rates: HashMap::new(),
+            actors_ports_directions: HashMap::new(),
+            channels: Vec::new(),
+            channels_src: Vec::new(),
+            channels_src_port: Vec::new(),
+            channels_dst: Vec::new(),
+            channels_dst_port: Vec::new(),
+            channels_initial_tokens: HashMap::new(),
+            channels_token_sizes: HashMap::new(),
+            actors_execution_times: HashMap::new(),
+            actors_state_sizes: HashMap::new(),
+            actors_default_processor: HashMap::new(),
+            throughput_constraint: None,
+        };
+        for actor in graph.sdf.actors {
+            let mut rates = HashMap::new();
+            let mut directions = HashMap::new();
+            for port in actor.ports {
+                rates.insert(port.name.to_owned(), port.rate);
+                directions.insert(port.name, port.port_type);
+            }
+            if let Some(t) = actor.actor_type {
+                model.actors_types.insert(actor.name.to_owned(), t);
+            }
+            model
+                .actors_ports_rates
+                .insert(actor.name.to_owned(), rates);
+            model
+                .actors_ports_directions
+                .insert(actor.name.to_owned(), directions);
+            model.actors.push(actor.name);
+        }
+        for channel in graph.sdf.channels {
+            let src_declared = model
+                .actors_ports_rates
+                .get(&channel.src_actor)
+                .map(|ports| ports.contains_key(&channel.src_port))
+                .unwrap_or(false);
+            let dst_declared = model
+                .actors_ports_rates
+                .get(&channel.dst_actor)
+                .map(|ports| ports.contains_key(&channel.dst_port))
+                .unwrap_or(false);
+            if !src_declared || !dst_declared {
+                return None;
+            }
+            model
+                .channels_initial_tokens
+                .insert(channel.name.to_owned(), channel.initial_tokens.unwrap_or(0));
+            model.channels_src.push(channel.src_actor);
+            model.channels_src_port.push(channel.src_port);
+            model.channels_dst.push(channel.dst_actor);
+            model.channels_dst_port.push(channel.dst_port);
+            model.channels.push(channel.name);
+        }
+        if let Some(props) = graph.sdf_properties {
+            for actor_props in props.actor_properties {
+                let mut times = HashMap::new();
+                let mut sizes = HashMap::new();
+                for processor in actor_props.processors {
+                    if processor.default.unwrap_or(false) {
+                        model.actors_default_processor.insert(
+                            actor_props.actor.to_owned(),
+                            processor.processor_type.to_owned(),
+                        );
+                    }
+                    if let Some(t) = processor.execution_time {
+                        times.insert(processor.processor_type.to_owned(), t.time);
+                    }
+                    if let Some(s) = processor.memory.and_then(|m| m.state_size) {
+                        sizes.insert(processor.processor_type, s.max);
+                    }
+                }
+                model
+                    .actors_execution_times
+                    .insert(actor_props.actor.to_owned(), times);
+                model.actors_state_sizes.insert(actor_props.actor, sizes);
+            }
+            for channel_props in props.channel_properties {
+                if let Some(ts) = channel_props.token_size {
+                    model
+                        .channels_token_sizes
+                        .insert(channel_props.channel, ts.sz);
+                }
+            }
+            model.throughput_constraint = props
+                .graph_properties
+                .and_then(|g| g.time_constraints)
+                .and_then(|t| t.throughput);
+        }
+        Some(model)
+    }
+
+    /// The rate of an actor port, or zero if the port is not declared.
+    pub fn port_rate(&self, actor: &str, port: &str) -> u64 {
+        self.actors_ports_rates
+            .get(actor)
+            .and_then(|ports| ports.get(port))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The state size of an actor, taken from its default processor or, if none is marked as default,
+    /// the largest one declared.
+    pub fn actor_state_size(&self, actor: &str) -> u64 {
+        self.actors_state_sizes
+            .get(actor)
+            .map(|sizes| {
+                self.actors_default_processor
+                    .get(actor)
+                    .and_then(|p| sizes.get(p))
+                    .copied()
+                    .unwrap_or_else(|| sizes.values().copied().max().unwrap_or(0))
+            })
+            .unwrap_or(0)
+    }
+}
+
+impl DesignModel for Sdf3DesignModel {
+    fn unique_identifier(&self) -> String {
+        "Sdf3DesignModel".to_string()
+    }
+
+    fn header(&self) -> DesignModelHeader {
+        let mut elems: HashSet<String> = HashSet::new();
+        let mut rels: HashSet<LabelledArcWithPorts> = HashSet::new();
+        elems.extend(self.actors.iter().map(|x| x.to_owned()));
+        elems.extend(self.channels.iter().map(|x| x.to_owned()));
+        for i in 0..self.channels.len() {
+            let c = &self.channels[i];
+            let src = &self.channels_src[i];
+            let src_port = &self.channels_src_port[i];
+            let dst = &self.channels_dst[i];
+            let dst_port = &self.channels_dst_port[i];
+            rels.insert(LabelledArcWithPorts::new(
+                self.port_rate(src, src_port),
+                src,
+                src_port,
+                c,
+                "",
+            ));
+            rels.insert(LabelledArcWithPorts::new(
+                self.port_rate(dst, dst_port),
+                c,
+                "",
+                dst,
+                dst_port,
+            ));
+        }
+        DesignModelHeader {
+            category: self.unique_identifier(),
+            model_paths: Vec::new(),
+            elements: elems.into_iter().collect(),
+            relations: rels.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Sdf3Xml {
+    #[serde(rename = "applicationGraph")]
+    application_graph: ApplicationGraphXml,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplicationGraphXml {
+    #[serde(rename = "@name")]
+    name: String,
+    sdf: SdfXml,
+    #[serde(rename = "sdfProperties")]
+    sdf_properties: Option<SdfPropertiesXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SdfXml {
+    #[serde(rename = "@name")]
+    name: Option<String>,
+    #[serde(rename = "actor", default)]
+    actors: Vec<ActorXml>,
+    #[serde(rename = "channel", default)]
+    channels: Vec<ChannelXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActorXml {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@type")]
+    actor_type: Option<String>,
+    #[serde(rename = "port", default)]
+    ports: Vec<PortXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PortXml {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@type")]
+    port_type: String,
+    #[serde(rename = "@rate", default = "default_rate")]
+    rate: u64,
+}
+
+fn default_rate() -> u64 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelXml {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@srcActor")]
+    src_actor: String,
+    #[serde(rename = "@srcPort")]
+    src_port: String,
+    #[serde(rename = "@dstActor")]
+    dst_actor: String,
+    #[serde(rename = "@dstPort")]
+    dst_port: String,
+    #[serde(rename = "@initialTokens")]
+    initial_tokens: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SdfPropertiesXml {
+    #[serde(rename = "actorProperties", default)]
+    actor_properties: Vec<ActorPropertiesXml>,
+    #[serde(rename = "channelProperties", default)]
+    channel_properties: Vec<ChannelPropertiesXml>,
+    #[serde(rename = "graphProperties")]
+    graph_properties: Option<GraphPropertiesXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActorPropertiesXml {
+    #[serde(rename = "@actor")]
+    actor: String,
+    #[serde(rename = "processor", default)]
+    processors: Vec<ProcessorXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessorXml {
+    #[serde(rename = "@type")]
+    processor_type: String,
+    #[serde(rename = "@default")]
+    default: Option<bool>,
+    #[serde(rename = "executionTime")]
+    execution_time: Option<ExecutionTimeXml>,
+    memory: Option<MemoryXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecutionTimeXml {
+    #[serde(rename = "@time")]
+    time: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MemoryXml {
+    #[serde(rename = "stateSize")]
+    state_size: Option<StateSizeXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StateSizeXml {
+    #[serde(rename = "@max")]
+    max: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelPropertiesXml {
+    #[serde(rename = "@channel")]
+    channel: String,
+    #[serde(rename = "tokenSize")]
+    token_size: Option<TokenSizeXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenSizeXml {
+    #[serde(rename = "@sz")]
+    sz: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphPropertiesXml {
+    #[serde(rename = "timeConstraints")]
+    time_constraints: Option<TimeConstraintsXml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeConstraintsXml {
+    throughput: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = r#"<?xml version="1.0"?>
+<sdf3 type="sdf" version="1.0">
+  <applicationGraph name="app">
+    <sdf name="small" type="G">
+      <actor name="a" type="A">
+        <port name="out" type="out" rate="2"/>
+      </actor>
+      <actor name="b" type="B">
+        <port name="in" type="in" rate="3"/>
+      </actor>
+      <channel name="ab" srcActor="a" srcPort="out" dstActor="b" dstPort="in" initialTokens="1"/>
+    </sdf>
+    <sdfProperties>
+      <actorProperties actor="a">
+        <processor type="slow">
+          <executionTime time="20"/>
+          <memory><stateSize max="64"/></memory>
+        </processor>
+        <processor type="fast" default="true">
+          <executionTime time="10"/>
+          <memory><stateSize max="32"/></memory>
+        </processor>
+      </actorProperties>
+      <channelProperties channel="ab">
+        <tokenSize sz="16"/>
+      </channelProperties>
+      <graphProperties>
+        <timeConstraints><throughput>0.25</throughput></timeConstraints>
+      </graphProperties>
+    </sdfProperties>
+  </applicationGraph>
+</sdf3>"#;
+
+    #[test]
+    fn small_graphs_are_read_with_their_properties() {
+        let m = Sdf3DesignModel::from_xml_str(SMALL).expect("the small graph should be read");
+        assert_eq!(m.graph_name, "small");
+        assert_eq!(m.actors, vec!["a", "b"]);
+        assert_eq!(m.actors_types["b"], "B");
+        assert_eq!(m.port_rate("a", "out"), 2);
+        assert_eq!(m.port_rate("b", "in"), 3);
+        assert_eq!(m.actors_ports_directions["b"]["in"], "in");
+        assert_eq!(m.channels, vec!["ab"]);
+        assert_eq!(m.channels_src_port, vec!["out"]);
+        assert_eq!(m.channels_dst, vec!["b"]);
+        assert_eq!(m.channels_initial_tokens["ab"], 1);
+        assert_eq!(m.channels_token_sizes["ab"], 16);
+        assert_eq!(m.actors_execution_times["a"]["slow"], 20);
+        assert_eq!(m.actor_state_size("a"), 32);
+        assert_eq!(m.actor_state_size("b"), 0);
+        assert_eq!(m.throughput_constraint, Some(0.25));
+    }
+
+    #[test]
+    fn malformed_xml_is_not_read() {
+        assert!(Sdf3DesignModel::from_xml_str("").is_none());
+        assert!(Sdf3DesignModel::from_xml_str(&SMALL.replace("</sdf>", "")).is_none());
+        assert!(Sdf3DesignModel::from_xml_str(&SMALL[..SMALL.len() / 2]).is_none());
+        assert!(Sdf3DesignModel::from_xml_str("<sdf3><other/></sdf3>").is_none());
+    }
+
+    #[test]
+    fn ports_without_rates_produce_and_consume_one_token() {
+        let m = Sdf3DesignModel::from_xml_str(&SMALL.replace(r#" rate="3""#, ""))
+            .expect("the graph should be read");
+        assert_eq!(m.port_rate("a", "out"), 2);
+        assert_eq!(m.port_rate("b", "in"), 1);
+    }
+
+    #[test]
+    fn ports_missing_from_the_graph_are_errors() {
+        let without_port_name = SMALL.replace(r#"name="in" "#, "");
+        assert!(Sdf3DesignModel::from_xml_str(&without_port_name).is_none());
+        let without_channel_port = SMALL.replace(r#" srcPort="out""#, "");
+        assert!(Sdf3DesignModel::from_xml_str(&without_channel_port).is_none());
+        let undeclared_port = SMALL.replace(r#"dstPort="in""#, r#"dstPort="other""#);
+        assert!(Sdf3DesignModel::from_xml_str(&undeclared_port).is_none());
+    }
+
+    #[test]
+    fn graphs_without_graph_properties_have_no_throughput_constraint() {
+        let start = SMALL.find("<graphProperties>").unwrap();
+        let end = SMALL.find("</graphProperties>").unwrap() + "</graphProperties>".len();
+        let without = format!("{}{}", &SMALL[..start], &SMALL[end..]);
+        let m = Sdf3DesignModel::from_xml_str(&without).expect("the graph should be read");
+        assert_eq!(m.throughput_constraint, None);
+        assert_eq!(m.channels_token_sizes["ab"], 16);
+        let empty = SMALL.replace(&SMALL[start..end], "<graphProperties/>");
+        let m = Sdf3DesignModel::from_xml_str(&empty).expect("the graph should be read");
+        assert_eq!(m.throughput_constraint, None);
+    }
+}
@@ -1,67 +1,125 @@
 use proc_macro::TokenStream;
-use quote::{format_ident, quote, ToTokens};
-use syn::{DeriveInput, Field};
+use quote::quote;
+use syn::{Attribute, DeriveInput, Ident, LitStr, Type};
 
-#[proc_macro_derive(DecisionModel, attributes(elems, relations))]
-pub fn derive_decision_model(input: TokenStream) -> TokenStream {
-    // Construct a string representation of the type definition
-    let mut output = TokenStream::new();
-
-    // Parse the string representation
-    let ast: DeriveInput = syn::parse(input).unwrap();
+/// Whether the attribute is `#[name]` or `#[name(...)]`.
+fn is_attribute(a: &Attribute, name: &str) -> bool {
+    a.path().is_ident(name)
+}
 
-    match ast.data {
-        syn::Data::Struct(sdata) => {
-            let mut elem_fields: Vec<&Field> = Vec::new();
-            let mut rels_fields: Vec<&Field> = Vec::new();
-            for f in &sdata.fields {
-                if f.attrs.iter().any(|a| {
-                    a.meta
-                        .path()
-                        .get_ident()
-                        .map(|p| p.to_string() == "elems")
-                        .unwrap_or(false)
-                }) {
-                    elem_fields.push(f);
-                } else if f.attrs.iter().any(|a| {
-                    a.meta
-                        .path()
-                        .get_ident()
-                        .map(|p| p.to_string() == "relations")
-                        .unwrap_or(false)
-                }) {
-                    rels_fields.push(f);
-                }
-            }
-            let elem_names = elem_fields.iter().map(|f| f.ident.as_ref().unwrap());
+/// Whether the type of a field is a map, so that its keys are the ones taken.
+fn is_map(ty: &Type) -> bool {
+    match ty {
+        Type::Path(tp) => tp
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident == "HashMap" || s.ident == "BTreeMap")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
 
-            let uid = ast.ident.to_string();
-            output.extend(TokenStream::from(quote! {
-                impl idesyde_core::DecisionModel for #uid {
-                    fn unique_identifier(&self) -> String {
-                        "#uid".to_string()
+/// Derives [idesyde_core::DecisionModel] for structs that can be serialized with serde.
+///
+/// The category of the decision model is the name of the struct and the bodies are its JSON, MsgPack
+/// and CBOR encodings. The covered elements are given by attributes:
+///
+/// - `#[elems]` on a `Vec` or `HashSet` field covers all its items, and on a `HashMap` field all its keys.
+/// - `#[relations]` on a field of `(source, target)` pairs, or on a `HashMap` from source to target, covers
+///   all these relations, labelled with the name of the field. `#[relations("label")]` sets the label instead.
+/// - `#[elems(method)]` on the struct covers all the elements returned by `method`, and
+///   `#[relations(method)]` all the [idesyde_core::headers::LabelledArcWithPorts] returned by it, for the
+///   ones that cannot be read directly from a field.
+#[proc_macro_derive(DecisionModel, attributes(elems, relations))]
+pub fn derive_decision_model(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).expect("Failed to parse the decision model");
+    let name = &ast.ident;
+    let category = name.to_string();
+    let mut covering = Vec::new();
+    if let syn::Data::Struct(sdata) = &ast.data {
+        for f in &sdata.fields {
+            let field = f
+                .ident
+                .as_ref()
+                .expect("Only structs with named fields can be decision models");
+            for a in &f.attrs {
+                if is_attribute(a, "elems") {
+                    if is_map(&f.ty) {
+                        covering.push(quote! {
+                            elems.extend(self.#field.keys().map(|x| x.to_string()));
+                        });
+                    } else {
+                        covering.push(quote! {
+                            elems.extend(self.#field.iter().map(|x| x.to_string()));
+                        });
                     }
-
-                    fn header(&self) -> idesyde_core::headers::DecisionModelHeader {
-                        let mut elems: Vec<String> = Vec::new();
-                        let mut rels: Vec<idesyde_core::LabelledArcWithPorts> = Vec::new();
-                        #(elems.extend(self.#elem_names.iter().map(|x| x.to_owned()));)"\n"*
-                        DecisionModelHeader {
-                            category: self.unique_identifier(),
-                            body_path: None,
-                            covered_elements: elems,
-                            covered_relations: rels,
+                } else if is_attribute(a, "relations") {
+                    let label = a
+                        .parse_args::<LitStr>()
+                        .map(|l| l.value())
+                        .unwrap_or_else(|_| field.to_string());
+                    covering.push(quote! {
+                        for (src, dst) in self.#field.iter() {
+                            rels.insert(idesyde_core::headers::LabelledArcWithPorts::new(
+                                #label, src, "", dst, "",
+                            ));
                         }
-                    }
+                    });
                 }
-            }));
+            }
         }
-        _ => (),
     }
-    output
-    // Build the impl
-    // let gen = impl_hello_world(&ast);
+    for a in &ast.attrs {
+        if is_attribute(a, "elems") {
+            let method: Ident = a
+                .parse_args()
+                .expect("#[elems(...)] on a struct must name a method");
+            covering.push(quote! {
+                elems.extend(self.#method().into_iter().map(|x| x.to_string()));
+            });
+        } else if is_attribute(a, "relations") {
+            let method: Ident = a
+                .parse_args()
+                .expect("#[relations(...)] on a struct must name a method");
+            covering.push(quote! {
+                rels.extend(self.#method());
+            });
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    TokenStream::from(quote! {
+        impl #impl_generics idesyde_core::DecisionModel for #name #ty_generics #where_clause {
+            fn unique_identifier(&self) -> String {
+                #category.to_string()
+            }
+
+            fn header(&self) -> idesyde_core::headers::DecisionModelHeader {
+                let mut elems: std::collections::HashSet<String> = std::collections::HashSet::new();
+                let mut rels: std::collections::HashSet<idesyde_core::headers::LabelledArcWithPorts> =
+                    std::collections::HashSet::new();
+                #(#covering)*
+                idesyde_core::headers::DecisionModelHeader {
+                    category: self.unique_identifier(),
+                    body_path: None,
+                    body_paths: std::collections::HashMap::new(),
+                    covered_elements: elems.into_iter().collect(),
+                    objectives: std::collections::HashMap::new(),
+                    covered_relations: rels.into_iter().collect(),
+                }
+            }
 
-    // // Return the generated impl
-    // gen.parse().unwrap()
+            fn body_as_json(&self) -> Option<String> {
+                idesyde_core::encode_body_as_json(self)
+            }
+
+            fn body_as_msgpack(&self) -> Option<Vec<u8>> {
+                idesyde_core::encode_body_as_msgpack(self)
+            }
+
+            fn body_as_cbor(&self) -> Option<Vec<u8>> {
+                idesyde_core::encode_body_as_cbor(self)
+            }
+        }
+    })
 }
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// The errors that can happen when reading and writing models, or when talking to modules.
+///
+/// Every error says which file or module caused it, so that the failure can be reported and the
+/// procedure can continue without it.
+#[derive(Debug, Error)]
+pub enum IdesydeError {
+    #[error("Failed to access {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Failed to {action} {} as {format}: {message}", .path.display())]
+    Format {
+        path: PathBuf,
+        action: String,
+        format: String,
+        message: String,
+    },
+    #[error("The body in {} does not follow the schema of {category}: {message}", .path.display())]
+    SchemaMismatch {
+        path: PathBuf,
+        category: String,
+        message: String,
+    },
+    #[error("Module {module} did not follow the protocol: {message}")]
+    ModuleProtocol { module: String, message: String },
+}
+
+impl IdesydeError {
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> IdesydeError {
+        IdesydeError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn encoding(
+        path: impl Into<PathBuf>,
+        format: &str,
+        message: impl ToString,
+    ) -> IdesydeError {
+        IdesydeError::Format {
+            path: path.into(),
+            action: "encode".to_string(),
+            format: format.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn decoding(
+        path: impl Into<PathBuf>,
+        format: &str,
+        message: impl ToString,
+    ) -> IdesydeError {
+        IdesydeError::Format {
+            path: path.into(),
+            action: "decode".to_string(),
+            format: format.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
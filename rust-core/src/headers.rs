@@ -1,68 +1,149 @@
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     hash::Hash,
     path::{Path, PathBuf},
 };
 
-use crate::DesignModel;
-
-// #[derive(Serialize, Clone, Deserialize, Debug)]
-// pub struct LabelledArcWithPorts {
-//     pub src: String,
-//     pub src_port: Option<String>,
-//     pub label: Option<String>,
-//     pub dst: String,
-//     pub dst_port: Option<String>,
-// }
-
-// impl PartialEq<LabelledArcWithPorts> for LabelledArcWithPorts {
-//     fn eq(&self, other: &LabelledArcWithPorts) -> bool {
-//         self.src == other.src
-//             && self.dst == other.dst
-//             && match (&self.src_port, &other.src_port) {
-//                 (Some(a), Some(b)) => a == b,
-//                 (None, None) => true,
-//                 _ => false,
-//             }
-//             && match (&self.dst_port, &other.dst_port) {
-//                 (Some(a), Some(b)) => a == b,
-//                 (None, None) => true,
-//                 _ => false,
-//             }
-//             && match (&self.label, &other.label) {
-//                 (Some(a), Some(b)) => a == b,
-//                 (None, None) => true,
-//                 _ => false,
-//             }
-//     }
-// }
-
-// impl Eq for LabelledArcWithPorts {}
-
-// impl Hash for LabelledArcWithPorts {
-//     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-//         self.src.hash(state);
-//         self.dst.hash(state);
-//         if let Some(a) = &self.src_port {
-//             a.hash(state);
-//         }
-//         if let Some(a) = &self.dst_port {
-//             a.hash(state);
-//         };
-//         if let Some(a) = &self.label {
-//             a.hash(state);
-//         };
-//     }
-// }
+use crate::{DesignModel, IdesydeError};
+
+/// A relation between two elements of a model, possibly through ports and with a label.
+///
+/// Before relations were part of the headers, they were encoded as elements in the form
+/// `label=src:src_port-dst:dst_port`, which can still be read with [LabelledArcWithPorts::from_element].
+#[derive(Serialize, Clone, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LabelledArcWithPorts {
+    pub src: String,
+    pub src_port: Option<String>,
+    pub label: Option<String>,
+    pub dst: String,
+    pub dst_port: Option<String>,
+}
+
+impl LabelledArcWithPorts {
+    /// Creates the relation, where empty labels and ports are taken as absent.
+    pub fn new(
+        label: impl ToString,
+        src: impl ToString,
+        src_port: impl ToString,
+        dst: impl ToString,
+        dst_port: impl ToString,
+    ) -> LabelledArcWithPorts {
+        let non_empty = |s: String| Some(s).filter(|x| !x.is_empty());
+        LabelledArcWithPorts {
+            src: src.to_string(),
+            src_port: non_empty(src_port.to_string()),
+            label: non_empty(label.to_string()),
+            dst: dst.to_string(),
+            dst_port: non_empty(dst_port.to_string()),
+        }
+    }
+
+    /// Reads a relation in the element encoding `label=src:src_port-dst:dst_port`, if `e` is one.
+    pub fn from_element(e: &str) -> Option<LabelledArcWithPorts> {
+        let (label, arc) = e.split_once('=')?;
+        let (src, rest) = arc.split_once(':')?;
+        let (rest, dst_port) = rest.rsplit_once(':')?;
+        let (src_port, dst) = rest.split_once('-')?;
+        if src.is_empty() || dst.is_empty() {
+            return None;
+        }
+        Some(LabelledArcWithPorts::new(
+            label, src, src_port, dst, dst_port,
+        ))
+    }
+}
+
+/// Moves the elements that are encoded relations into `relations`.
+///
+/// The encoding always came together with both ends of the relation as elements, so an element is only taken as
+/// a relation if its source and destination are also elements. This way, elements whose names merely contain
+/// `=`, `:` or `-` are kept as they are.
+fn split_encoded_relations(
+    elements: &mut BTreeSet<String>,
+    relations: &mut BTreeSet<LabelledArcWithPorts>,
+) {
+    let encoded: Vec<(String, LabelledArcWithPorts)> = elements
+        .iter()
+        .filter_map(|e| LabelledArcWithPorts::from_element(e).map(|r| (e.to_owned(), r)))
+        .filter(|(_, r)| elements.contains(&r.src) && elements.contains(&r.dst))
+        .collect();
+    for (e, r) in encoded {
+        elements.remove(&e);
+        relations.insert(r);
+    }
+}
+
+/// Compares two sets by inclusion, in a single pass over both as they are sorted.
+///
+/// `a` is `Greater` than `b` if it is a strict superset of it, `Less` if it is a strict subset, and the sets are
+/// not comparable if neither includes the other.
+pub fn set_inclusion<T: Ord>(a: &BTreeSet<T>, b: &BTreeSet<T>) -> Option<Ordering> {
+    let (mut a_only, mut b_only) = (false, false);
+    let (mut ai, mut bi) = (a.iter().peekable(), b.iter().peekable());
+    loop {
+        match (ai.peek(), bi.peek()) {
+            (Some(x), Some(y)) => match x.cmp(y) {
+                Ordering::Less => {
+                    a_only = true;
+                    ai.next();
+                }
+                Ordering::Greater => {
+                    b_only = true;
+                    bi.next();
+                }
+                Ordering::Equal => {
+                    ai.next();
+                    bi.next();
+                }
+            },
+            (Some(_), None) => {
+                a_only = true;
+                break;
+            }
+            (None, Some(_)) => {
+                b_only = true;
+                break;
+            }
+            (None, None) => break,
+        }
+        if a_only && b_only {
+            break;
+        }
+    }
+    match (a_only, b_only) {
+        (false, false) => Some(Ordering::Equal),
+        (true, false) => Some(Ordering::Greater),
+        (false, true) => Some(Ordering::Less),
+        (true, true) => None,
+    }
+}
+
+/// Combines the inclusion of the elements and of the relations of two headers into the inclusion of the headers.
+fn combine_inclusions(elems: Option<Ordering>, rels: Option<Ordering>) -> Option<Ordering> {
+    match (elems?, rels?) {
+        (Ordering::Equal, o) | (o, Ordering::Equal) => Some(o),
+        (e, r) if e == r => Some(e),
+        _ => None,
+    }
+}
 
 #[derive(Serialize, Clone, Deserialize, Debug)]
 pub struct DesignModelHeader {
     pub category: String,
     pub model_paths: Vec<String>,
-    pub elements: Vec<String>,
-    // pub relations: Vec<LabelledArcWithPorts>,
+    pub elements: BTreeSet<String>,
+    #[serde(default)]
+    pub relations: BTreeSet<LabelledArcWithPorts>,
+}
+
+impl DesignModelHeader {
+    /// Reads the relations that older modules encode as elements, so that all relations are typed.
+    pub fn with_typed_relations(mut self) -> DesignModelHeader {
+        split_encoded_relations(&mut self.elements, &mut self.relations);
+        self
+    }
 }
 
 impl DesignModel for DesignModelHeader {
@@ -77,22 +158,16 @@ impl DesignModel for DesignModelHeader {
 
 impl PartialEq<DesignModelHeader> for DesignModelHeader {
     fn eq(&self, o: &DesignModelHeader) -> bool {
-        self.category == o.category && self.elements == o.elements // && self.relations == o.relations
+        self.category == o.category && self.elements == o.elements && self.relations == o.relations
     }
 }
 
 impl PartialOrd<DesignModelHeader> for DesignModelHeader {
     fn partial_cmp(&self, o: &DesignModelHeader) -> std::option::Option<std::cmp::Ordering> {
-        let superset = o.elements.iter().all(|v| self.elements.contains(v));
-        // && o.relations.iter().all(|v| self.relations.contains(v));
-        let subset = self.elements.iter().all(|v| o.elements.contains(v));
-        // && self.relations.iter().all(|v| o.relations.contains(v));
-        return match (superset, subset) {
-            (true, true) => Some(Ordering::Equal),
-            (true, false) => Some(Ordering::Greater),
-            (false, true) => Some(Ordering::Less),
-            _ => None,
-        };
+        combine_inclusions(
+            set_inclusion(&self.elements, &o.elements),
+            set_inclusion(&self.relations, &o.relations),
+        )
     }
 }
 
@@ -104,9 +179,9 @@ impl Hash for DesignModelHeader {
         for m in &self.elements {
             m.hash(state);
         }
-        // for e in &self.relations {
-        //     e.hash(state);
-        // }
+        for e in &self.relations {
+            e.hash(state);
+        }
     }
 }
 
@@ -114,40 +189,42 @@ impl Hash for DesignModelHeader {
 pub struct DecisionModelHeader {
     pub category: String,
     pub body_path: Option<String>,
-    pub covered_elements: Vec<String>,
-    // pub covered_relations: Vec<LabelledArcWithPorts>,
+    /// The paths of the bodies of the decision model, by their format: "json", "msgpack" or "cbor".
+    #[serde(default)]
+    pub body_paths: HashMap<String, String>,
+    pub covered_elements: BTreeSet<String>,
+    /// The objective values of the decision model, all to be minimised. Only solved decision models have them.
+    #[serde(default)]
+    pub objectives: HashMap<String, f64>,
+    #[serde(default)]
+    pub covered_relations: BTreeSet<LabelledArcWithPorts>,
+}
+
+impl DecisionModelHeader {
+    /// Reads the relations that older modules encode as covered elements, so that all relations are typed.
+    pub fn with_typed_relations(mut self) -> DecisionModelHeader {
+        split_encoded_relations(&mut self.covered_elements, &mut self.covered_relations);
+        self
+    }
 }
 
 impl PartialEq<DecisionModelHeader> for DecisionModelHeader {
     fn eq(&self, o: &DecisionModelHeader) -> bool {
-        self.category == o.category && self.covered_elements == o.covered_elements
-        // && self.covered_relations == o.covered_relations
+        self.category == o.category
+            && self.covered_elements == o.covered_elements
+            && self.covered_relations == o.covered_relations
     }
 }
 
 impl PartialOrd<DecisionModelHeader> for DecisionModelHeader {
     fn partial_cmp(&self, o: &DecisionModelHeader) -> std::option::Option<std::cmp::Ordering> {
-        let superset = o
-            .covered_elements
-            .iter()
-            .all(|v| self.covered_elements.contains(v));
-        // && o.covered_relations
-        //     .iter()
-        //     .all(|v| self.covered_relations.contains(v));
-        let subset = self
-            .covered_elements
-            .iter()
-            .all(|v| o.covered_elements.contains(v));
-        // && self
-        //     .covered_relations
-        //     .iter()
-        //     .all(|v| o.covered_relations.contains(v));
-        return match (superset, subset) {
-            (true, true) => self.body_path.partial_cmp(&o.body_path),
-            (true, false) => Some(Ordering::Greater),
-            (false, true) => Some(Ordering::Less),
-            _ => None,
-        };
+        match combine_inclusions(
+            set_inclusion(&self.covered_elements, &o.covered_elements),
+            set_inclusion(&self.covered_relations, &o.covered_relations),
+        ) {
+            Some(Ordering::Equal) => self.body_path.partial_cmp(&o.body_path),
+            o => o,
+        }
     }
 }
 
@@ -159,9 +236,9 @@ impl Hash for DecisionModelHeader {
         for m in &self.covered_elements {
             m.hash(state);
         }
-        // for e in &self.covered_elements {
-        //     e.hash(state);
-        // }
+        for e in &self.covered_relations {
+            e.hash(state);
+        }
     }
 }
 
@@ -224,26 +301,28 @@ impl PartialOrd<ExplorationBid> for ExplorationBid {
 pub fn load_decision_model_header_from_path(p: &Path) -> Option<DecisionModelHeader> {
     if let Ok(f) = std::fs::File::open(p) {
         // let inb = std::io::BufReader::new(f);
-        if p.extension()
+        let header: Option<DecisionModelHeader> = if p
+            .extension()
             .map(|ext| ext.eq_ignore_ascii_case("msgpack"))
             .unwrap_or(false)
         {
-            // rmp_serde::from_read::<std::fs::File, DecisionModelHeader>(f).expect("Problems");
-            // return None;
-            return rmp_serde::from_read(f).ok();
+            rmp_serde::from_read(f).ok()
         } else if p
             .extension()
             .map(|ext| ext.eq_ignore_ascii_case("cbor"))
             .unwrap_or(false)
         {
-            return ciborium::from_reader(f).ok();
+            ciborium::from_reader(f).ok()
         } else if p
             .extension()
             .map(|ext| ext.eq_ignore_ascii_case("json"))
             .unwrap_or(false)
         {
-            return serde_json::from_reader(f).ok();
-        }
+            serde_json::from_reader(f).ok()
+        } else {
+            None
+        };
+        return header.map(|h| h.with_typed_relations());
     }
     None
 }
@@ -278,42 +357,228 @@ pub fn load_decision_model_headers_from_binary(
     decision_models
 }
 
-pub fn load_design_model_headers_from_binary(header_path: &Path) -> Vec<DesignModelHeader> {
+/// Loads all the design model headers in `header_path`, one result per header file found.
+///
+/// A file that cannot be read or decoded gives an error naming it, so that the other headers can still be used.
+pub fn load_design_model_headers_from_binary(
+    header_path: &Path,
+) -> Vec<Result<DesignModelHeader, IdesydeError>> {
     let mut design_models = Vec::new();
-    // let known_decision_model_paths =
     if let Ok(ls) = header_path.read_dir() {
         for dir_entry in ls.flatten() {
-            if dir_entry
-                .path()
+            let path = dir_entry.path();
+            if path
                 .file_name()
                 .and_then(|f| f.to_str())
                 .map_or(false, |f| f.starts_with("header"))
             {
-                if dir_entry
-                    .path()
+                if path
                     .extension()
                     .map(|x| x.eq_ignore_ascii_case("msgpack"))
                     .unwrap_or(false)
                 {
-                    let contents = std::fs::read(dir_entry.path())
-                        .expect("Failed to read design model header file.");
-                    let header = rmp_serde::decode::from_slice(&contents)
-                        .expect("Failed to deserialize design model header.");
-                    design_models.push(header);
-                } else if dir_entry
-                    .path()
+                    design_models.push(
+                        std::fs::read(&path)
+                            .map_err(|e| IdesydeError::io(&path, e))
+                            .and_then(|contents| {
+                                rmp_serde::decode::from_slice::<DesignModelHeader>(&contents)
+                                    .map(|h| h.with_typed_relations())
+                                    .map_err(|e| IdesydeError::decoding(&path, "MsgPack", e))
+                            }),
+                    );
+                } else if path
                     .extension()
                     .map(|x| x.eq_ignore_ascii_case("cbor"))
                     .unwrap_or(false)
                 {
-                    let contents = std::fs::read(dir_entry.path())
-                        .expect("Failed to read decision model header file.");
-                    let header = ciborium::from_reader(contents.as_slice())
-                        .expect("Failed to deserialize design model header.");
-                    design_models.push(header);
+                    design_models.push(
+                        std::fs::read(&path)
+                            .map_err(|e| IdesydeError::io(&path, e))
+                            .and_then(|contents| {
+                                ciborium::from_reader::<DesignModelHeader, _>(contents.as_slice())
+                                    .map(|h| h.with_typed_relations())
+                                    .map_err(|e| IdesydeError::decoding(&path, "CBOR", e))
+                            }),
+                    );
                 }
             }
         }
     }
     design_models
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(elements: &[&str]) -> BTreeSet<String> {
+        elements.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn encoded_relations_are_read_with_their_ports_and_label() {
+        assert_eq!(
+            LabelledArcWithPorts::from_element("2=a:out-b:in"),
+            Some(LabelledArcWithPorts {
+                src: "a".to_string(),
+                src_port: Some("out".to_string()),
+                label: Some("2".to_string()),
+                dst: "b".to_string(),
+                dst_port: Some("in".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn empty_ports_and_labels_are_absent() {
+        assert_eq!(
+            LabelledArcWithPorts::from_element("=a:-b:"),
+            Some(LabelledArcWithPorts {
+                src: "a".to_string(),
+                src_port: None,
+                label: None,
+                dst: "b".to_string(),
+                dst_port: None,
+            })
+        );
+        assert_eq!(
+            LabelledArcWithPorts::from_element("=a:-b:"),
+            Some(LabelledArcWithPorts::new("", "a", "", "b", ""))
+        );
+    }
+
+    #[test]
+    fn malformed_encodings_are_not_relations() {
+        for e in [
+            "", "a", "label", "l=a", "l=a:p", "l=a:p-b", "l=a-b", "a:p-b:q", "l=:p-b:q",
+            "l=a:p-:q", "=:-:",
+        ] {
+            assert_eq!(LabelledArcWithPorts::from_element(e), None, "{}", e);
+        }
+    }
+
+    #[test]
+    fn encoded_relations_between_elements_are_split() {
+        let mut elements = set(&["a", "b", "1=a:out-b:in", "=b:-a:"]);
+        let mut relations = BTreeSet::new();
+        split_encoded_relations(&mut elements, &mut relations);
+        assert_eq!(elements, set(&["a", "b"]));
+        assert_eq!(
+            relations,
+            BTreeSet::from([
+                LabelledArcWithPorts::new("1", "a", "out", "b", "in"),
+                LabelledArcWithPorts::new("", "b", "", "a", ""),
+            ])
+        );
+    }
+
+    #[test]
+    fn elements_that_only_look_like_relations_are_kept() {
+        let mut elements = set(&[
+            "a",
+            "b",
+            "key=value",
+            "platform::Bus",
+            "x=y:z-w:v",
+            "2=a:out-c:in",
+            "timing=ms:10-20:30",
+        ]);
+        let mut relations = BTreeSet::new();
+        split_encoded_relations(&mut elements, &mut relations);
+        assert_eq!(elements.len(), 7);
+        assert!(relations.is_empty());
+    }
+
+    #[test]
+    fn headers_with_encoded_relations_equal_typed_ones() {
+        let encoded = DesignModelHeader {
+            category: "Graph".to_string(),
+            model_paths: Vec::new(),
+            elements: set(&["a", "b", "=a:p-b:q"]),
+            relations: BTreeSet::new(),
+        };
+        let typed = DesignModelHeader {
+            category: "Graph".to_string(),
+            model_paths: Vec::new(),
+            elements: set(&["a", "b"]),
+            relations: BTreeSet::from([LabelledArcWithPorts::new("", "a", "p", "b", "q")]),
+        };
+        assert_ne!(encoded, typed);
+        assert_eq!(encoded.with_typed_relations(), typed);
+    }
+
+    #[test]
+    fn sets_are_compared_by_inclusion() {
+        let abc = set(&["a", "b", "c"]);
+        assert_eq!(set_inclusion(&abc, &abc), Some(Ordering::Equal));
+        assert_eq!(
+            set_inclusion(&BTreeSet::<String>::new(), &BTreeSet::new()),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(set_inclusion(&set(&["a", "c"]), &abc), Some(Ordering::Less));
+        assert_eq!(set_inclusion(&abc, &set(&["b"])), Some(Ordering::Greater));
+        assert_eq!(set_inclusion(&BTreeSet::new(), &abc), Some(Ordering::Less));
+        assert_eq!(
+            set_inclusion(&abc, &BTreeSet::new()),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(set_inclusion(&set(&["a", "b"]), &set(&["b", "c"])), None);
+        assert_eq!(set_inclusion(&set(&["a"]), &set(&["b"])), None);
+        // the differences are found at the ends of the sets too
+        assert_eq!(set_inclusion(&set(&["a", "b"]), &set(&["b", "z"])), None);
+        assert_eq!(
+            set_inclusion(&set(&["a", "z"]), &set(&["z"])),
+            Some(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn inclusions_of_elements_and_relations_are_combined() {
+        use Ordering::*;
+        for o in [Some(Less), Some(Equal), Some(Greater), None] {
+            assert_eq!(combine_inclusions(Some(Equal), o), o);
+            assert_eq!(combine_inclusions(o, Some(Equal)), o);
+            assert_eq!(combine_inclusions(None, o), None);
+            assert_eq!(combine_inclusions(o, None), None);
+        }
+        assert_eq!(combine_inclusions(Some(Less), Some(Less)), Some(Less));
+        assert_eq!(
+            combine_inclusions(Some(Greater), Some(Greater)),
+            Some(Greater)
+        );
+        assert_eq!(combine_inclusions(Some(Less), Some(Greater)), None);
+        assert_eq!(combine_inclusions(Some(Greater), Some(Less)), None);
+    }
+
+    fn decision_header(elements: &[&str], relations: &[(&str, &str)]) -> DecisionModelHeader {
+        DecisionModelHeader {
+            category: "Model".to_string(),
+            body_path: None,
+            body_paths: HashMap::new(),
+            covered_elements: set(elements),
+            objectives: HashMap::new(),
+            covered_relations: relations
+                .iter()
+                .map(|(src, dst)| LabelledArcWithPorts::new("", src, "", dst, ""))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn headers_are_ordered_by_their_elements_and_relations() {
+        let small = decision_header(&["a", "b"], &[("a", "b")]);
+        let more_elements = decision_header(&["a", "b", "c"], &[("a", "b")]);
+        let more_relations = decision_header(&["a", "b"], &[("a", "b"), ("b", "a")]);
+        let more_of_both = decision_header(&["a", "b", "c"], &[("a", "b"), ("b", "c")]);
+        let other_relations = decision_header(&["a", "b", "c"], &[("b", "a")]);
+        assert_eq!(small.partial_cmp(&small), Some(Ordering::Equal));
+        assert_eq!(small.partial_cmp(&more_elements), Some(Ordering::Less));
+        assert_eq!(more_relations.partial_cmp(&small), Some(Ordering::Greater));
+        assert_eq!(more_of_both.partial_cmp(&small), Some(Ordering::Greater));
+        // more elements but fewer relations, and the other way around
+        assert_eq!(more_elements.partial_cmp(&more_relations), None);
+        assert_eq!(more_relations.partial_cmp(&more_elements), None);
+        // more elements but other relations
+        assert_eq!(other_relations.partial_cmp(&small), None);
+    }
+}
@@ -1,15 +1,19 @@
+pub mod errors;
 pub mod headers;
+pub mod protocol;
 
 use std::{
     collections::{HashMap, HashSet},
     fs,
     hash::Hash,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use downcast_rs::{impl_downcast, DowncastSync};
+pub use errors::IdesydeError;
 use headers::{DecisionModelHeader, DesignModelHeader, ExplorationBid};
-use serde::de::DeserializeOwned;
+pub use idesyde_core_derive::DecisionModel;
+use serde::{de::DeserializeOwned, Serialize};
 use std::cmp::Ordering;
 
 /// The trait/interface for a design model in the design space identification methodology, as
@@ -61,6 +65,14 @@ pub trait DecisionModel: Send + DowncastSync {
         None
     }
 
+    /// The objective values of the decision model, all to be minimised.
+    ///
+    /// They are usually only meaningful for solved decision models, i.e. the ones coming out of exploration,
+    /// and are empty by default.
+    fn objectives(&self) -> HashMap<String, f64> {
+        HashMap::new()
+    }
+
     fn dominates(&self, o: Box<dyn DecisionModel>) -> bool {
         match self.header().partial_cmp(&o.header()) {
             Some(Ordering::Greater) => true,
@@ -78,6 +90,23 @@ impl DecisionModel for DecisionModelHeader {
     fn header(&self) -> DecisionModelHeader {
         self.to_owned()
     }
+
+    fn objectives(&self) -> HashMap<String, f64> {
+        self.objectives.to_owned()
+    }
+}
+
+/// Checks if the objective values `a` Pareto-dominate `b`, where all objectives are minimised.
+///
+/// This is the case if both have the same objectives, `a` is never worse than `b` and it is strictly better in at least one objective.
+/// Objective values of different objectives are never comparable.
+pub fn pareto_dominates(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> bool {
+    !a.is_empty()
+        && a.len() == b.len()
+        && a.iter()
+            .all(|(k, v)| b.get(k).map(|o| v <= o).unwrap_or(false))
+        && a.iter()
+            .any(|(k, v)| b.get(k).map(|o| v < o).unwrap_or(false))
 }
 
 impl PartialEq<dyn DecisionModel> for dyn DecisionModel {
@@ -92,36 +121,59 @@ impl PartialOrd<dyn DecisionModel> for dyn DecisionModel {
     }
 }
 
+/// Encodes a decision model body as JSON, as done by `#[derive(DecisionModel)]`.
+pub fn encode_body_as_json<T: Serialize + ?Sized>(m: &T) -> Option<String> {
+    serde_json::to_string(m).ok()
+}
+
+/// Encodes a decision model body as MsgPack, as done by `#[derive(DecisionModel)]`.
+pub fn encode_body_as_msgpack<T: Serialize + ?Sized>(m: &T) -> Option<Vec<u8>> {
+    rmp_serde::to_vec(m).ok()
+}
+
+/// Encodes a decision model body as CBOR, as done by `#[derive(DecisionModel)]`.
+pub fn encode_body_as_cbor<T: Serialize + ?Sized>(m: &T) -> Option<Vec<u8>> {
+    let mut b: Vec<u8> = Vec::new();
+    ciborium::into_writer(m, &mut b).ok().map(|_| b)
+}
+
+/// Writes the header in all the encodings, as `header_{prefix}_{category}_{suffix}` files in `p`.
+fn write_header_encodings<H: Serialize>(
+    h: &H,
+    category: &str,
+    p: &Path,
+    prefix_str: &str,
+    suffix_str: &str,
+) -> Result<(), IdesydeError> {
+    let json_path = p.join(format!(
+        "header_{}_{}_{}.json",
+        prefix_str, category, suffix_str
+    ));
+    let json =
+        serde_json::to_string(h).map_err(|e| IdesydeError::encoding(&json_path, "JSON", e))?;
+    fs::write(&json_path, json).map_err(|e| IdesydeError::io(&json_path, e))?;
+    let msgpack_path = p.join(format!(
+        "header_{}_{}_{}.msgpack",
+        prefix_str, category, suffix_str
+    ));
+    let msgpack =
+        rmp_serde::to_vec(h).map_err(|e| IdesydeError::encoding(&msgpack_path, "MsgPack", e))?;
+    fs::write(&msgpack_path, msgpack).map_err(|e| IdesydeError::io(&msgpack_path, e))?;
+    let cbor_path = p.join(format!(
+        "header_{}_{}_{}.cbor",
+        prefix_str, category, suffix_str
+    ));
+    let cbor_file = fs::File::create(&cbor_path).map_err(|e| IdesydeError::io(&cbor_path, e))?;
+    ciborium::into_writer(h, cbor_file).map_err(|e| IdesydeError::encoding(&cbor_path, "CBOR", e))
+}
+
 pub fn write_design_model_header_to_path(
     h: &DesignModelHeader,
     p: &Path,
     prefix_str: &str,
     suffix_str: &str,
-) -> bool {
-    std::fs::write(
-        p.join(format!(
-            "header_{}_{}_{}.json",
-            prefix_str, h.category, suffix_str
-        )),
-        serde_json::to_string(h).expect("Failed to serialize decision model to json."),
-    )
-    .expect("Failed to write serialized design model during identification.");
-    fs::write(
-        p.join(format!(
-            "header_{}_{}_{}.msgpack",
-            prefix_str, h.category, suffix_str
-        )),
-        rmp_serde::to_vec(h).expect("Failed to serialize design model to msgpack."),
-    )
-    .expect("Failed to write serialized design model during identification.");
-    let cbor_file = fs::File::create(p.join(format!(
-        "header_{}_{}_{}.cbor",
-        prefix_str, h.category, suffix_str
-    )))
-    .expect("Failed to create file to deserialize CBOR design model header.");
-    ciborium::into_writer(h, cbor_file)
-        .expect("Failed to serialize design model header during identification.");
-    true
+) -> Result<(), IdesydeError> {
+    write_header_encodings(h, &h.category, p, prefix_str, suffix_str)
 }
 
 pub fn write_decision_model_header_to_path(
@@ -129,31 +181,8 @@ pub fn write_decision_model_header_to_path(
     p: &Path,
     prefix_str: &str,
     suffix_str: &str,
-) -> bool {
-    std::fs::write(
-        p.join(format!(
-            "header_{}_{}_{}.json",
-            prefix_str, h.category, suffix_str
-        )),
-        serde_json::to_string(h).expect("Failed to serialize decision model to json."),
-    )
-    .expect("Failed to write serialized decision model during identification.");
-    fs::write(
-        p.join(format!(
-            "header_{}_{}_{}.msgpack",
-            prefix_str, h.category, suffix_str
-        )),
-        rmp_serde::to_vec(h).expect("Failed to serialize decision model to msgpack."),
-    )
-    .expect("Failed to write serialized decision model during identification.");
-    let cbor_file = fs::File::create(p.join(format!(
-        "header_{}_{}_{}.cbor",
-        prefix_str, h.category, suffix_str
-    )))
-    .expect("Failed to create file to deserialize CBOR decision model header.");
-    ciborium::into_writer(h, cbor_file)
-        .expect("Failed to serialize decision model header during identification.");
-    true
+) -> Result<(), IdesydeError> {
+    write_header_encodings(h, &h.category, p, prefix_str, suffix_str)
 }
 
 pub fn write_decision_model_to_path<M: DecisionModel + ?Sized>(
@@ -161,25 +190,32 @@ pub fn write_decision_model_to_path<M: DecisionModel + ?Sized>(
     p: &Path,
     prefix_str: &str,
     suffix_str: &str,
-) -> DecisionModelHeader {
+) -> Result<DecisionModelHeader, IdesydeError> {
     let mut h = m.header();
-    if let Some(j) = m.body_as_json() {
-        let p = format!("body_{}_{}_{}.json", prefix_str, h.category, suffix_str);
-        std::fs::write(&p, j).expect("Failed to write JSON body of decision model.");
-        h.body_path = Some(p);
-    }
-    if let Some(b) = m.body_as_msgpack() {
-        let p = format!("body_{}_{}_{}.msgpack", prefix_str, h.category, suffix_str);
-        std::fs::write(&p, b).expect("Failed to write MsgPack body of decision model.");
-        h.body_path = Some(p);
-    }
-    if let Some(b) = m.body_as_cbor() {
-        let p = format!("body_{}_{}_{}.cbor", prefix_str, h.category, suffix_str);
-        std::fs::write(&p, b).expect("Failed to write CBOR body of decision model.");
-        h.body_path = Some(p);
-    }
-    write_decision_model_header_to_path(&h, p, prefix_str, suffix_str);
-    h
+    h.objectives.extend(m.objectives());
+    let bodies = [
+        ("json", m.body_as_json().map(|j| j.into_bytes())),
+        ("msgpack", m.body_as_msgpack()),
+        ("cbor", m.body_as_cbor()),
+    ];
+    for (format, body) in bodies {
+        if let Some(b) = body {
+            let bp = p.join(format!(
+                "body_{}_{}_{}.{}",
+                prefix_str, h.category, suffix_str, format
+            ));
+            std::fs::write(&bp, b).map_err(|e| IdesydeError::io(&bp, e))?;
+            h.body_paths
+                .insert(format.to_string(), bp.to_string_lossy().to_string());
+        }
+    }
+    // kept for the modules that only know of a single body
+    h.body_path = BODY_FORMATS
+        .iter()
+        .find_map(|f| h.body_paths.get(*f))
+        .cloned();
+    write_decision_model_header_to_path(&h, p, prefix_str, suffix_str)?;
+    Ok(h)
 }
 
 pub type IdentificationRule =
@@ -236,6 +272,11 @@ pub trait ExplorationModule: Send + Sync {
         time_resolution: i64,
         memory_resolution: i64,
     ) -> Box<dyn Iterator<Item = Box<dyn DecisionModel>>>;
+    /// Cancels the explorations of this module that are still running, so that their iterators end
+    /// without waiting for a next solution that might never come.
+    ///
+    /// Modules whose iterators do not block for long between solutions can keep the default, which does nothing.
+    fn stop_exploring(&self) {}
 }
 
 impl PartialEq<dyn ExplorationModule> for dyn ExplorationModule {
@@ -251,7 +292,7 @@ pub struct StandaloneIdentificationModule {
     identification_rules: Vec<MarkedIdentificationRule>,
     reverse_identification_rules: Vec<ReverseIdentificationRule>,
     read_design_model: fn(path: &Path) -> Option<Box<dyn DesignModel>>,
-    write_design_model: fn(design_model: &Box<dyn DesignModel>, dest: &Path) -> bool,
+    write_design_model: fn(design_model: &dyn DesignModel, dest: &Path) -> Option<PathBuf>,
     decision_header_to_model: fn(header: &DecisionModelHeader) -> Option<Box<dyn DecisionModel>>,
     pub decision_model_schemas: HashSet<String>,
 }
@@ -262,7 +303,7 @@ impl StandaloneIdentificationModule {
         identification_rules: Vec<MarkedIdentificationRule>,
         reverse_identification_rules: Vec<ReverseIdentificationRule>,
         read_design_model: fn(path: &Path) -> Option<Box<dyn DesignModel>>,
-        write_design_model: fn(design_model: &Box<dyn DesignModel>, dest: &Path) -> bool,
+        write_design_model: fn(design_model: &dyn DesignModel, dest: &Path) -> Option<PathBuf>,
         decision_header_to_model: fn(
             header: &DecisionModelHeader,
         ) -> Option<Box<dyn DecisionModel>>,
@@ -282,7 +323,13 @@ impl StandaloneIdentificationModule {
     pub fn read_design_model(&self, path: &Path) -> Option<Box<dyn DesignModel>> {
         return (self.read_design_model)(path);
     }
-    pub fn write_design_model(&self, design_model: &Box<dyn DesignModel>, dest: &Path) -> bool {
+    /// Writes the design model to `dest`, which is either the file to write or a directory to write a new
+    /// file in. Returns the path of the file written, if the design model could be written.
+    pub fn write_design_model(
+        &self,
+        design_model: &dyn DesignModel,
+        dest: &Path,
+    ) -> Option<PathBuf> {
         return (self.write_design_model)(design_model, dest);
     }
     pub fn decision_header_to_model(
@@ -358,6 +405,86 @@ impl IdentificationModule for StandaloneIdentificationModule {
     }
 }
 
+pub type BiddingFunction = fn(&Box<dyn DecisionModel>) -> ExplorationBid;
+pub type ExplorationFunction = fn(
+    &Box<dyn DecisionModel>,
+    i64,
+    i64,
+    i64,
+    i64,
+) -> Box<dyn Iterator<Item = Box<dyn DecisionModel>>>;
+
+/// An exploration module made of plain functions, so that it can be run as an executable with
+/// `execute_standalone_exploration_module` from `idesyde-blueprints`.
+///
+/// The decision models given to `bid` and `explore` are the ones `decision_header_to_model` makes out of
+/// the headers the orchestrator chose; headers it cannot convert are never explored.
+pub struct StandaloneExplorationModule {
+    unique_identifier: String,
+    bid: BiddingFunction,
+    explore: ExplorationFunction,
+    decision_header_to_model: fn(header: &DecisionModelHeader) -> Option<Box<dyn DecisionModel>>,
+    pub decision_model_schemas: HashSet<String>,
+}
+
+impl StandaloneExplorationModule {
+    pub fn new(
+        unique_identifier: &str,
+        bid: BiddingFunction,
+        explore: ExplorationFunction,
+        decision_header_to_model: fn(
+            header: &DecisionModelHeader,
+        ) -> Option<Box<dyn DecisionModel>>,
+        decision_model_schemas: HashSet<String>,
+    ) -> StandaloneExplorationModule {
+        StandaloneExplorationModule {
+            unique_identifier: unique_identifier.to_owned(),
+            bid,
+            explore,
+            decision_header_to_model,
+            decision_model_schemas,
+        }
+    }
+
+    pub fn decision_header_to_model(
+        &self,
+        header: &DecisionModelHeader,
+    ) -> Option<Box<dyn DecisionModel>> {
+        (self.decision_header_to_model)(header)
+    }
+}
+
+impl ExplorationModule for StandaloneExplorationModule {
+    fn unique_identifier(&self) -> String {
+        self.unique_identifier.to_owned()
+    }
+
+    fn available_criterias(&self, _m: Box<dyn DecisionModel>) -> HashMap<String, f32> {
+        HashMap::new()
+    }
+
+    fn bid(&self, m: &Box<dyn DecisionModel>) -> ExplorationBid {
+        (self.bid)(m)
+    }
+
+    fn explore(
+        &self,
+        m: &Box<dyn DecisionModel>,
+        max_sols: i64,
+        total_timeout: i64,
+        time_resolution: i64,
+        memory_resolution: i64,
+    ) -> Box<dyn Iterator<Item = Box<dyn DecisionModel>>> {
+        (self.explore)(
+            m,
+            max_sols,
+            total_timeout,
+            time_resolution,
+            memory_resolution,
+        )
+    }
+}
+
 // pub trait StandaloneIdentificationModule: IdentificationModule {
 //     // fn unique_identifier(&self) -> String;
 //     fn uid(&self) -> String;
@@ -439,8 +566,12 @@ impl IdentificationModule for StandaloneIdentificationModule {
 //     }
 // }
 
-pub fn load_decision_model<T: DecisionModel + DeserializeOwned>(
-    path: &std::path::PathBuf,
+/// The formats of decision model bodies, from the most to the least preferred when loading them.
+pub const BODY_FORMATS: [&str; 3] = ["msgpack", "cbor", "json"];
+
+/// Loads the body of a decision model from `path`, decoding it according to the file extension.
+pub fn load_decision_model_from_path<T: DecisionModel + DeserializeOwned>(
+    path: &Path,
 ) -> Option<T> {
     if let Ok(f) = std::fs::File::open(path) {
         if let Some(ext) = path.extension() {
@@ -455,3 +586,64 @@ pub fn load_decision_model<T: DecisionModel + DeserializeOwned>(
     }
     None
 }
+
+/// Loads the body of the decision model described by `header`.
+///
+/// The bodies are tried in the order of [BODY_FORMATS], so that the best encoding that can be decoded is used.
+/// Headers with a single `body_path` are also accepted.
+pub fn load_decision_model<T: DecisionModel + DeserializeOwned>(
+    header: &DecisionModelHeader,
+) -> Option<T> {
+    BODY_FORMATS
+        .iter()
+        .filter_map(|f| header.body_paths.get(*f))
+        .chain(header.body_path.iter())
+        .find_map(|bp| load_decision_model_from_path(Path::new(bp)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objectives(values: &[(&str, f64)]) -> HashMap<String, f64> {
+        values.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn better_objectives_dominate() {
+        let a = objectives(&[("period", 1.0), ("memory", 2.0)]);
+        let b = objectives(&[("period", 2.0), ("memory", 2.0)]);
+        assert!(pareto_dominates(&a, &b));
+        assert!(!pareto_dominates(&b, &a));
+        // better in one objective and worse in another
+        let c = objectives(&[("period", 0.5), ("memory", 3.0)]);
+        assert!(!pareto_dominates(&a, &c));
+        assert!(!pareto_dominates(&c, &a));
+    }
+
+    #[test]
+    fn equal_objectives_do_not_dominate() {
+        let a = objectives(&[("period", 1.0), ("memory", 2.0)]);
+        assert!(!pareto_dominates(&a, &a.clone()));
+    }
+
+    #[test]
+    fn different_objectives_are_incomparable() {
+        let a = objectives(&[("period", 1.0)]);
+        let b = objectives(&[("memory", 2.0)]);
+        assert!(!pareto_dominates(&a, &b));
+        assert!(!pareto_dominates(&b, &a));
+        let more = objectives(&[("period", 2.0), ("memory", 2.0)]);
+        assert!(!pareto_dominates(&a, &more));
+        assert!(!pareto_dominates(&more, &a));
+    }
+
+    #[test]
+    fn empty_objectives_are_incomparable() {
+        let empty = HashMap::new();
+        let a = objectives(&[("period", 1.0)]);
+        assert!(!pareto_dominates(&empty, &empty));
+        assert!(!pareto_dominates(&empty, &a));
+        assert!(!pareto_dominates(&a, &empty));
+    }
+}
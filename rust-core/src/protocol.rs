@@ -0,0 +1,350 @@
+use std::{
+    collections::BTreeSet,
+    io::{Read, Write},
+    path::Path,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::headers::ExplorationBid;
+
+/// The version of the header format and of the requests and responses that modules and the orchestrator
+/// exchange.
+pub const PROTOCOL_VERSION: &str = "0.6.0";
+
+/// The argument that starts a module as a server instead of running it once.
+pub const SERVER_ARG: &str = "--server";
+
+/// The argument that makes a module print its [ModuleDescription] as JSON and exit.
+pub const DESCRIBE_ARG: &str = "--describe";
+
+/// The argument that makes a module print the JSON schemas of its decision models, one per line, and exit.
+pub const SCHEMAS_ARG: &str = "--schemas";
+
+/// The largest frame accepted, so that the output of a module that is not a server is not taken as a
+/// huge frame.
+pub const MAXIMUM_FRAME_LENGTH: u32 = 64 * 1024 * 1024;
+
+/// Which requests of the orchestrator a module answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleKind {
+    Identification,
+    Exploration,
+}
+
+/// How the executable of a module is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleLauncher {
+    Native,
+    Java,
+    Python,
+}
+
+impl ModuleLauncher {
+    /// The launcher of a module without a manifest, guessed from the extension of its executable.
+    pub fn for_path(p: &Path) -> ModuleLauncher {
+        match p.extension().and_then(|s| s.to_str()) {
+            Some("jar") => ModuleLauncher::Java,
+            Some("py") => ModuleLauncher::Python,
+            _ => ModuleLauncher::Native,
+        }
+    }
+}
+
+/// What a module declares about itself in a manifest file next to it, named after its executable as in
+/// `idesyde-common.manifest.toml` or `idesyde-common.manifest.json`.
+///
+/// The executable is the one named by the manifest file, unless `executable` gives another path relative
+/// to the manifest. An empty set of categories means that the module does not restrict the decision
+/// models it takes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleManifest {
+    pub unique_identifier: String,
+    pub kind: ModuleKind,
+    pub launcher: ModuleLauncher,
+    pub protocol_version: String,
+    #[serde(default)]
+    pub executable: Option<String>,
+    #[serde(default)]
+    pub decision_model_categories: BTreeSet<String>,
+    /// The wall-clock time each request can take, over the one given to the orchestrator.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    /// The memory the module can use, over the one given to the orchestrator.
+    #[serde(default)]
+    pub memory_limit_megabytes: Option<u64>,
+    /// Whether the module can be started once as a server with [SERVER_ARG], instead of once per request.
+    #[serde(default)]
+    pub server: bool,
+}
+
+/// What a module answers when run with [DESCRIBE_ARG], so that the orchestrator can check that it speaks
+/// the same protocol before registering it.
+///
+/// As in a [ModuleManifest], an empty set of categories means that the module does not restrict the
+/// decision models it takes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleDescription {
+    pub unique_identifier: String,
+    pub kind: ModuleKind,
+    pub protocol_version: String,
+    #[serde(default)]
+    pub decision_model_categories: BTreeSet<String>,
+    /// The JSON schemas of the bodies of the decision models the module works with.
+    #[serde(default)]
+    pub decision_model_schemas: BTreeSet<String>,
+    /// Whether the module can be started once as a server with [SERVER_ARG], instead of once per request.
+    #[serde(default)]
+    pub server: bool,
+}
+
+impl ModuleDescription {
+    /// The manifest of a module that described itself, run with `launcher`.
+    pub fn to_manifest(&self, launcher: ModuleLauncher) -> ModuleManifest {
+        ModuleManifest {
+            unique_identifier: self.unique_identifier.to_owned(),
+            kind: self.kind,
+            launcher,
+            protocol_version: self.protocol_version.to_owned(),
+            executable: None,
+            decision_model_categories: self.decision_model_categories.to_owned(),
+            timeout_seconds: None,
+            memory_limit_megabytes: None,
+            server: self.server,
+        }
+    }
+}
+
+/// Whether a module speaking `version` can work with this orchestrator, which speaks [PROTOCOL_VERSION].
+///
+/// Versions that only differ in their patch number are compatible, as are versions with the same major
+/// number from 1.0 on.
+pub fn is_compatible_protocol_version(version: &str) -> bool {
+    let major_minor = |v: &str| -> Option<(u64, u64)> {
+        let mut numbers = v.trim().trim_start_matches('v').split('.');
+        let major = numbers.next()?.parse().ok()?;
+        let minor = numbers.next().unwrap_or("0").parse().ok()?;
+        Some((major, minor))
+    };
+    match (major_minor(version), major_minor(PROTOCOL_VERSION)) {
+        (Some((0, minor)), Some((0, own_minor))) => minor == own_minor,
+        (Some((major, _)), Some((own_major, _))) => major == own_major,
+        _ => false,
+    }
+}
+
+/// What the orchestrator asks of a module started as a server.
+///
+/// The paths where the models are read and written are given when the module is started, so that the
+/// requests only carry what changes between them. Every request is answered by one [ModuleResponse],
+/// except for [ModuleRequest::Explore], which is answered by one [ModuleResponse::Solution] per solution
+/// found and a final [ModuleResponse::Done].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params")]
+pub enum ModuleRequest {
+    Identification {
+        iteration: i32,
+    },
+    /// Reverse identifies each of the solutions whose (MsgPack) headers are in `solution_paths`, and
+    /// only those.
+    ReverseIdentification {
+        solution_paths: Vec<String>,
+    },
+    Bid {
+        decision_model_path: String,
+    },
+    Explore {
+        decision_model_path: String,
+        maximum_solutions: i64,
+        total_timeout: i64,
+        time_resolution: i64,
+        memory_resolution: i64,
+    },
+    Shutdown,
+}
+
+/// What a module started as a server answers to the orchestrator.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "result", content = "value")]
+pub enum ModuleResponse {
+    /// Sent once the module has started and can take requests.
+    Ready {
+        unique_identifier: String,
+    },
+    /// The paths of the (MsgPack) headers written for an identification or reverse identification.
+    Headers(Vec<String>),
+    Bid(ExplorationBid),
+    /// The path of the (MsgPack) header of a solution found during exploration.
+    Solution(String),
+    Done,
+    Error(String),
+}
+
+/// Writes `message` as one frame: its length as a big-endian `u32` followed by its CBOR encoding.
+pub fn write_message<W: Write, M: Serialize>(w: &mut W, message: &M) -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::new();
+    ciborium::into_writer(message, &mut buf)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    let len = u32::try_from(buf.len())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    w.write_all(&len.to_be_bytes())?;
+    w.write_all(&buf)?;
+    w.flush()
+}
+
+/// Reads one frame written by [write_message], or `None` if the stream ended before a new frame.
+pub fn read_message<R: Read, M: DeserializeOwned>(r: &mut R) -> std::io::Result<Option<M>> {
+    let mut len_bytes = [0u8; 4];
+    match r.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len_bytes);
+    if len > MAXIMUM_FRAME_LENGTH {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "a frame of {} bytes is larger than the maximum allowed",
+                len
+            ),
+        ));
+    }
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    ciborium::from_reader(buf.as_slice())
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether the version with the given numbers is compatible, relative to the orchestrator's own major and
+    /// minor numbers.
+    fn compatible(major_offset: u64, minor: Option<u64>, rest: &str) -> bool {
+        let mut numbers = PROTOCOL_VERSION
+            .split('.')
+            .map(|n| n.parse::<u64>().unwrap());
+        let (major, own_minor) = (numbers.next().unwrap(), numbers.next().unwrap());
+        let v = format!(
+            "{}.{}{}",
+            major + major_offset,
+            minor.unwrap_or(own_minor),
+            rest
+        );
+        is_compatible_protocol_version(&v)
+    }
+
+    #[test]
+    fn versions_differing_in_patch_are_compatible() {
+        assert!(is_compatible_protocol_version(PROTOCOL_VERSION));
+        assert!(compatible(0, None, ".0"));
+        assert!(compatible(0, None, ".999"));
+        assert!(compatible(0, None, ""));
+        assert!(is_compatible_protocol_version(&format!(
+            " v{} ",
+            PROTOCOL_VERSION
+        )));
+    }
+
+    #[test]
+    fn versions_differing_in_minor_before_1_0_are_incompatible() {
+        assert!(PROTOCOL_VERSION.starts_with("0."));
+        assert!(!compatible(0, Some(0), ".10"));
+        assert!(!compatible(0, Some(99), ".10"));
+        assert!(!is_compatible_protocol_version("0"));
+    }
+
+    #[test]
+    fn versions_differing_in_major_are_incompatible() {
+        assert!(!compatible(1, None, ".0"));
+        assert!(!compatible(2, Some(0), ".0"));
+    }
+
+    #[test]
+    fn malformed_versions_are_incompatible() {
+        assert!(!is_compatible_protocol_version(""));
+        assert!(!is_compatible_protocol_version("latest"));
+        assert!(!is_compatible_protocol_version("0.five.1"));
+        assert!(!is_compatible_protocol_version("-1.5.0"));
+    }
+
+    #[test]
+    fn messages_round_trip_in_order() {
+        let explore = ModuleRequest::Explore {
+            decision_model_path: "header_0_SDFToTiledMultiCore_Orchestrator.msgpack".to_string(),
+            maximum_solutions: 3,
+            total_timeout: 60,
+            time_resolution: -1,
+            memory_resolution: -1,
+        };
+        let mut stream: Vec<u8> = Vec::new();
+        write_message(&mut stream, &explore).unwrap();
+        write_message(&mut stream, &ModuleRequest::Shutdown).unwrap();
+        write_message(
+            &mut stream,
+            &ModuleResponse::Solution("sol.msgpack".to_string()),
+        )
+        .unwrap();
+        let mut r = stream.as_slice();
+        assert_eq!(
+            read_message::<_, ModuleRequest>(&mut r).unwrap(),
+            Some(explore)
+        );
+        assert_eq!(
+            read_message::<_, ModuleRequest>(&mut r).unwrap(),
+            Some(ModuleRequest::Shutdown)
+        );
+        assert!(matches!(
+            read_message::<_, ModuleResponse>(&mut r).unwrap(),
+            Some(ModuleResponse::Solution(p)) if p == "sol.msgpack"
+        ));
+        assert!(read_message::<_, ModuleResponse>(&mut r).unwrap().is_none());
+    }
+
+    #[test]
+    fn an_ended_stream_has_no_message() {
+        let mut empty: &[u8] = &[];
+        assert!(read_message::<_, ModuleRequest>(&mut empty)
+            .unwrap()
+            .is_none());
+        // a stream that ends in the middle of the length of a frame ended before it
+        let mut partial: &[u8] = &[0, 0];
+        assert!(read_message::<_, ModuleRequest>(&mut partial)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn a_stream_that_ends_within_a_frame_is_an_error() {
+        let mut stream: Vec<u8> = Vec::new();
+        write_message(&mut stream, &ModuleRequest::Identification { iteration: 1 }).unwrap();
+        stream.truncate(stream.len() - 1);
+        let e = read_message::<_, ModuleRequest>(&mut stream.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected_before_being_read() {
+        let mut stream: Vec<u8> = (MAXIMUM_FRAME_LENGTH + 1).to_be_bytes().to_vec();
+        stream.extend_from_slice(b"not read");
+        let e = read_message::<_, ModuleRequest>(&mut stream.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        // e.g. the first line of a module that is not a server
+        let mut text: &[u8] = b"header_0_SDFApplication_Module.msgpack\n";
+        let e = read_message::<_, ModuleRequest>(&mut text).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn frames_that_are_not_messages_are_rejected() {
+        let mut stream: Vec<u8> = Vec::new();
+        write_message(&mut stream, &"not a request").unwrap();
+        let e = read_message::<_, ModuleRequest>(&mut stream.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,736 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use idesyde_common::SDFToTiledMultiCore;
+use idesyde_core::{
+    headers::{DecisionModelHeader, ExplorationBid},
+    load_decision_model, write_decision_model_to_path, DecisionModel, ExplorationModule,
+};
+use log::{debug, warn};
+
+/// An exploration module that runs in the same process as the orchestrator.
+///
+/// It explores [SDFToTiledMultiCore] decision models with a constructive heuristic followed by
+/// a local search, so that small cases can be solved without any external solver installed.
+/// Every solution found is written to the solved path and given back as its header, just like
+/// external explorers do, so that identification modules can reverse identify them. The solutions are numbered across all
+/// explorations of the module, so that concurrent explorations do not overwrite each other.
+#[derive(Debug, Clone)]
+pub struct HeuristicExplorationModule {
+    solved_path: PathBuf,
+    solutions_written: Arc<AtomicUsize>,
+}
+
+impl HeuristicExplorationModule {
+    pub fn new(solved_path: &Path) -> HeuristicExplorationModule {
+        HeuristicExplorationModule {
+            solved_path: solved_path.to_path_buf(),
+            solutions_written: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+/// Gets the [SDFToTiledMultiCore] behind the decision model, either directly or by loading the body
+/// of its header.
+fn as_sdf_to_tiled_multicore(m: &dyn DecisionModel) -> Option<SDFToTiledMultiCore> {
+    if let Some(sdf_to_tiled) = m.downcast_ref::<SDFToTiledMultiCore>() {
+        return Some(sdf_to_tiled.to_owned());
+    }
+    m.downcast_ref::<DecisionModelHeader>()
+        .filter(|h| h.category == "SDFToTiledMultiCore")
+        .and_then(load_decision_model::<SDFToTiledMultiCore>)
+}
+
+impl ExplorationModule for HeuristicExplorationModule {
+    fn unique_identifier(&self) -> String {
+        "HeuristicExplorationModule".to_string()
+    }
+
+    fn available_criterias(&self, _m: Box<dyn DecisionModel>) -> HashMap<String, f32> {
+        HashMap::new()
+    }
+
+    fn bid(&self, m: &Box<dyn DecisionModel>) -> ExplorationBid {
+        ExplorationBid {
+            unique_identifier: self.unique_identifier(),
+            can_explore: m.unique_identifier() == "SDFToTiledMultiCore"
+                && as_sdf_to_tiled_multicore(m.as_ref()).is_some(),
+            properties: HashMap::new(),
+        }
+    }
+
+    fn explore(
+        &self,
+        m: &Box<dyn DecisionModel>,
+        max_sols: i64,
+        total_timeout: i64,
+        _time_resolution: i64,
+        _memory_resolution: i64,
+    ) -> Box<dyn Iterator<Item = Box<dyn DecisionModel>>> {
+        match as_sdf_to_tiled_multicore(m.as_ref()) {
+            Some(model) => Box::new(MappingSearch {
+                problem: MappingProblem::new(&model),
+                model,
+                current: None,
+                found: 0,
+                max_sols,
+                deadline: if total_timeout > 0 {
+                    Some(Instant::now() + Duration::from_secs(total_timeout as u64))
+                } else {
+                    None
+                },
+                solved_path: self.solved_path.to_owned(),
+                solutions_written: self.solutions_written.clone(),
+                uid: self.unique_identifier(),
+            }),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+/// The [SDFToTiledMultiCore] decision model flattened into indexes, for quick evaluation of mappings.
+///
+/// A mapping assigns every actor to a processor index, and the actor is then scheduled by the runtime of
+/// that processor and stored in the memory of its tile.
+struct MappingProblem {
+    actors: Vec<String>,
+    repetitions: Vec<f64>,
+    wcets: Vec<Vec<Option<f64>>>,
+    actor_sizes: Vec<u64>,
+    min_throughputs: Vec<Option<f64>>,
+    /// (source actor, destination actor, bits per iteration, buffer size in bits)
+    channels: Vec<(usize, usize, f64, u64)>,
+    channels_identifiers: Vec<String>,
+    processors: Vec<String>,
+    schedulers: Vec<String>,
+    memories: Vec<String>,
+    memory_capacities: Vec<Option<u64>>,
+    paths: Vec<Vec<Vec<usize>>>,
+    bandwidths: Vec<f64>,
+    firing_order: Vec<usize>,
+}
+
+impl MappingProblem {
+    fn new(m: &SDFToTiledMultiCore) -> MappingProblem {
+        let app = &m.sdf_application;
+        let hw = &m.platform.hardware;
+        let actors = app.actors_identifiers.to_owned();
+        let actor_idx: HashMap<&String, usize> =
+            actors.iter().enumerate().map(|(i, a)| (a, i)).collect();
+        let all_wcets = m.wcets();
+        let wcets = actors
+            .iter()
+            .map(|a| {
+                hw.processors
+                    .iter()
+                    .map(|pe| all_wcets.get(a).and_then(|w| w.get(pe)).copied())
+                    .collect()
+            })
+            .collect();
+        let mut producers: HashMap<&String, (usize, i64)> = HashMap::new();
+        let mut consumers: HashMap<&String, (usize, i64)> = HashMap::new();
+        for ((src, dst), v) in app
+            .topology_srcs
+            .iter()
+            .zip(app.topology_dsts.iter())
+            .zip(app.topology_edge_value.iter())
+        {
+            if let Some(a) = actor_idx.get(src) {
+                producers.insert(dst, (*a, v.abs()));
+            } else if let Some(a) = actor_idx.get(dst) {
+                consumers.insert(src, (*a, v.abs()));
+            }
+        }
+        let mut channels = Vec::new();
+        let mut channels_identifiers = Vec::new();
+        for c in &app.channels_identifiers {
+            if let (Some((src, prod)), Some((dst, _))) = (producers.get(c), consumers.get(c)) {
+                let token_size = app.channel_token_sizes.get(c).copied().unwrap_or(0);
+                let q = app
+                    .repetition_vector
+                    .get(&actors[*src])
+                    .copied()
+                    .unwrap_or(1);
+                let produced = q * (*prod as u64);
+                let initial = app.channel_num_initial_tokens.get(c).copied().unwrap_or(0) as u64;
+                channels_identifiers.push(c.to_owned());
+                channels.push((
+                    *src,
+                    *dst,
+                    (produced * token_size) as f64,
+                    (produced + initial) * token_size,
+                ));
+            }
+        }
+        let processors = hw.processors.to_owned();
+        let schedulers = processors
+            .iter()
+            .map(|pe| {
+                m.platform
+                    .runtimes
+                    .processor_affinities
+                    .get(pe)
+                    .cloned()
+                    .unwrap_or_else(|| pe.to_owned())
+            })
+            .collect();
+        let memories: Vec<String> = processors
+            .iter()
+            .map(|pe| {
+                hw.tile_memory(pe)
+                    .or_else(|| hw.memories.first())
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let memory_capacities = memories
+            .iter()
+            .map(|mem| hw.tile_memory_sizes.get(mem).copied())
+            .collect();
+        let comm_elems: Vec<&String> = hw
+            .network_interfaces
+            .iter()
+            .chain(hw.routers.iter())
+            .collect();
+        let comm_idx: HashMap<&String, usize> = comm_elems
+            .iter()
+            .enumerate()
+            .map(|(i, ce)| (*ce, i))
+            .collect();
+        let paths = processors
+            .iter()
+            .map(|src| {
+                processors
+                    .iter()
+                    .map(|dst| {
+                        hw.pre_computed_paths
+                            .get(src)
+                            .and_then(|ps| ps.get(dst))
+                            .map(|p| p.iter().flat_map(|ce| comm_idx.get(ce).copied()).collect())
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+        let bandwidths = comm_elems
+            .iter()
+            .map(|ce| {
+                hw.communication_elements_bit_per_sec_per_channel
+                    .get(*ce)
+                    .copied()
+                    .unwrap_or(0.0)
+                    * hw.communication_elements_max_channels
+                        .get(*ce)
+                        .copied()
+                        .unwrap_or(1) as f64
+            })
+            .collect();
+        MappingProblem {
+            repetitions: actors
+                .iter()
+                .map(|a| app.repetition_vector.get(a).copied().unwrap_or(1) as f64)
+                .collect(),
+            actor_sizes: actors
+                .iter()
+                .map(|a| app.actor_sizes.get(a).copied().unwrap_or(0))
+                .collect(),
+            min_throughputs: actors
+                .iter()
+                .map(|a| app.minimum_actor_throughputs.get(a).copied())
+                .collect(),
+            firing_order: app
+                .topological_and_heavy_job_ordering
+                .iter()
+                .flat_map(|a| actor_idx.get(a).copied())
+                .collect(),
+            actors,
+            wcets,
+            channels,
+            channels_identifiers,
+            processors,
+            schedulers,
+            memories,
+            memory_capacities,
+            paths,
+            bandwidths,
+        }
+    }
+
+    /// Estimates the iteration period of the (partial) mapping as the load of its most loaded resource,
+    /// be it a processor or a communication element. Returns `None` if the mapping is infeasible,
+    /// i.e. an actor cannot run on its processor, a tile memory overflows or a throughput constraint is violated.
+    fn period(&self, mapping: &[Option<usize>]) -> Option<f64> {
+        let mut loads = vec![0.0; self.processors.len()];
+        let mut used_memory = vec![0u64; self.processors.len()];
+        for (a, pe) in mapping.iter().enumerate() {
+            if let Some(p) = pe {
+                loads[*p] += self.repetitions[a] * self.wcets[a][*p]?;
+                used_memory[*p] += self.actor_sizes[a];
+            }
+        }
+        let mut traffic = vec![0.0; self.bandwidths.len()];
+        for (src, dst, bits, buffer) in &self.channels {
+            if let (Some(ps), Some(pd)) = (mapping[*src], mapping[*dst]) {
+                used_memory[pd] += buffer;
+                if ps != pd {
+                    for ce in &self.paths[ps][pd] {
+                        traffic[*ce] += bits;
+                    }
+                }
+            }
+        }
+        if used_memory
+            .iter()
+            .zip(self.memory_capacities.iter())
+            .any(|(used, cap)| cap.map(|c| *used > c).unwrap_or(false))
+        {
+            return None;
+        }
+        let mut period = loads.iter().copied().fold(0.0, f64::max);
+        for (t, bw) in traffic.iter().zip(self.bandwidths.iter()) {
+            if *t > 0.0 {
+                if *bw <= 0.0 {
+                    return None;
+                }
+                period = period.max(t / bw);
+            }
+        }
+        let violates_throughput = mapping.iter().enumerate().any(|(a, pe)| {
+            pe.is_some()
+                && period > 0.0
+                && self.min_throughputs[a]
+                    .map(|th| self.repetitions[a] / period < th)
+                    .unwrap_or(false)
+        });
+        if violates_throughput {
+            None
+        } else {
+            Some(period)
+        }
+    }
+
+    /// Maps the actors one by one, in the order they first fire, onto the processor that
+    /// keeps the estimated period the lowest.
+    fn construct(&self) -> Option<Vec<usize>> {
+        let mut mapping: Vec<Option<usize>> = vec![None; self.actors.len()];
+        let mut order: Vec<usize> = Vec::new();
+        for a in self
+            .firing_order
+            .iter()
+            .copied()
+            .chain(0..self.actors.len())
+        {
+            if !order.contains(&a) {
+                order.push(a);
+            }
+        }
+        for a in order {
+            let mut best: Option<(usize, f64)> = None;
+            for p in 0..self.processors.len() {
+                mapping[a] = Some(p);
+                if let Some(period) = self.period(&mapping) {
+                    if best.map(|(_, b)| period < b).unwrap_or(true) {
+                        best = Some((p, period));
+                    }
+                }
+            }
+            mapping[a] = Some(best?.0);
+        }
+        mapping.into_iter().collect()
+    }
+
+    /// Looks for the first move or swap of actors between processors that lowers the estimated period.
+    fn improve(&self, mapping: &[usize], period: f64) -> Option<(Vec<usize>, f64)> {
+        let mut candidate: Vec<Option<usize>> = mapping.iter().map(|p| Some(*p)).collect();
+        let better = |c: &[Option<usize>]| self.period(c).filter(|p| *p < period * (1.0 - 1e-9));
+        for a in 0..mapping.len() {
+            for p in 0..self.processors.len() {
+                if p != mapping[a] {
+                    candidate[a] = Some(p);
+                    if let Some(new_period) = better(&candidate) {
+                        return Some((candidate.into_iter().flatten().collect(), new_period));
+                    }
+                    candidate[a] = Some(mapping[a]);
+                }
+            }
+        }
+        for a in 0..mapping.len() {
+            for b in (a + 1)..mapping.len() {
+                if mapping[a] != mapping[b] {
+                    candidate[a] = Some(mapping[b]);
+                    candidate[b] = Some(mapping[a]);
+                    if let Some(new_period) = better(&candidate) {
+                        return Some((candidate.into_iter().flatten().collect(), new_period));
+                    }
+                    candidate[a] = Some(mapping[a]);
+                    candidate[b] = Some(mapping[b]);
+                }
+            }
+        }
+        None
+    }
+
+    /// Fills the decisions of the decision model with the mapping, where the super loop of every
+    /// runtime follows the firing order of the application.
+    fn to_solution(&self, m: &SDFToTiledMultiCore, mapping: &[usize]) -> SDFToTiledMultiCore {
+        let mut solution = m.to_owned();
+        solution.processes_to_runtime_scheduling = mapping
+            .iter()
+            .enumerate()
+            .map(|(a, p)| (self.actors[a].to_owned(), self.schedulers[*p].to_owned()))
+            .collect();
+        solution.processes_to_memory_mapping = mapping
+            .iter()
+            .enumerate()
+            .map(|(a, p)| (self.actors[a].to_owned(), self.memories[*p].to_owned()))
+            .collect();
+        solution.super_loop_schedules = self
+            .schedulers
+            .iter()
+            .enumerate()
+            .map(|(p, s)| {
+                (
+                    s.to_owned(),
+                    self.firing_order
+                        .iter()
+                        .filter(|a| mapping[**a] == p)
+                        .map(|a| self.actors[*a].to_owned())
+                        .collect(),
+                )
+            })
+            .collect();
+        // the buffers are accounted in the memory of the consumer
+        solution.channels_to_memory_mapping = self
+            .channels
+            .iter()
+            .zip(self.channels_identifiers.iter())
+            .map(|((_, dst, _, _), c)| (c.to_owned(), self.memories[mapping[*dst]].to_owned()))
+            .collect();
+        // the slots of every communication element are given in turns to the channels crossing it
+        let hw = &m.platform.hardware;
+        let mut crossing: HashMap<&String, Vec<&String>> = HashMap::new();
+        for ((src, dst, _, _), c) in self.channels.iter().zip(self.channels_identifiers.iter()) {
+            let mem = &self.memories[mapping[*dst]];
+            for (from, to) in [
+                (&self.processors[mapping[*src]], mem),
+                (mem, &self.processors[mapping[*dst]]),
+            ] {
+                for ce in hw
+                    .pre_computed_paths
+                    .get(from)
+                    .and_then(|ps| ps.get(to))
+                    .into_iter()
+                    .flatten()
+                {
+                    let cs = crossing.entry(ce).or_default();
+                    if !cs.contains(&c) {
+                        cs.push(c);
+                    }
+                }
+            }
+        }
+        solution.channels_slot_allocations = HashMap::new();
+        for (ce, cs) in crossing {
+            let slots = hw
+                .communication_elements_max_channels
+                .get(ce)
+                .copied()
+                .unwrap_or(1)
+                .max(1) as usize;
+            for (k, c) in cs.iter().enumerate() {
+                let allocation = (0..slots)
+                    .map(|i| {
+                        if cs.len() <= slots {
+                            i % cs.len() == k
+                        } else {
+                            i == k % slots
+                        }
+                    })
+                    .collect();
+                solution
+                    .channels_slot_allocations
+                    .entry(c.to_string())
+                    .or_default()
+                    .insert(ce.to_owned(), allocation);
+            }
+        }
+        solution
+    }
+}
+
+/// Lazily explores the mappings: the first solution is the constructive one and every following
+/// solution is a strict improvement found by the local search.
+struct MappingSearch {
+    problem: MappingProblem,
+    model: SDFToTiledMultiCore,
+    current: Option<(Vec<usize>, f64)>,
+    found: i64,
+    max_sols: i64,
+    deadline: Option<Instant>,
+    solved_path: PathBuf,
+    solutions_written: Arc<AtomicUsize>,
+    uid: String,
+}
+
+impl Iterator for MappingSearch {
+    type Item = Box<dyn DecisionModel>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if (self.max_sols > 0 && self.found >= self.max_sols)
+            || self.deadline.map(|d| Instant::now() >= d).unwrap_or(false)
+        {
+            return None;
+        }
+        let next = match &self.current {
+            None => self.problem.construct().and_then(|mapping| {
+                let opt: Vec<Option<usize>> = mapping.iter().map(|p| Some(*p)).collect();
+                self.problem.period(&opt).map(|period| (mapping, period))
+            }),
+            Some((mapping, period)) => self.problem.improve(mapping, *period),
+        };
+        let (mapping, period) = next?;
+        let solution = Box::new(self.problem.to_solution(&self.model, &mapping));
+        debug!(
+            "{} found a mapping with an estimated period of {} s and a self-timed period of {:?} s.",
+            self.uid,
+            period,
+            solution.self_timed_period()
+        );
+        self.current = Some((mapping, period));
+        self.found += 1;
+        match write_decision_model_to_path(
+            &solution,
+            &self.solved_path,
+            format!(
+                "{:0>16}",
+                self.solutions_written.fetch_add(1, Ordering::SeqCst)
+            )
+            .as_str(),
+            &self.uid,
+        ) {
+            Ok(header) => Some(Box::new(header) as Box<dyn DecisionModel>),
+            Err(e) => {
+                warn!("{} could not write a solution: {}", self.uid, e);
+                Some(solution as Box<dyn DecisionModel>)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use idesyde_common::{
+        PartitionedTiledMulticore, RuntimesAndProcessors, SDFApplication, TiledMultiCore,
+    };
+
+    /// Two tiles, each with a processor at 1 kHz, its runtime and its memory, connected by a bus of 1 kbit/s.
+    /// The memories are listed in the opposite order of the processors, so that only the interconnect tells
+    /// which memory is in which tile.
+    fn two_tiles() -> PartitionedTiledMulticore {
+        let processors = vec!["p0".to_string(), "p1".to_string()];
+        let path = |ce: &[&str]| ce.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+        let paths = |own: &str, other_pe: &str, other_mem: &str| {
+            HashMap::from([
+                (own.to_string(), path(&[])),
+                (other_pe.to_string(), path(&["bus"])),
+                (other_mem.to_string(), path(&["bus"])),
+            ])
+        };
+        PartitionedTiledMulticore {
+            hardware: TiledMultiCore {
+                processors: processors.clone(),
+                memories: vec!["m1".to_string(), "m0".to_string()],
+                network_interfaces: Vec::new(),
+                routers: vec!["bus".to_string()],
+                interconnect_topology_srcs: path(&["p0", "p1", "p0", "bus", "p1", "bus"]),
+                interconnect_topology_dsts: path(&["m0", "m1", "bus", "p0", "bus", "p1"]),
+                processors_provisions: processors
+                    .iter()
+                    .map(|pe| {
+                        (
+                            pe.to_owned(),
+                            HashMap::from([(
+                                "mode".to_string(),
+                                HashMap::from([("all".to_string(), 1.0)]),
+                            )]),
+                        )
+                    })
+                    .collect(),
+                processors_frequency: processors.iter().map(|pe| (pe.to_owned(), 1000)).collect(),
+                tile_memory_sizes: HashMap::new(),
+                communication_elements_max_channels: HashMap::from([("bus".to_string(), 1)]),
+                communication_elements_bit_per_sec_per_channel: HashMap::from([(
+                    "bus".to_string(),
+                    1000.0,
+                )]),
+                pre_computed_paths: HashMap::from([
+                    ("p0".to_string(), paths("m0", "p1", "m1")),
+                    ("p1".to_string(), paths("m1", "p0", "m0")),
+                ]),
+            },
+            runtimes: RuntimesAndProcessors {
+                runtimes: vec!["r0".to_string(), "r1".to_string()],
+                processors,
+                runtime_host: HashMap::from([
+                    ("r0".to_string(), "p0".to_string()),
+                    ("r1".to_string(), "p1".to_string()),
+                ]),
+                processor_affinities: HashMap::from([
+                    ("p0".to_string(), "r0".to_string()),
+                    ("p1".to_string(), "r1".to_string()),
+                ]),
+                is_bare_metal: vec![true, true],
+                is_fixed_priority: vec![false, false],
+                is_earliest_deadline_first: vec![false, false],
+                is_cyclic_executive: vec![true, true],
+            },
+        }
+    }
+
+    /// An actor `a` of 300 cycles sending one byte per firing to an actor `b` of 100 cycles, on [two_tiles].
+    fn two_actors() -> SDFToTiledMultiCore {
+        let cycles = |n: u64| {
+            HashMap::from([("group".to_string(), HashMap::from([("all".to_string(), n)]))])
+        };
+        SDFToTiledMultiCore {
+            sdf_application: SDFApplication {
+                actors_identifiers: vec!["a".to_string(), "b".to_string()],
+                channels_identifiers: vec!["ab".to_string()],
+                topology_srcs: vec!["a".to_string(), "ab".to_string()],
+                topology_dsts: vec!["ab".to_string(), "b".to_string()],
+                topology_edge_value: vec![1, 1],
+                actor_sizes: HashMap::new(),
+                actor_computational_needs: HashMap::from([
+                    ("a".to_string(), cycles(300)),
+                    ("b".to_string(), cycles(100)),
+                ]),
+                channel_num_initial_tokens: HashMap::from([("ab".to_string(), 0)]),
+                channel_token_sizes: HashMap::from([("ab".to_string(), 8)]),
+                minimum_actor_throughputs: HashMap::new(),
+                repetition_vector: HashMap::from([("a".to_string(), 1), ("b".to_string(), 1)]),
+                topological_and_heavy_job_ordering: vec!["a".to_string(), "b".to_string()],
+            },
+            platform: two_tiles(),
+            processes_to_runtime_scheduling: HashMap::new(),
+            processes_to_memory_mapping: HashMap::new(),
+            super_loop_schedules: HashMap::new(),
+            channels_to_memory_mapping: HashMap::new(),
+            channels_slot_allocations: HashMap::new(),
+        }
+    }
+
+    fn assert_close(found: Option<f64>, expected: f64) {
+        let found = found.expect("the mapping should be feasible");
+        assert!(
+            (found - expected).abs() <= 1e-9,
+            "expected {} but found {}",
+            expected,
+            found
+        );
+    }
+
+    #[test]
+    fn processors_use_the_memory_of_their_tile() {
+        let problem = MappingProblem::new(&two_actors());
+        assert_eq!(problem.memories, vec!["m0", "m1"]);
+    }
+
+    #[test]
+    fn the_constructive_mapping_spreads_the_load() {
+        let model = two_actors();
+        let problem = MappingProblem::new(&model);
+        let mapping = problem
+            .construct()
+            .expect("a mapping should be constructed");
+        assert_eq!(mapping, vec![0, 1]);
+        // a is the slowest, as the byte it sends takes 8 ms to cross the bus
+        let opt: Vec<Option<usize>> = mapping.iter().map(|p| Some(*p)).collect();
+        assert_close(problem.period(&opt), 0.3);
+        let solution = problem.to_solution(&model, &mapping);
+        assert_eq!(
+            solution.processes_to_runtime_scheduling,
+            HashMap::from([
+                ("a".to_string(), "r0".to_string()),
+                ("b".to_string(), "r1".to_string())
+            ])
+        );
+        assert_eq!(
+            solution.processes_to_memory_mapping,
+            HashMap::from([
+                ("a".to_string(), "m0".to_string()),
+                ("b".to_string(), "m1".to_string())
+            ])
+        );
+        assert_eq!(
+            solution.channels_to_memory_mapping,
+            HashMap::from([("ab".to_string(), "m1".to_string())])
+        );
+        assert_eq!(
+            solution.channels_slot_allocations,
+            HashMap::from([(
+                "ab".to_string(),
+                HashMap::from([("bus".to_string(), vec![true])])
+            )])
+        );
+        assert!(solution.self_timed_period().is_some());
+    }
+
+    #[test]
+    fn the_local_search_only_takes_strict_improvements() {
+        let problem = MappingProblem::new(&two_actors());
+        let shared = vec![0, 0];
+        let (mut mapping, mut period) = (shared.clone(), 0.4);
+        assert_close(problem.period(&[Some(0), Some(0)]), period);
+        let mut steps = 0;
+        while let Some((next, next_period)) = problem.improve(&mapping, period) {
+            assert!(next_period < period);
+            assert_close(
+                problem.period(&next.iter().map(|p| Some(*p)).collect::<Vec<_>>()),
+                next_period,
+            );
+            (mapping, period) = (next, next_period);
+            steps += 1;
+        }
+        assert_eq!(steps, 1);
+        assert_ne!(mapping, shared);
+        assert_close(Some(period), 0.3);
+        // swapping the actors of the constructive mapping gives the same period, which is no improvement
+        assert!(problem.improve(&[0, 1], 0.3).is_none());
+    }
+
+    #[test]
+    fn explored_solutions_are_written_and_only_get_better() {
+        let dir = std::env::temp_dir().join(format!("idesyde-heuristics-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let explorer = HeuristicExplorationModule::new(&dir);
+        let m: Box<dyn DecisionModel> = Box::new(two_actors());
+        assert!(explorer.bid(&m).can_explore);
+        let solutions: Vec<Box<dyn DecisionModel>> = explorer.explore(&m, 0, 0, -1, -1).collect();
+        assert!(!solutions.is_empty());
+        let mut last_period = f64::INFINITY;
+        for s in &solutions {
+            let header = s
+                .downcast_ref::<DecisionModelHeader>()
+                .expect("the solutions should be given as headers");
+            let solved = load_decision_model::<SDFToTiledMultiCore>(header)
+                .expect("the body of the solution should be written");
+            let period = solved
+                .self_timed_period()
+                .expect("the solution should be live");
+            assert_eq!(header.objectives.get("period"), Some(&period));
+            assert!(period < last_period);
+            last_period = period;
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use idesyde_core::headers::{
+    load_decision_model_headers_from_binary, load_design_model_headers_from_binary,
+    DecisionModelHeader, DesignModelHeader, LabelledArcWithPorts,
+};
+use idesyde_core::DecisionModel;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::orchestration::{remove_decision_model_header_files, Bidding};
+use sha3::{Digest, Sha3_256};
+
+/// The content hashes of a run, kept in the run path between runs so that only what changed is recomputed.
+///
+/// The inputs are hashed by their file names and the modules by their paths. The explorations are
+/// kept by the hash of what was explored (see [exploration_key]), together with the headers of the solutions
+/// that were found, and the explorers that found them.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct RunCache {
+    pub inputs: HashMap<String, String>,
+    pub modules: HashMap<String, String>,
+    pub explorations: HashMap<String, Vec<(String, String)>>,
+}
+
+impl RunCache {
+    pub fn load(p: &Path) -> Option<RunCache> {
+        std::fs::read(p)
+            .ok()
+            .and_then(|b| serde_json::from_slice(&b).ok())
+    }
+
+    pub fn write(&self, p: &Path) -> bool {
+        serde_json::to_string_pretty(self)
+            .ok()
+            .and_then(|j| std::fs::write(p, j).ok())
+            .is_some()
+    }
+
+    /// Gets the cached solutions of the exploration with `key`, as pairs of the explorer and the header
+    /// of the solution. Returns `None` if any of the headers no longer exists.
+    pub fn cached_solutions(&self, key: &str) -> Option<Vec<(String, DecisionModelHeader)>> {
+        self.explorations.get(key).and_then(|sols| {
+            sols.iter()
+                .map(|(explorer, p)| {
+                    idesyde_core::headers::load_decision_model_header_from_path(Path::new(p))
+                        .map(|h| (explorer.to_owned(), h))
+                })
+                .collect()
+        })
+    }
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn hash_file(p: &Path) -> Option<String> {
+    std::fs::read(p).ok().map(|b| hash_bytes(&b))
+}
+
+/// Hashes all the files directly under `p`, by their paths.
+pub fn hash_files_in(p: &Path) -> HashMap<String, String> {
+    let mut hashes = HashMap::new();
+    if let Ok(ls) = p.read_dir() {
+        for e in ls.flatten() {
+            if e.path().is_file() {
+                if let (Some(s), Some(h)) = (e.path().to_str(), hash_file(&e.path())) {
+                    hashes.insert(s.to_string(), h);
+                }
+            }
+        }
+    }
+    hashes
+}
+
+/// Feeds `bytes` to `hasher` preceded by their length, so that consecutive parts cannot be told apart
+/// from differently split ones, e.g. `["ab", "c"]` from `["a", "bc"]`.
+fn update_with_length(hasher: &mut Sha3_256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_be_bytes());
+    hasher.update(bytes);
+}
+
+/// Hashes the contents of a decision model: its category, its covered elements in any order and its body.
+///
+/// The body is taken from the model itself or, for headers, from the JSON body next to the file it points to.
+/// As the bodies of the same decision model can have their maps in any order, the JSON body is hashed with its keys sorted.
+pub fn hash_decision_model(m: &dyn DecisionModel) -> String {
+    let h = m.header();
+    let mut hasher = Sha3_256::new();
+    update_with_length(&mut hasher, h.category.as_bytes());
+    hasher.update((h.covered_elements.len() as u64).to_be_bytes());
+    for e in &h.covered_elements {
+        update_with_length(&mut hasher, e.as_bytes());
+    }
+    hasher.update((h.covered_relations.len() as u64).to_be_bytes());
+    for r in &h.covered_relations {
+        if let Ok(b) = serde_json::to_vec(r) {
+            update_with_length(&mut hasher, &b);
+        }
+    }
+    let json_body = m.body_as_json().or_else(|| {
+        h.body_paths
+            .get("json")
+            .cloned()
+            .or_else(|| {
+                h.body_path.as_ref().map(|bp| {
+                    Path::new(bp)
+                        .with_extension("json")
+                        .to_string_lossy()
+                        .to_string()
+                })
+            })
+            .and_then(|bp| std::fs::read_to_string(bp).ok())
+    });
+    if let Some(j) = json_body {
+        match serde_json::from_str::<serde_json::Value>(&j) {
+            Ok(v) => update_with_length(&mut hasher, v.to_string().as_bytes()),
+            Err(_) => update_with_length(&mut hasher, j.as_bytes()),
+        }
+    } else if let Some(b) = h.body_path.and_then(|bp| std::fs::read(bp).ok()) {
+        update_with_length(&mut hasher, &b);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Computes the key of an exploration over all the given biddings, so that the same explorers exploring
+/// byte-identical decision models with the same parameters have the same key, regardless of the order of the
+/// biddings.
+pub fn exploration_key(
+    biddings: &[Bidding],
+    max_sols: i64,
+    total_timeout: i64,
+    time_resolution: i64,
+    memory_resolution: i64,
+) -> String {
+    let mut parts: Vec<String> = biddings
+        .iter()
+        .map(|(e, m)| {
+            format!(
+                "{}:{}",
+                e.unique_identifier(),
+                hash_decision_model(m.as_ref())
+            )
+        })
+        .collect();
+    parts.sort();
+    parts.push(format!(
+        "{}:{}:{}:{}",
+        max_sols, total_timeout, time_resolution, memory_resolution
+    ));
+    hash_bytes(parts.join(";").as_bytes())
+}
+
+/// Removes everything that was identified from the changed inputs, so that only it is identified again.
+///
+/// The elements and relations of a changed input are the ones of the design model headers, still from the previous
+/// run, that come from it. Every decision model in `identified_path` covering any of them is removed,
+/// along with its bodies. Returns how many decision models were removed.
+///
+/// The paths of the inputs are compared once canonicalized, since the modules may have been given the inputs path
+/// in another form than the orchestrator uses.
+pub fn invalidate_changed_inputs(
+    changed_inputs: &[String],
+    inputs_path: &Path,
+    identified_path: &Path,
+) -> usize {
+    let canonical = |p: &Path| std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let changed_paths: Vec<PathBuf> = changed_inputs
+        .iter()
+        .map(|f| canonical(&inputs_path.join(f)))
+        .collect();
+    let stale_headers: Vec<DesignModelHeader> = load_design_model_headers_from_binary(inputs_path)
+        .into_iter()
+        .flatten()
+        .filter(|h| {
+            h.model_paths
+                .iter()
+                .any(|p| changed_paths.contains(&canonical(Path::new(p))))
+        })
+        .collect();
+    let stale_elements: HashSet<&String> = stale_headers.iter().flat_map(|h| &h.elements).collect();
+    let stale_relations: HashSet<&LabelledArcWithPorts> =
+        stale_headers.iter().flat_map(|h| &h.relations).collect();
+    let mut removed = 0;
+    for (header_path, header) in load_decision_model_headers_from_binary(identified_path) {
+        if header
+            .covered_elements
+            .iter()
+            .any(|e| stale_elements.contains(e))
+            || header
+                .covered_relations
+                .iter()
+                .any(|r| stale_relations.contains(r))
+        {
+            remove_decision_model_header_files(&header_path, &header);
+            // the same header is found in both binary encodings
+            if header_path
+                .extension()
+                .map(|x| x.eq_ignore_ascii_case("msgpack"))
+                .unwrap_or(false)
+            {
+                removed += 1;
+            }
+        }
+    }
+    debug!(
+        "{} element(s) and {} relation(s) of changed inputs made {} decision model(s) outdated",
+        stale_elements.len(),
+        stale_relations.len(),
+        removed
+    );
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeSet;
+
+    use idesyde_core::headers::ExplorationBid;
+    use idesyde_core::{
+        write_decision_model_header_to_path, write_design_model_header_to_path, ExplorationModule,
+    };
+
+    fn test_dir(name: &str) -> PathBuf {
+        let p = std::env::temp_dir().join(format!("idesyde-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&p);
+        std::fs::create_dir_all(&p).expect("Failed to create the test directory");
+        p
+    }
+
+    fn decision_header(category: &str, elements: &[&str]) -> DecisionModelHeader {
+        DecisionModelHeader {
+            category: category.to_string(),
+            body_path: None,
+            body_paths: HashMap::new(),
+            covered_elements: elements.iter().map(|e| e.to_string()).collect(),
+            objectives: HashMap::new(),
+            covered_relations: BTreeSet::new(),
+        }
+    }
+
+    /// Only answers its unique identifier, which is all that an exploration key needs.
+    struct NamedExplorer(&'static str);
+
+    impl ExplorationModule for NamedExplorer {
+        fn unique_identifier(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn available_criterias(&self, _m: Box<dyn DecisionModel>) -> HashMap<String, f32> {
+            HashMap::new()
+        }
+
+        fn bid(&self, _m: &Box<dyn DecisionModel>) -> ExplorationBid {
+            ExplorationBid {
+                unique_identifier: self.unique_identifier(),
+                can_explore: true,
+                properties: HashMap::new(),
+            }
+        }
+
+        fn explore(
+            &self,
+            _m: &Box<dyn DecisionModel>,
+            _max_sols: i64,
+            _total_timeout: i64,
+            _time_resolution: i64,
+            _memory_resolution: i64,
+        ) -> Box<dyn Iterator<Item = Box<dyn DecisionModel>>> {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    #[test]
+    fn only_what_comes_from_changed_inputs_is_invalidated() {
+        let dir = test_dir("cache-invalidation");
+        let (inputs, identified) = (dir.join("inputs"), dir.join("identified"));
+        std::fs::create_dir_all(&inputs).unwrap();
+        std::fs::create_dir_all(&identified).unwrap();
+        // both inputs are read by the same module into the same category, as the first one in another form of its path
+        for (name, element, model_path) in [
+            ("a", "x", inputs.join(".").join("a.fiodl")),
+            ("b", "y", inputs.join("b.fiodl")),
+        ] {
+            std::fs::write(inputs.join(format!("{}.fiodl", name)), name).unwrap();
+            let h = DesignModelHeader {
+                category: "ForSyDeIODesignModel".to_string(),
+                model_paths: vec![model_path.display().to_string()],
+                elements: BTreeSet::from([element.to_string()]),
+                relations: BTreeSet::new(),
+            };
+            write_design_model_header_to_path(&h, &inputs, name, "Module").unwrap();
+        }
+        for (prefix, elements) in [("0", vec!["x"]), ("1", vec!["y"]), ("2", vec!["x", "y"])] {
+            let h = decision_header("Identified", &elements);
+            write_decision_model_header_to_path(&h, &identified, prefix, "Module").unwrap();
+        }
+        assert_eq!(
+            invalidate_changed_inputs(&["a.fiodl".to_string()], &inputs, &identified),
+            2
+        );
+        let left: Vec<BTreeSet<String>> = load_decision_model_headers_from_binary(&identified)
+            .into_iter()
+            .map(|(_, h)| h.covered_elements)
+            .collect();
+        assert!(!left.is_empty());
+        assert!(left.iter().all(|e| e == &BTreeSet::from(["y".to_string()])));
+        assert_eq!(
+            invalidate_changed_inputs(&["c.fiodl".to_string()], &inputs, &identified),
+            0
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cached_solutions_are_found_while_their_headers_exist() {
+        let dir = test_dir("cache-hit");
+        let h = decision_header("Solved", &["x"]);
+        write_decision_model_header_to_path(&h, &dir, "0", "Explorer").unwrap();
+        let header_path = dir.join("header_0_Solved_Explorer.msgpack");
+        let mut cache = RunCache::default();
+        cache.explorations.insert(
+            "key".to_string(),
+            vec![("Explorer".to_string(), header_path.display().to_string())],
+        );
+        let cache_path = dir.join("cache.json");
+        assert!(cache.write(&cache_path));
+        let loaded = RunCache::load(&cache_path).expect("the cache should be loaded");
+        assert_eq!(loaded, cache);
+        assert_eq!(
+            loaded.cached_solutions("key"),
+            Some(vec![("Explorer".to_string(), h)])
+        );
+        assert_eq!(loaded.cached_solutions("other"), None);
+        std::fs::remove_file(&header_path).unwrap();
+        assert_eq!(loaded.cached_solutions("key"), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn decision_model_hashes_depend_on_contents_only() {
+        let dir = test_dir("cache-hash");
+        let with_body = |name: &str, body: &str| {
+            let bp = dir.join(format!("body_{}.json", name));
+            std::fs::write(&bp, body).unwrap();
+            let mut h = decision_header("Solved", &["x"]);
+            h.body_path = Some(bp.display().to_string());
+            h
+        };
+        let first = with_body("first", r#"{"a": 1, "b": [1, 2]}"#);
+        let reordered = with_body("reordered", r#"{"b": [1, 2], "a": 1}"#);
+        let other = with_body("other", r#"{"a": 1, "b": [2, 1]}"#);
+        assert_eq!(hash_decision_model(&first), hash_decision_model(&reordered));
+        assert_ne!(hash_decision_model(&first), hash_decision_model(&other));
+        // the elements are not merged into each other
+        assert_ne!(
+            hash_decision_model(&decision_header("Solved", &["ab", "c"])),
+            hash_decision_model(&decision_header("Solved", &["a", "bc"]))
+        );
+        assert_ne!(
+            hash_decision_model(&decision_header("Solvedx", &[])),
+            hash_decision_model(&decision_header("Solved", &["x"]))
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn exploration_keys_do_not_depend_on_the_order_of_the_biddings() {
+        let (e1, e2): (Box<dyn ExplorationModule>, Box<dyn ExplorationModule>) =
+            (Box::new(NamedExplorer("E1")), Box::new(NamedExplorer("E2")));
+        let (m1, m2): (Box<dyn DecisionModel>, Box<dyn DecisionModel>) = (
+            Box::new(decision_header("A", &["x"])),
+            Box::new(decision_header("B", &["y"])),
+        );
+        let key = exploration_key(&[(&e1, &m1), (&e2, &m2)], 1, 10, -1, -1);
+        assert_eq!(
+            key,
+            exploration_key(&[(&e2, &m2), (&e1, &m1)], 1, 10, -1, -1)
+        );
+        assert_eq!(
+            key,
+            exploration_key(&[(&e1, &m1), (&e2, &m2)], 1, 10, -1, -1)
+        );
+        assert_ne!(
+            key,
+            exploration_key(&[(&e1, &m2), (&e2, &m1)], 1, 10, -1, -1)
+        );
+        assert_ne!(
+            key,
+            exploration_key(&[(&e1, &m1), (&e2, &m2)], 2, 10, -1, -1)
+        );
+    }
+}
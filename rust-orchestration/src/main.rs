@@ -1,17 +1,29 @@
-use std::{path::Path, time::UNIX_EPOCH};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use clap::Parser;
 use env_logger::WriteStyle;
 use idesyde_core::{
-    headers::{load_decision_model_headers_from_binary, load_design_model_headers_from_binary},
+    headers::{
+        load_decision_model_headers_from_binary, load_design_model_headers_from_binary,
+        DesignModelHeader,
+    },
     DecisionModel, DesignModel, ExplorationModule, IdentificationModule,
 };
+use idesyde_heuristics::HeuristicExplorationModule;
 use log::{debug, error, info, warn, Level};
 use rayon::prelude::*;
 
 use crate::orchestration::compute_dominant_biddings;
 
+pub mod cache;
 pub mod orchestration;
+pub mod process;
+pub mod schemas;
+pub mod session;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -41,6 +53,36 @@ struct Args {
     )]
     run_path: Option<String>,
 
+    #[arg(
+        long,
+        help = "Adds a path where identification modules are searched for. Can be given many times.",
+        long_help = "Adds a path where identification modules are searched for. Can be given many times. \n\
+         If none is given, the paths in the IDESYDE_IMODULES_PATH environment variable are used, and otherwise the imodules directory in the working directory."
+    )]
+    imodules_path: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Adds a path where exploration modules are searched for. Can be given many times.",
+        long_help = "Adds a path where exploration modules are searched for. Can be given many times. \n\
+         If none is given, the paths in the IDESYDE_EMODULES_PATH environment variable are used, and otherwise the emodules directory in the working directory."
+    )]
+    emodules_path: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Sets the time-out in seconds of each request to an external module. \nIf not given, there is no time-out.",
+        long_help = "Sets the time-out in seconds of each request to an external module. \nIf not given, there is no time-out. \nModules that declare a time-out in their manifests use theirs instead."
+    )]
+    module_timeout: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Sets the memory limit in megabytes of each external module. \nIf not given, there is no limit.",
+        long_help = "Sets the memory limit in megabytes of each external module. \nIf not given, there is no limit. \nModules that declare a memory limit in their manifests use theirs instead."
+    )]
+    module_memory_limit: Option<u64>,
+
     #[arg(short, long, help = "Sets the verbosity of this run.")]
     verbosity: Option<String>,
 
@@ -76,6 +118,30 @@ struct Args {
         help = "For explorer with mandatory discretization, this factor is used for the memory downsizing resolution."
     )]
     x_memory_resolution: Option<i64>,
+
+    #[arg(
+        long,
+        help = "Sets the maximum number of dominant biddings explored concurrently. Default is 1. \nIf non-positive, all dominant biddings are explored.",
+        long_help = "Sets the maximum number of dominant biddings explored concurrently. Default is 1. \nIf non-positive, all dominant biddings are explored. \nThe explorers share the solution and time budgets, and equivalent solutions are only kept once."
+    )]
+    x_max_explorers: Option<i64>,
+}
+
+/// The paths where modules are searched for: the ones given in the command line, otherwise the ones in
+/// the `env_var` environment variable, and otherwise `default_dir` in the working directory.
+fn modules_paths(cli_paths: &[String], env_var: &str, default_dir: &str) -> Vec<PathBuf> {
+    if !cli_paths.is_empty() {
+        return cli_paths.iter().map(PathBuf::from).collect();
+    }
+    if let Some(env_paths) = std::env::var_os(env_var).filter(|v| !v.is_empty()) {
+        return std::env::split_paths(&env_paths).collect();
+    }
+    let default_path = std::env::current_dir()
+        .expect("Failed to get working directory.")
+        .join(default_dir);
+    std::fs::create_dir_all(&default_path)
+        .expect("Failed to create the default modules directory during identification.");
+    vec![default_path]
 }
 
 fn main() {
@@ -97,6 +163,7 @@ fn main() {
         .format_target(false)
         .format_module_path(false)
         .init();
+    process::propagate_termination_signals();
     if args.inputs.len() > 0 {
         rayon::ThreadPoolBuilder::new()
             .num_threads(args.parallel_jobs.unwrap_or(1))
@@ -123,82 +190,99 @@ fn main() {
         let run_path = Path::new(run_path_str);
         let output_path = Path::new(output_path_str);
         let inputs_path = &run_path.join("inputs");
-        let imodules_path = &std::env::current_dir()
-            .expect("Failed to get working directory.")
-            .join("imodules");
-        let emodules_path = &std::env::current_dir()
-            .expect("Failed to get working directory.")
-            .join("emodules");
+        let module_limits = process::ModuleLimits {
+            timeout: args.module_timeout.map(Duration::from_secs),
+            memory_limit_megabytes: args.module_memory_limit,
+        };
+        let imodules_paths =
+            modules_paths(&args.imodules_path, "IDESYDE_IMODULES_PATH", "imodules");
+        let emodules_paths =
+            modules_paths(&args.emodules_path, "IDESYDE_EMODULES_PATH", "emodules");
         let identified_path = run_path.join("identified");
         let explored_path = &run_path.join("explored");
         let reverse_path = &run_path.join("reversed");
+        let logs_path = &run_path.join("logs");
 
         std::fs::create_dir_all(run_path)
             .expect("Failed to create run path directory during identification.");
         std::fs::create_dir_all(inputs_path)
             .expect("Failed to create input directory during identification.");
-        std::fs::create_dir_all(imodules_path)
-            .expect("Failed to create imodules directory during identification.");
-        std::fs::create_dir_all(emodules_path)
-            .expect("Failed to create emodules directory during identification.");
         std::fs::create_dir_all(&identified_path)
             .expect("Failed to create identified directory during identification.");
         std::fs::create_dir_all(&explored_path)
             .expect("Failed to create explored directory during identification.");
         std::fs::create_dir_all(&reverse_path)
             .expect("Failed to create explored directory during identification.");
+        std::fs::create_dir_all(&logs_path)
+            .expect("Failed to create logs directory during identification.");
+        process::set_module_logs_path(logs_path);
 
+        let cache_path = run_path.join("cache.json");
+        let mut current_cache = cache::RunCache {
+            inputs: sorted_inputs
+                .iter()
+                .flat_map(|x| {
+                    let p = Path::new(x);
+                    p.file_name()
+                        .and_then(|f| f.to_str())
+                        .zip(cache::hash_file(p))
+                        .map(|(f, h)| (f.to_string(), h))
+                })
+                .collect(),
+            modules: imodules_paths
+                .iter()
+                .chain(emodules_paths.iter())
+                .flat_map(|p| cache::hash_files_in(p))
+                .collect(),
+            explorations: HashMap::new(),
+        };
         let mut is_input_incremental = true;
-        let mut current_input_stamp: Vec<(String, u64)> = sorted_inputs
-            .iter()
-            .map(|x| {
-                (
-                    x.to_owned(),
-                    std::fs::metadata(std::path::PathBuf::from(x))
-                        .ok()
-                        .and_then(|y| y.modified().ok())
-                        .and_then(|y| y.duration_since(UNIX_EPOCH).ok())
-                        .map(|y| y.as_secs())
-                        .unwrap_or(0),
-                )
-            })
-            .collect();
-        if let Ok(f) = std::fs::read(run_path.join("input_stamp.json")) {
-            let previous_input_stream: Vec<(String, u64)> =
-                serde_json::from_slice(&f).unwrap_or(Vec::new());
-            for x in &current_input_stamp {
-                if !previous_input_stream.contains(x) {
-                    is_input_incremental = false;
-                    break;
+        if let Some(previous_cache) = cache::RunCache::load(&cache_path) {
+            if previous_cache.modules != current_cache.modules {
+                debug!(
+                    "Detected that the modules changed. Cleaning the workspace before proceeding."
+                );
+                is_input_incremental = false;
+            } else {
+                let changed_inputs: Vec<String> = current_cache
+                    .inputs
+                    .iter()
+                    .filter(|(f, h)| {
+                        previous_cache
+                            .inputs
+                            .get(*f)
+                            .map(|ph| ph != *h)
+                            .unwrap_or(false)
+                    })
+                    .map(|(f, _)| f.to_owned())
+                    .collect();
+                if !changed_inputs.is_empty() {
+                    debug!(
+                        "Detected that {} input(s) changed. Removing what was identified from them.",
+                        changed_inputs.len()
+                    );
+                    cache::invalidate_changed_inputs(
+                        &changed_inputs,
+                        inputs_path,
+                        &identified_path,
+                    );
+                    // the reverse identified design models depend on all the inputs
+                    clean_directory(reverse_path);
                 }
-            }
-            for x in previous_input_stream {
-                if !current_input_stamp.contains(&x) {
-                    current_input_stamp.push(x);
+                for (f, h) in previous_cache.inputs {
+                    current_cache.inputs.entry(f).or_insert(h);
                 }
+                current_cache.explorations = previous_cache.explorations;
             }
         }
 
         if !is_input_incremental {
-            debug!("Detected that the inputs are not incremental. Cleaning the workspace before proceeding.");
-            for dir in vec![inputs_path, &identified_path, &explored_path, &reverse_path] {
-                if let Ok(d) = std::fs::read_dir(dir) {
-                    for x in d {
-                        if let Ok(f) = x {
-                            if std::fs::remove_file(f.path()).is_err() {
-                                warn!("Failed to remove workspace file during incremental reset. Trying to proceed");
-                            };
-                        }
-                    }
-                };
+            for dir in [inputs_path, &identified_path, explored_path, reverse_path] {
+                clean_directory(dir);
             }
         }
-        if let Ok(f) = std::fs::File::create(run_path.join("input_stamp.json")) {
-            if serde_json::to_writer(f, &current_input_stamp).is_err() {
-                warn!("Failed to produce increment stamps. Incremetability might not work.")
-            };
-        } else {
-            warn!("Failed to create increment stamps. Incremetability might not work.")
+        if !current_cache.write(&cache_path) {
+            warn!("Failed to write the run cache. Incremetability might not work.")
         }
 
         debug!("Copying input files");
@@ -217,33 +301,79 @@ fn main() {
 
         let mut imodules: Vec<Box<dyn IdentificationModule>> = Vec::new();
         let ex_imodules = orchestration::find_and_prepare_identification_modules(
-            imodules_path,
+            &imodules_paths,
             &identified_path,
             &inputs_path,
             &explored_path,
             &reverse_path,
             &output_path,
+            &module_limits,
+        );
+        let mut schema_registry = schemas::SchemaRegistry::new();
+        let native_imodules = orchestration::native_identification_modules(
+            &inputs_path,
+            &identified_path,
+            &output_path,
         );
         for eximod in ex_imodules {
+            // the executables of the linked modules are not run, as they would identify the same models
+            let replaced = eximod
+                .command_path()
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map_or(false, |s| {
+                    native_imodules.iter().any(|(name, _)| *name == s)
+                })
+                || native_imodules
+                    .iter()
+                    .any(|(_, m)| m.unique_identifier() == eximod.unique_identifier());
+            if replaced {
+                debug!(
+                    "Skipping external identification module {} as it is linked natively",
+                    &eximod.unique_identifier()
+                );
+                continue;
+            }
             debug!(
                 "Registering external identification module with identifier {}",
                 &eximod.unique_identifier()
             );
+            schema_registry.register(&eximod.unique_identifier(), eximod.decision_model_schemas());
             imodules.push(Box::new(eximod) as Box<dyn IdentificationModule>);
         }
+        for (_, nimod) in native_imodules {
+            debug!(
+                "Registering native identification module with identifier {}",
+                &nimod.unique_identifier()
+            );
+            schema_registry.register(&nimod.unique_identifier(), nimod.decision_model_schemas());
+            imodules.push(Box::new(nimod) as Box<dyn IdentificationModule>);
+        }
         let mut emodules: Vec<Box<dyn ExplorationModule>> = Vec::new();
         let ex_emodules = orchestration::find_exploration_modules(
-            emodules_path,
+            &emodules_paths,
             &identified_path,
             &explored_path,
+            &module_limits,
         );
         for exemod in ex_emodules {
             debug!(
                 "Registering external exploration module with identifier {}",
                 &exemod.unique_identifier()
             );
+            schema_registry.register(&exemod.unique_identifier(), exemod.decision_model_schemas());
             emodules.push(Box::new(exemod) as Box<dyn ExplorationModule>);
         }
+        let heuristic_emodule = HeuristicExplorationModule::new(explored_path);
+        debug!(
+            "Registering native exploration module with identifier {}",
+            heuristic_emodule.unique_identifier()
+        );
+        emodules.push(Box::new(heuristic_emodule) as Box<dyn ExplorationModule>);
+        debug!(
+            "Collected the schemas of {} decision model categories",
+            schema_registry.categories().len()
+        );
 
         // a zero-step to make design model headers available
         imodules.par_iter().for_each(|imodule| {
@@ -253,11 +383,17 @@ fn main() {
         //     imodule.identification_step(0, &Vec::new(), &Vec::new());
         // }
         // now we can proceed safely
-        let design_model_headers = load_design_model_headers_from_binary(&inputs_path);
-        let design_models: Vec<Box<dyn DesignModel>> = design_model_headers
-            .iter()
-            .map(|h| Box::new(h.to_owned()) as Box<dyn DesignModel>)
-            .collect();
+        let design_models: Vec<Box<dyn DesignModel>> =
+            load_design_model_headers_from_binary(&inputs_path)
+                .into_iter()
+                .flat_map(|res| match res {
+                    Ok(h) => Some(Box::new(h) as Box<dyn DesignModel>),
+                    Err(e) => {
+                        warn!("Ignoring a design model header: {}", e);
+                        None
+                    }
+                })
+                .collect();
         let mut pre_identified: Vec<Box<dyn DecisionModel>> =
             load_decision_model_headers_from_binary(&identified_path)
                 .iter()
@@ -268,6 +404,7 @@ fn main() {
             &design_models,
             &mut pre_identified,
             1,
+            &schema_registry,
         );
         info!("Identified {} decision model(s)", identified.len());
         let identified_refs = identified.iter().collect();
@@ -360,7 +497,12 @@ fn main() {
         //         "Orchestrator",
         //     );
         // }
-        if let Some((e, m)) = dominant_biddings.first() {
+        let portfolio_size = match args.x_max_explorers.unwrap_or(1) {
+            n if n > 0 => (n as usize).min(dominant_biddings.len()),
+            _ => dominant_biddings.len(),
+        };
+        let portfolio = &dominant_biddings[..portfolio_size];
+        for (e, m) in portfolio {
             debug!(
                 "Proceeding to explore {} with {}",
                 m.unique_identifier(),
@@ -376,80 +518,155 @@ fn main() {
             (None, Some(n)) => info!("Starting exploration up to {} solutions.", n),
             (None, None) => info!("Starting exploration until completion."),
         }
-        if let Some((exp, decision_model)) = dominant_biddings.first() {
-            // let (mut tx, rx) = spmc::channel();
-            let sols_found: Vec<Box<dyn DecisionModel>> = exp
-                .explore(
-                    &decision_model,
-                    args.x_max_solutions.unwrap_or(0),
-                    args.x_total_time_out.unwrap_or(0),
-                    args.x_time_resolution.unwrap_or(-1),
-                    args.x_memory_resolution.unwrap_or(-1),
-                )
-                .enumerate()
-                // .par_bridge()
-                .map(|(i, sol)| {
-                    debug!("Found a new solution. Total count is {}.", i + 1);
-                    sol
-                })
-                .collect();
-            info!(
-                "Finished exploration with {} solution(s).",
-                sols_found.len()
-            );
-            if !sols_found.is_empty() {
-                let total_reversed: usize = imodules
+        if !portfolio.is_empty() {
+            let mut archive = orchestration::ParetoArchive::default();
+            // every solution is reverse identified as soon as it arrives, so that it is usable even if
+            // the exploration is stopped later on, and what was reverse identified from a solution is
+            // removed again once a later solution dominates it
+            let mut on_solution = |explorer: &str,
+                                   sols_found: usize,
+                                   sol: Box<dyn DecisionModel>| {
+                debug!(
+                    "Found a new solution with {}. Total count is {}.",
+                    explorer,
+                    sols_found + 1
+                );
+                if !schema_registry.accepts(&sol.header()) {
+                    orchestration::remove_decision_model_files(explored_path, &sol.header());
+                    return false;
+                }
+                let (kept, dominated) = archive.offer(explorer, sol.as_ref());
+                for s in &dominated {
+                    archive.remove_dominated_files(s, explored_path, reverse_path);
+                }
+                if !dominated.is_empty() {
+                    debug!(
+                        "Dropped {} solution(s) dominated by the new one.",
+                        dominated.len()
+                    );
+                }
+                if !kept {
+                    debug!("Dropped the new solution as it is dominated by a previous one.");
+                    orchestration::remove_decision_model_files(explored_path, &sol.header());
+                    return true;
+                }
+                let solv = vec![sol];
+                let reversed: Vec<(PathBuf, DesignModelHeader)> = imodules
                     .par_iter()
                     .enumerate()
-                    .map(|(_, imodule)| {
-                        let mut n_reversed = 0;
-                        for reverse in imodule.reverse_identification(&sols_found, &design_models) {
-                            idesyde_core::write_design_model_header_to_path(
-                                &reverse.header(),
-                                &reverse_path,
-                                format!("{}", n_reversed).as_str(),
+                    .flat_map(|(j, imodule)| {
+                        let mut reversed = Vec::new();
+                        for reverse in imodule.reverse_identification(&solv, &design_models) {
+                            let h = reverse.header();
+                            let prefix = format!("{}_{}_{}", sols_found, j, reversed.len());
+                            if let Err(e) = idesyde_core::write_design_model_header_to_path(
+                                &h,
+                                reverse_path,
+                                prefix.as_str(),
                                 "Orchestrator",
-                            );
-                            n_reversed += 1;
+                            ) {
+                                warn!(
+                                    "Failed to keep a design model reverse identified by {}: {}",
+                                    imodule.unique_identifier(),
+                                    e
+                                );
+                                continue;
+                            }
                             debug!(
                                 "Reverse identified a {} design model.",
                                 reverse.unique_identifier()
                             );
+                            reversed.push((
+                                reverse_path.join(format!(
+                                    "header_{}_{}_Orchestrator.msgpack",
+                                    prefix, h.category
+                                )),
+                                h,
+                            ));
                         }
-                        n_reversed
+                        reversed
                     })
-                    .sum();
+                    .collect();
+                archive.add_reversed(&solv[0].header(), reversed);
+                true
+            };
+            let exploration_key = cache::exploration_key(
+                portfolio,
+                args.x_max_solutions.unwrap_or(0),
+                args.x_total_time_out.unwrap_or(0),
+                args.x_time_resolution.unwrap_or(-1),
+                args.x_memory_resolution.unwrap_or(-1),
+            );
+            let found_per_explorer = match current_cache
+                .cached_solutions(&exploration_key)
+                .filter(|cached| !cached.is_empty())
+            {
+                Some(cached) => {
+                    info!(
+                        "Reusing {} cached solution(s) of the same exploration.",
+                        cached.len()
+                    );
+                    let mut found_per_explorer: HashMap<String, usize> = HashMap::new();
+                    for (explorer, h) in cached {
+                        let sols_found = found_per_explorer.values().sum();
+                        if on_solution(explorer.as_str(), sols_found, Box::new(h)) {
+                            *found_per_explorer.entry(explorer).or_insert(0) += 1;
+                        }
+                    }
+                    found_per_explorer
+                }
+                None => {
+                    // the solutions of other explorations would be overwritten
+                    clean_directory(explored_path);
+                    current_cache.explorations.clear();
+                    orchestration::explore_portfolio(
+                        portfolio,
+                        args.x_max_solutions.unwrap_or(0),
+                        args.x_total_time_out.unwrap_or(0),
+                        args.x_time_resolution.unwrap_or(-1),
+                        args.x_memory_resolution.unwrap_or(-1),
+                        &mut on_solution,
+                    )
+                }
+            };
+            current_cache.explorations.insert(
+                exploration_key,
+                archive
+                    .solution_paths(explored_path)
+                    .into_iter()
+                    .flat_map(|(explorer, p)| {
+                        p.and_then(|x| x.to_str().map(|y| y.to_string()))
+                            .map(|x| (explorer, x))
+                    })
+                    .collect(),
+            );
+            if !current_cache.write(&cache_path) {
+                warn!("Failed to write the run cache. Incremetability might not work.")
+            }
+            let sols_found: usize = found_per_explorer.values().sum();
+            info!("Finished exploration with {} solution(s).", sols_found);
+            for (explorer, n) in &found_per_explorer {
+                info!("{} found {} of the solution(s).", explorer, n);
+            }
+            info!(
+                "Kept {} non-dominated solution(s).",
+                archive.solutions.len()
+            );
+            if !archive.write_summary(explored_path, &run_path.join("pareto.json")) {
+                warn!("Failed to write the Pareto summary of the exploration.");
+            }
+            if sols_found > 0 {
                 info!(
                     "Finished reverse identification of {} design model(s).",
-                    total_reversed
+                    archive
+                        .solutions
+                        .iter()
+                        .map(|s| s.reversed.len())
+                        .sum::<usize>()
                 );
             } else {
                 info!("No solution to reverse identify.");
             }
-            // for (i, sol) in exp
-            //     .explore(
-            //         &decision_model,
-            //         args.x_max_solutions.unwrap_or(0),
-            //         args.x_total_time_out.unwrap_or(0),
-            //         args.x_time_resolution.unwrap_or(-1),
-            //         args.x_memory_resolution.unwrap_or(-1),
-            //     )
-            //     .enumerate()
-            // {
-            //     sols_found += 1;
-            //     let solv = vec![sol];
-            //     debug!("Found a new solution. Total count is {}.", i + 1);
-            //     imodules.par_iter().for_each(|imodule| {
-            //         for reverse in imodule.reverse_identification(&solv, &design_models) {
-            //             idesyde_core::write_design_model_header_to_path(
-            //                 &reverse.header(),
-            //                 &reverse_path,
-            //                 format!("{}_{}", "reversed_", i).as_str(),
-            //                 "Orchestrator",
-            //             );
-            //         }
-            //     });
-            // }
         } else {
             info!("No dominant bidding to start exploration. Finished.")
         }
@@ -457,3 +674,16 @@ fn main() {
         info!("At least one input design model is necessary")
     }
 }
+
+/// Removes all the files directly under `dir`, which is part of the workspace.
+fn clean_directory(dir: &Path) {
+    if let Ok(d) = std::fs::read_dir(dir) {
+        for f in d.flatten() {
+            if f.path().is_file() && std::fs::remove_file(f.path()).is_err() {
+                warn!(
+                    "Failed to remove workspace file during incremental reset. Trying to proceed"
+                );
+            }
+        }
+    }
+}
@@ -1,40 +1,130 @@
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::HashSet;
 
 use std::hash::Hash;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Child;
 use std::process::Stdio;
+use std::sync::atomic;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
 
 use idesyde_core::headers::load_decision_model_header_from_path;
 use idesyde_core::headers::load_decision_model_headers_from_binary;
 use idesyde_core::headers::DecisionModelHeader;
 use idesyde_core::headers::DesignModelHeader;
 use idesyde_core::headers::ExplorationBid;
+use idesyde_core::protocol::is_compatible_protocol_version;
+use idesyde_core::protocol::ModuleDescription;
+use idesyde_core::protocol::ModuleKind;
+use idesyde_core::protocol::ModuleLauncher;
+use idesyde_core::protocol::ModuleManifest;
+use idesyde_core::protocol::ModuleRequest;
+use idesyde_core::protocol::ModuleResponse;
+use idesyde_core::protocol::DESCRIBE_ARG;
+use idesyde_core::protocol::PROTOCOL_VERSION;
+use idesyde_core::protocol::SCHEMAS_ARG;
 use idesyde_core::DecisionModel;
 use idesyde_core::DesignModel;
 use idesyde_core::ExplorationModule;
 use idesyde_core::IdentificationModule;
+use idesyde_core::IdesydeError;
+use idesyde_core::StandaloneIdentificationModule;
 use log::debug;
 use log::warn;
 
 use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+
+use crate::process::forward_module_logs;
+use crate::process::run_module;
+use crate::process::run_module_quietly;
+use crate::process::spawn_module;
+use crate::process::stop_module;
+use crate::process::ModuleLimits;
+use crate::schemas::SchemaRegistry;
+use crate::session::module_command;
+use crate::session::ModuleServer;
+use crate::session::ModuleSession;
+use crate::session::CANCELLATION_POLL;
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct ExternalIdentificationModule {
+    unique_identifier_: String,
+    launcher: ModuleLauncher,
+    limits: ModuleLimits,
     command_path_: PathBuf,
     inputs_path_: PathBuf,
     identified_path_: PathBuf,
     solved_path_: PathBuf,
     reverse_path_: PathBuf,
     output_path_: PathBuf,
+    decision_model_schemas: BTreeSet<String>,
+    server: ModuleServer,
+}
+
+/// Reads a MsgPack header that `module` reported on its output, telling which file and module were wrong if it fails.
+fn read_module_header<H: DeserializeOwned>(module: &str, p: &str) -> Result<H, IdesydeError> {
+    let b = std::fs::read(p).map_err(|e| IdesydeError::ModuleProtocol {
+        module: module.to_string(),
+        message: format!("it reported the header {} which cannot be read: {}", p, e),
+    })?;
+    rmp_serde::from_slice::<H>(b.as_slice()).map_err(|e| IdesydeError::ModuleProtocol {
+        module: module.to_string(),
+        message: format!(
+            "it reported the header {} which cannot be decoded: {}",
+            p, e
+        ),
+    })
+}
+
+/// Reads the headers that `module` reported in the response to a request, as it would report them on its output
+/// if it was run once.
+fn module_response_header_paths(
+    module: &str,
+    response: Result<ModuleResponse, IdesydeError>,
+) -> Vec<String> {
+    match response {
+        Ok(ModuleResponse::Headers(paths)) => paths,
+        Ok(r) => {
+            warn!(
+                "Module {} answered {:?} instead of headers. Check this module for correctness.",
+                module, r
+            );
+            Vec::new()
+        }
+        Err(e) => {
+            warn!("{}. Check this module for correctness.", e);
+            Vec::new()
+        }
+    }
+}
+
+impl ExternalIdentificationModule {
+    pub fn command_path(&self) -> &Path {
+        &self.command_path_
+    }
+
+    /// The schemas of the decision models of this module, if it described itself.
+    pub fn decision_model_schemas(&self) -> &BTreeSet<String> {
+        &self.decision_model_schemas
+    }
 }
 
 impl IdentificationModule for ExternalIdentificationModule {
     fn unique_identifier(&self) -> String {
-        self.command_path_.to_str().unwrap().to_string()
+        self.unique_identifier_.to_owned()
     }
 
     fn identification_step(
@@ -43,51 +133,40 @@ impl IdentificationModule for ExternalIdentificationModule {
         _design_models: &Vec<Box<dyn DesignModel>>,
         _decision_models: &Vec<Box<dyn DecisionModel>>,
     ) -> Vec<Box<dyn DecisionModel>> {
-        let is_java = self
-            .command_path_
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| s == "jar")
-            .unwrap_or(false);
-        let output = match is_java {
-            true => std::process::Command::new("java")
-                .arg("-jar")
-                .arg(&self.command_path_)
-                .arg("-m")
-                .arg(&self.inputs_path_)
-                .arg("-i")
-                .arg(&self.identified_path_)
-                .arg("-t")
-                .arg(iteration.to_string())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output(),
-            false => std::process::Command::new(&self.command_path_)
+        if let Some(response) = self
+            .server
+            .call(&ModuleRequest::Identification { iteration })
+        {
+            return module_response_header_paths(&self.unique_identifier(), response)
+                .iter()
+                .flat_map(|p| {
+                    read_module_header::<DecisionModelHeader>(&self.unique_identifier(), p)
+                        .map(|h| Box::new(h.with_typed_relations()) as Box<dyn DecisionModel>)
+                        .map_err(|e| warn!("{}. Check this module for correctness.", e))
+                        .ok()
+                })
+                .collect();
+        }
+        let output = run_module(
+            &self.unique_identifier(),
+            module_command(self.launcher, &self.command_path_, &self.limits)
                 .arg("-m")
                 .arg(&self.inputs_path_)
                 .arg("-i")
                 .arg(&self.identified_path_)
                 .arg("-t")
-                .arg(iteration.to_string())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output(),
-        };
-        if let Ok(out) = output {
+                .arg(iteration.to_string()),
+            &self.limits,
+        );
+        if let Some(out) = output {
             if let Ok(s) = String::from_utf8(out.stdout) {
                 let identified: Vec<Box<dyn DecisionModel>> = s
                     .lines()
                     .flat_map(|p| {
-                        if let Ok(b) = std::fs::read(p) {
-                            if let Ok(header) = rmp_serde::from_slice::<DecisionModelHeader>(b.as_slice()) {
-                                return Some(Box::new(header) as Box<dyn DecisionModel>);
-                            } else {
-                                warn!("Failed to deserialize header coming from {}. Check this module for correctness.", self.unique_identifier());
-                            }
-                        } else {
-                            warn!("Unexpected header file from module {}. Check this module for correctness.", self.unique_identifier())
-                        }
-                        None
+                        read_module_header::<DecisionModelHeader>(&self.unique_identifier(), p)
+                            .map(|h| Box::new(h.with_typed_relations()) as Box<dyn DecisionModel>)
+                            .map_err(|e| warn!("{}. Check this module for correctness.", e))
+                            .ok()
                     })
                     .collect();
                 return identified;
@@ -96,57 +175,89 @@ impl IdentificationModule for ExternalIdentificationModule {
         Vec::new()
     }
 
+    /// Reverse identifies only the given solutions, which must have their headers in the solved path.
+    ///
+    /// A module started as a server is sent the paths of their headers. Otherwise, the module is run with a
+    /// solved path of its own, next to the reverse path, that holds only these headers, and it writes its
+    /// design models to that path as well, so that the ones of different solutions are not overwritten.
     fn reverse_identification(
         &self,
-        _decision_model: &Vec<Box<dyn DecisionModel>>,
+        decision_model: &Vec<Box<dyn DecisionModel>>,
         _design_model: &Vec<Box<dyn DesignModel>>,
     ) -> Vec<Box<dyn DesignModel>> {
-        let is_java = self
-            .command_path_
-            .extension()
+        let solution_paths = solution_header_paths(&self.solved_path_, decision_model);
+        if solution_paths.is_empty() {
+            return Vec::new();
+        }
+        if let Some(response) = self.server.call(&ModuleRequest::ReverseIdentification {
+            solution_paths: solution_paths
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+        }) {
+            return module_response_header_paths(&self.unique_identifier(), response)
+                .iter()
+                .flat_map(|p| {
+                    read_module_header::<DesignModelHeader>(&self.unique_identifier(), p)
+                        .map(|h| Box::new(h.with_typed_relations()) as Box<dyn DesignModel>)
+                        .map_err(|e| warn!("{}. Check this module for correctness.", e))
+                        .ok()
+                })
+                .collect();
+        }
+        let solution_name = solution_paths[0]
+            .file_stem()
             .and_then(|s| s.to_str())
-            .map(|s| s == "jar")
-            .unwrap_or(false);
-        let output = match is_java {
-            true => std::process::Command::new("java")
-                .arg("-jar")
-                .arg(&self.command_path_)
-                .arg("-m")
-                .arg(&self.inputs_path_)
-                .arg("-s")
-                .arg(&self.solved_path_)
-                .arg("-r")
-                .arg(&self.reverse_path_)
-                .arg("-o")
-                .arg(&self.output_path_)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output(),
-            false => std::process::Command::new(&self.command_path_)
+            .unwrap_or_default()
+            .to_string();
+        let reverse_path =
+            self.reverse_path_
+                .join(format!("{}_{}", solution_name, self.unique_identifier()));
+        let solved_path = reverse_path.join("solved");
+        if let Err(e) = std::fs::create_dir_all(&solved_path) {
+            warn!(
+                "Failed to create {} to reverse identify with {}: {}",
+                solved_path.display(),
+                self.unique_identifier(),
+                e
+            );
+            return Vec::new();
+        }
+        for p in &solution_paths {
+            if let Some(name) = p.file_name() {
+                if let Err(e) = std::fs::copy(p, solved_path.join(name)) {
+                    warn!("Failed to copy {}: {}", p.display(), e);
+                }
+            }
+        }
+        let output = run_module(
+            &self.unique_identifier(),
+            module_command(self.launcher, &self.command_path_, &self.limits)
                 .arg("-m")
                 .arg(&self.inputs_path_)
                 .arg("-s")
-                .arg(&self.solved_path_)
+                .arg(&solved_path)
                 .arg("-r")
-                .arg(&self.reverse_path_)
+                .arg(&reverse_path)
                 .arg("-o")
-                .arg(&self.output_path_)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output(),
-        };
-        if let Ok(out) = output {
+                .arg(&self.output_path_),
+            &self.limits,
+        );
+        if std::fs::remove_dir_all(&solved_path).is_err() {
+            warn!(
+                "Failed to remove {}. This is a benign error. Continuing",
+                solved_path.display()
+            );
+        }
+        if let Some(out) = output {
             if let Ok(s) = String::from_utf8(out.stdout) {
                 let reversed: Vec<Box<dyn DesignModel>> = s
                     .lines()
-                    .map(|p| {
-                        let b = std::fs::read(p)
-                            .expect("Failed to read header file from disk during identification");
-                        let header = rmp_serde::from_slice::<DesignModelHeader>(b.as_slice())
-                            .expect(
-                            "Failed to deserialize header file from disk during identification.",
-                        );
-                        Box::new(header) as Box<dyn DesignModel>
+                    .flat_map(|p| {
+                        read_module_header::<DesignModelHeader>(&self.unique_identifier(), p)
+                            .map(|h| Box::new(h.with_typed_relations()) as Box<dyn DesignModel>)
+                            .map_err(|e| warn!("{}. Check this module for correctness.", e))
+                            .ok()
                     })
                     .collect();
                 return reversed;
@@ -156,16 +267,235 @@ impl IdentificationModule for ExternalIdentificationModule {
     }
 }
 
+/// Whether `a` and `b` are headers of the same decision model. Unlike header equality, which only
+/// compares what the decision models cover, this also compares the bodies they point to, since all
+/// the solutions of a decision model cover the same elements.
+pub fn is_same_decision_model(a: &DecisionModelHeader, b: &DecisionModelHeader) -> bool {
+    a == b && a.body_path == b.body_path && a.body_paths == b.body_paths
+}
+
+/// Finds the paths of the (MsgPack) headers in `solved_path` of the given solutions.
+fn solution_header_paths(solved_path: &Path, solutions: &[Box<dyn DecisionModel>]) -> Vec<PathBuf> {
+    let headers: Vec<DecisionModelHeader> = solutions.iter().map(|s| s.header()).collect();
+    let mut paths: Vec<PathBuf> = load_decision_model_headers_from_binary(solved_path)
+        .into_iter()
+        .filter(|(p, h)| {
+            p.extension()
+                .is_some_and(|x| x.eq_ignore_ascii_case("msgpack"))
+                && headers.iter().any(|s| is_same_decision_model(s, h))
+        })
+        .map(|(p, _)| p)
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// An identification module linked into the orchestrator, so that it runs in the same process.
+///
+/// It exchanges decision models just like an external module: every decision model it identifies is written
+/// to the identified path and only its header is returned, and the bodies of the headers it is given are
+/// loaded again before they are used. This way all modules, native or external, see the same decision models.
+/// The design models are read once from the inputs path, the first time they are needed.
+pub struct NativeIdentificationModule {
+    module: StandaloneIdentificationModule,
+    inputs_path_: PathBuf,
+    identified_path_: PathBuf,
+    output_path_: PathBuf,
+    design_models: OnceLock<Vec<Box<dyn DesignModel>>>,
+}
+
+impl NativeIdentificationModule {
+    pub fn new(
+        module: StandaloneIdentificationModule,
+        inputs_path: &Path,
+        identified_path: &Path,
+        output_path: &Path,
+    ) -> NativeIdentificationModule {
+        NativeIdentificationModule {
+            module,
+            inputs_path_: inputs_path.to_path_buf(),
+            identified_path_: identified_path.to_path_buf(),
+            output_path_: output_path.to_path_buf(),
+            design_models: OnceLock::new(),
+        }
+    }
+
+    pub fn decision_model_schemas(&self) -> &HashSet<String> {
+        &self.module.decision_model_schemas
+    }
+
+    /// Reads the input models this module understands, writing their headers next to them just like
+    /// an external module does.
+    fn design_models(&self) -> &Vec<Box<dyn DesignModel>> {
+        self.design_models.get_or_init(|| {
+            let mut design_models = Vec::new();
+            if let Ok(ls) = self.inputs_path_.read_dir() {
+                for p in ls.flatten().map(|e| e.path()) {
+                    let is_header = p
+                        .file_name()
+                        .and_then(|f| f.to_str())
+                        .map_or(false, |f| f.starts_with("header"));
+                    if is_header || !p.is_file() {
+                        continue;
+                    }
+                    if let Some(m) = self.module.read_design_model(&p) {
+                        let mut h = m.header();
+                        h.model_paths.push(p.to_string_lossy().to_string());
+                        // named after the input, so that the headers of different inputs do not overwrite each other
+                        if let Err(e) = idesyde_core::write_design_model_header_to_path(
+                            &h,
+                            &self.inputs_path_,
+                            &p.file_stem()
+                                .map(|f| f.to_string_lossy())
+                                .unwrap_or_default(),
+                            &self.module.unique_identifier(),
+                        ) {
+                            warn!("{}", e);
+                        }
+                        design_models.push(m);
+                    }
+                }
+            }
+            design_models
+        })
+    }
+
+    /// Gets the decision models as this module knows them, i.e. with the bodies of the headers loaded.
+    ///
+    /// All the decision models exchanged during identification are headers, so the ones that are not
+    /// cannot be loaded again and are left out with a warning.
+    fn known_decision_models(
+        &self,
+        decision_models: &[Box<dyn DecisionModel>],
+    ) -> Vec<Box<dyn DecisionModel>> {
+        decision_models
+            .iter()
+            .flat_map(|m| {
+                let h = m.downcast_ref::<DecisionModelHeader>();
+                if h.is_none() {
+                    warn!(
+                        "Module {} left out a {} decision model that was not given as a header",
+                        self.unique_identifier(),
+                        m.unique_identifier()
+                    );
+                }
+                h
+            })
+            .map(|h| {
+                self.module
+                    .decision_header_to_model(h)
+                    .unwrap_or_else(|| Box::new(h.to_owned()))
+            })
+            .collect()
+    }
+}
+
+impl IdentificationModule for NativeIdentificationModule {
+    fn unique_identifier(&self) -> String {
+        self.module.unique_identifier()
+    }
+
+    fn identification_step(
+        &self,
+        iteration: i32,
+        _design_models: &Vec<Box<dyn DesignModel>>,
+        decision_models: &Vec<Box<dyn DecisionModel>>,
+    ) -> Vec<Box<dyn DecisionModel>> {
+        let known = self.known_decision_models(decision_models);
+        let identified = self
+            .module
+            .identification_step(iteration, self.design_models(), &known);
+        let prefix = format!("{:0>16}", iteration);
+        // the written headers are returned, just like an external module, so that every module sees the bodies
+        identified
+            .iter()
+            .flat_map(|m| {
+                idesyde_core::write_decision_model_to_path(
+                    m,
+                    &self.identified_path_,
+                    &prefix,
+                    &self.unique_identifier(),
+                )
+                .map_err(|e| warn!("{}", e))
+                .ok()
+            })
+            .map(|h| Box::new(h) as Box<dyn DecisionModel>)
+            .collect()
+    }
+
+    fn reverse_identification(
+        &self,
+        decision_models: &Vec<Box<dyn DecisionModel>>,
+        _design_models: &Vec<Box<dyn DesignModel>>,
+    ) -> Vec<Box<dyn DesignModel>> {
+        let known = self.known_decision_models(decision_models);
+        self.module
+            .reverse_identification(&known, self.design_models())
+            .iter()
+            .map(|m| {
+                let mut h = m.header();
+                if let Some(written) = self.module.write_design_model(m.as_ref(), &self.output_path_) {
+                    h.model_paths.push(written.to_string_lossy().to_string());
+                }
+                Box::new(h) as Box<dyn DesignModel>
+            })
+            .collect()
+    }
+}
+
+/// The identification modules that are linked into the orchestrator, with the names of the executables
+/// they make unnecessary.
+pub fn native_identification_modules(
+    inputs_path: &Path,
+    identified_path: &Path,
+    output_path: &Path,
+) -> Vec<(&'static str, NativeIdentificationModule)> {
+    vec![
+        (
+            "idesyde-common",
+            NativeIdentificationModule::new(
+                idesyde_common::common_identification_module(),
+                inputs_path,
+                identified_path,
+                output_path,
+            ),
+        ),
+        (
+            "idesyde-bridge-forsyde-io",
+            NativeIdentificationModule::new(
+                idesyde_bridge_forsyde_io::forsyde_io_identification_module(),
+                inputs_path,
+                identified_path,
+                output_path,
+            ),
+        ),
+    ]
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct ExternalExplorationModule {
+    unique_identifier_: String,
+    launcher: ModuleLauncher,
+    limits: ModuleLimits,
+    decision_model_categories: BTreeSet<String>,
     command_path_: PathBuf,
     identified_path_: PathBuf,
     solved_path_: PathBuf,
+    decision_model_schemas: BTreeSet<String>,
+    server: Arc<ModuleServer>,
+    cancellation: ExplorationCancellation,
+}
+
+impl ExternalExplorationModule {
+    /// The schemas of the decision models of this module, if it described itself.
+    pub fn decision_model_schemas(&self) -> &BTreeSet<String> {
+        &self.decision_model_schemas
+    }
 }
 
 impl ExplorationModule for ExternalExplorationModule {
     fn unique_identifier(&self) -> String {
-        self.command_path_.to_str().unwrap().to_string()
+        self.unique_identifier_.to_owned()
     }
 
     fn available_criterias(
@@ -176,41 +506,63 @@ impl ExplorationModule for ExternalExplorationModule {
     }
 
     fn bid(&self, m: &Box<dyn idesyde_core::DecisionModel>) -> ExplorationBid {
+        // the module is not even asked about decision models its manifest says it does not take
+        if !self.decision_model_categories.is_empty()
+            && !self
+                .decision_model_categories
+                .contains(&m.header().category)
+        {
+            return ExplorationBid {
+                unique_identifier: self.unique_identifier(),
+                can_explore: false,
+                properties: HashMap::new(),
+            };
+        }
         let headers = load_decision_model_headers_from_binary(&self.identified_path_);
         let chosen_path = headers
             .iter()
             .find(|(_, h)| h == &m.header())
             .map(|(p, _)| p)
             .unwrap();
-        let is_java = self
-            .command_path_
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| s == "jar")
-            .unwrap_or(false);
-        let output = match is_java {
-            true => std::process::Command::new("java")
-                .arg("-jar")
-                .arg(&self.command_path_)
-                .arg("-c")
-                .arg(chosen_path)
-                .arg("-i")
-                .arg(&self.identified_path_)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output(),
-            false => std::process::Command::new(&self.command_path_)
+        if let Some(response) = self.server.call(&ModuleRequest::Bid {
+            decision_model_path: chosen_path.display().to_string(),
+        }) {
+            return match response {
+                Ok(ModuleResponse::Bid(bid)) => bid,
+                Ok(r) => {
+                    warn!("Exploration module {} answered {:?} instead of a bid. Assuming it cannot explore.", self.unique_identifier(), r);
+                    ExplorationBid {
+                        unique_identifier: self.unique_identifier(),
+                        can_explore: false,
+                        properties: HashMap::new(),
+                    }
+                }
+                Err(e) => {
+                    warn!("{}. Assuming it cannot explore.", e);
+                    ExplorationBid {
+                        unique_identifier: self.unique_identifier(),
+                        can_explore: false,
+                        properties: HashMap::new(),
+                    }
+                }
+            };
+        }
+        let output = run_module(
+            &self.unique_identifier(),
+            module_command(self.launcher, &self.command_path_, &self.limits)
                 .arg("-c")
                 .arg(chosen_path)
                 .arg("-i")
-                .arg(&self.identified_path_)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output(),
+                .arg(&self.identified_path_),
+            &self.limits,
+        );
+        let Some(o) = output.map(|out| out.stdout) else {
+            return ExplorationBid {
+                unique_identifier: self.unique_identifier(),
+                can_explore: false,
+                properties: HashMap::new(),
+            };
         };
-        let o = output
-            .expect("Failed to get combination from exploration module.")
-            .stdout;
         match serde_json::from_slice(&o.as_slice()) {
             Ok(bid) => bid,
             Err(e) => {
@@ -243,34 +595,38 @@ impl ExplorationModule for ExternalExplorationModule {
             .find(|(_, h)| h == &m.header())
             .map(|(p, _)| p)
             .unwrap();
-        let is_java = self
-            .command_path_
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| s == "jar")
-            .unwrap_or(false);
-        let child_opt = match is_java {
-            true => std::process::Command::new("java")
-                .arg("-jar")
-                .arg(&self.command_path_)
-                .arg("-e")
-                .arg(chosen_path)
-                .arg("-i")
-                .arg(&self.identified_path_)
-                .arg("-o")
-                .arg(&self.solved_path_)
-                .arg("--maximum-solutions")
-                .arg(format!("{}", max_sols))
-                .arg("--total-timeout")
-                .arg(format!("{}", total_timeout))
-                .arg("--time-resolution")
-                .arg(format!("{}", time_resolution))
-                .arg("--memory-resolution")
-                .arg(format!("{}", memory_resolution))
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn(),
-            false => std::process::Command::new(&self.command_path_)
+        let deadline = if total_timeout > 0 {
+            Some(Instant::now() + Duration::from_secs(total_timeout as u64))
+        } else {
+            None
+        };
+        let cancelled = self.cancellation.flag();
+        if let Some(mut session) = self.server.take() {
+            let deadline = self.limits.deadline_before(deadline);
+            let request = ModuleRequest::Explore {
+                decision_model_path: chosen_path.display().to_string(),
+                maximum_solutions: max_sols,
+                total_timeout,
+                time_resolution,
+                memory_resolution,
+            };
+            return match session.request(&request) {
+                Ok(()) => Box::new(SessionExplorationIterator {
+                    uid: self.unique_identifier(),
+                    server: self.server.clone(),
+                    session: Some(session),
+                    deadline,
+                    cancelled,
+                }),
+                Err(e) => {
+                    warn!("{}", e);
+                    Box::new(std::iter::empty())
+                }
+            };
+        }
+        let deadline = self.limits.deadline_before(deadline);
+        let child_opt = spawn_module(
+            module_command(self.launcher, &self.command_path_, &self.limits)
                 .arg("-e")
                 .arg(chosen_path)
                 .arg("-i")
@@ -286,113 +642,593 @@ impl ExplorationModule for ExternalExplorationModule {
                 .arg("--memory-resolution")
                 .arg(format!("{}", memory_resolution))
                 .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn(),
-        };
+                .stderr(Stdio::piped()),
+            &self.limits,
+        );
         let uid = self.unique_identifier().clone();
-        let child = child_opt.expect("Failed to initiate explorer");
-        let out = child.stdout.expect("Failed to acquire explorer STDOUT");
-        let err = child.stderr.expect("Failed to achique explorer STDERR");
-        if BufReader::new(err).lines().flatten().any(|l| !l.is_empty()) {
-            warn!(
-                "Exploration module {} produced error messages. Please check it for correctness.",
-                uid
-            );
+        let mut child = match child_opt {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("Failed to initiate exploration module {}: {}", uid, e);
+                return Box::new(std::iter::empty());
+            }
+        };
+        let out = child
+            .stdout
+            .take()
+            .expect("Failed to acquire explorer STDOUT");
+        let err = child
+            .stderr
+            .take()
+            .expect("Failed to achique explorer STDERR");
+        // both streams are drained concurrently so that the solutions can be consumed as they are produced
+        forward_module_logs(&uid, err);
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for l in BufReader::new(out).lines() {
+                match l {
+                    Ok(f) => {
+                        if tx.send(f).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => {
+                        warn!("Failed to read solution during exploration");
+                        break;
+                    }
+                }
+            }
+        });
+        Box::new(ExternalExplorationIterator {
+            uid,
+            child,
+            solutions: rx,
+            deadline,
+            cancelled,
+        })
+    }
+
+    fn stop_exploring(&self) {
+        self.cancellation.cancel();
+    }
+}
+
+/// The flag shared by the explorations of a module that were started since it was last cancelled.
+///
+/// It takes no part in the comparisons of the module it belongs to.
+#[derive(Debug, Default)]
+struct ExplorationCancellation {
+    flag: Mutex<Arc<AtomicBool>>,
+}
+
+impl ExplorationCancellation {
+    /// The flag that the explorations started now are cancelled by.
+    fn flag(&self) -> Arc<AtomicBool> {
+        self.flag
+            .lock()
+            .expect("Failed to lock the exploration cancellation")
+            .clone()
+    }
+
+    /// Cancels the explorations started so far, leaving the next ones with a new flag.
+    fn cancel(&self) {
+        let mut flag = self
+            .flag
+            .lock()
+            .expect("Failed to lock the exploration cancellation");
+        flag.store(true, atomic::Ordering::SeqCst);
+        *flag = Arc::new(AtomicBool::new(false));
+    }
+}
+
+impl PartialEq for ExplorationCancellation {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ExplorationCancellation {}
+
+impl Hash for ExplorationCancellation {
+    fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
+}
+
+/// The solutions of an external exploration module, read from its output as they are produced.
+///
+/// The module is stopped once its output ends, the time-out is reached, the exploration is cancelled or
+/// the iterator is dropped.
+struct ExternalExplorationIterator {
+    uid: String,
+    child: Child,
+    solutions: Receiver<String>,
+    deadline: Option<Instant>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ExternalExplorationIterator {
+    fn stop(&mut self) {
+        stop_module(&self.uid, &mut self.child);
+    }
+}
+
+impl Iterator for ExternalExplorationIterator {
+    type Item = Box<dyn DecisionModel>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let poll = Instant::now() + CANCELLATION_POLL;
+            let received = match self.solutions.recv_timeout(
+                self.deadline
+                    .map_or(poll, |d| d.min(poll))
+                    .saturating_duration_since(Instant::now()),
+            ) {
+                Err(RecvTimeoutError::Timeout)
+                    if !self.cancelled.load(atomic::Ordering::SeqCst)
+                        && self.deadline.is_none_or(|d| Instant::now() < d) =>
+                {
+                    continue;
+                }
+                r => r.ok(),
+            };
+            match received {
+                Some(f) => {
+                    if let Some(h) = load_decision_model_header_from_path(Path::new(f.as_str())) {
+                        return Some(Box::new(h) as Box<dyn DecisionModel>);
+                    }
+                    warn!("Exploration module {} produced non-compliant output '{}' during exploration. Please check it for correctness.", self.uid, f);
+                }
+                None => {
+                    self.stop();
+                    return None;
+                }
+            }
         }
-        let buf = BufReader::new(out);
-        Box::new(
-            buf.lines()
-                .map(|l| l.expect("Failed to read solution during exploration"))
-                .flat_map(move |f| {
-                    let h = load_decision_model_header_from_path(Path::new(f.as_str()));
-                    if h.is_none() {
-                        warn!("Exploration module {} produced non-compliant output '{}' during exploration. Please check it for correctness.", uid, f);
+    }
+}
+
+impl Drop for ExternalExplorationIterator {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The solutions of an exploration module started as a server, read from its responses as they are produced.
+///
+/// The session is given back to the module once the exploration is done, and stopped if the time-out is
+/// reached or the iterator is dropped before that.
+struct SessionExplorationIterator {
+    uid: String,
+    server: Arc<ModuleServer>,
+    session: Option<ModuleSession>,
+    deadline: Option<Instant>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Iterator for SessionExplorationIterator {
+    type Item = Box<dyn DecisionModel>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(session) = self.session.as_mut() {
+            match session.cancellable_response(self.deadline, &self.cancelled) {
+                Ok(ModuleResponse::Solution(f)) => {
+                    if let Some(h) = load_decision_model_header_from_path(Path::new(f.as_str())) {
+                        return Some(Box::new(h) as Box<dyn DecisionModel>);
                     }
-                    h
-                    // if (f.ends_with(".msgpack")) {
-                    //     match std::fs::read(&f).and_then(|b| rmp_serde::from_slice::<DecisionModelHeader>(&b)) {
-                    //         Ok(h) => Some(h),
-                    //         _ => None
-                    //     }
-                    // } else if (f.ends_with(".cbor")) {
-                    //     match std::fs::read(&f).and_then(|b| ::from_slice::<DecisionModelHeader>(&b)) {
-                    //         Ok(h) => Some(h),
-                    //         _ => None
-                    //     }
-                    // } else {
-                    //     None
-                    // }
-                })
-                .map(|m| Box::new(m) as Box<dyn DecisionModel>),
-        )
-        // Box::new(BufRead::lines(out))
+                    warn!("Exploration module {} produced non-compliant output '{}' during exploration. Please check it for correctness.", self.uid, f);
+                }
+                Ok(ModuleResponse::Done) => {
+                    if let Some(s) = self.session.take() {
+                        self.server.give_back(s);
+                    }
+                }
+                Ok(r) => {
+                    warn!("Exploration module {} answered {:?} during exploration. Please check it for correctness.", self.uid, r);
+                    self.session = None;
+                }
+                Err(e) => {
+                    debug!("{}", e);
+                    self.session = None;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Drop for SessionExplorationIterator {
+    fn drop(&mut self) {
+        if let Some(mut s) = self.session.take() {
+            s.stop();
+        }
+    }
+}
+
+/// How long a module has to describe itself.
+const DESCRIBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A module found in the module paths, with its manifest if it has one or described itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredModule {
+    pub command_path: PathBuf,
+    pub unique_identifier: String,
+    pub launcher: ModuleLauncher,
+    pub limits: ModuleLimits,
+    pub manifest: Option<ModuleManifest>,
+    pub decision_model_schemas: BTreeSet<String>,
+}
+
+/// Whether a file without manifest can be run as a module, so that stray files in the module paths are
+/// not executed.
+fn is_runnable(p: &Path) -> bool {
+    if let Some("jar" | "py") = p.extension().and_then(|s| s.to_str()) {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        p.metadata()
+            .map(|m| m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        p.extension().and_then(|s| s.to_str()) == Some("exe")
     }
 }
 
+/// Reads the manifest at `p`, as TOML or JSON depending on its extension.
+pub fn read_module_manifest(p: &Path) -> Result<ModuleManifest, IdesydeError> {
+    let s = std::fs::read_to_string(p).map_err(|e| IdesydeError::io(p, e))?;
+    match p.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&s).map_err(|e| IdesydeError::decoding(p, "toml", e)),
+        _ => serde_json::from_str(&s).map_err(|e| IdesydeError::decoding(p, "json", e)),
+    }
+}
+
+/// Runs the module at `command_path` with `args` to ask it about itself, giving its output if it answered.
+fn query_module(
+    uid: &str,
+    launcher: ModuleLauncher,
+    command_path: &Path,
+    args: &[&str],
+    limits: &ModuleLimits,
+) -> Option<String> {
+    let limits = ModuleLimits {
+        timeout: Some(
+            limits
+                .timeout
+                .map_or(DESCRIBE_TIMEOUT, |t| t.min(DESCRIBE_TIMEOUT)),
+        ),
+        ..*limits
+    };
+    let output = run_module_quietly(
+        uid,
+        module_command(launcher, command_path, &limits)
+            .args(args)
+            .stdin(Stdio::null()),
+        &limits,
+    )?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Asks the module at `command_path` to describe itself, or `None` if it does not understand
+/// [DESCRIBE_ARG], as is the case for modules written before it existed.
+pub fn describe_module(
+    uid: &str,
+    launcher: ModuleLauncher,
+    command_path: &Path,
+    limits: &ModuleLimits,
+) -> Option<ModuleDescription> {
+    // anything else the module prints, e.g. from its runtime, is skipped
+    query_module(uid, launcher, command_path, &[DESCRIBE_ARG], limits)?
+        .lines()
+        .rev()
+        .find_map(|l| serde_json::from_str(l).ok())
+}
+
+/// Asks the module at `command_path` for the schemas of its decision models through [SCHEMAS_ARG], printed
+/// one after the other, for the modules that do not describe themselves. Modules that do not answer have none.
+pub fn module_schemas(
+    uid: &str,
+    launcher: ModuleLauncher,
+    command_path: &Path,
+    limits: &ModuleLimits,
+) -> BTreeSet<String> {
+    // the JVM modules take the flag as a boolean option
+    let args: &[&str] = match launcher {
+        ModuleLauncher::Java => &[SCHEMAS_ARG, "true"],
+        _ => &[SCHEMAS_ARG],
+    };
+    query_module(uid, launcher, command_path, args, limits)
+        .map(|out| {
+            serde_json::Deserializer::from_str(&out)
+                .into_iter::<serde_json::Value>()
+                .map_while(|v| v.ok())
+                .filter(|v| v.is_object())
+                .map(|v| v.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a module speaks a protocol version compatible with the orchestrator, warning if it does not.
+fn has_compatible_protocol(uid: &str, protocol_version: &str) -> bool {
+    if is_compatible_protocol_version(protocol_version) {
+        return true;
+    }
+    warn!(
+        "Ignoring module {} as it speaks protocol version {}, which is not compatible with version {} of the orchestrator. Please update it.",
+        uid, protocol_version, PROTOCOL_VERSION
+    );
+    false
+}
+
+/// A module without a manifest, described by what it answers to [DESCRIBE_ARG] if it answers.
+///
+/// Modules that do not describe themselves are kept with a warning, as their protocol version cannot be
+/// checked.
+fn discover_undeclared_module(
+    command_path: PathBuf,
+    kind: ModuleKind,
+    limits: &ModuleLimits,
+) -> Option<DiscoveredModule> {
+    let uid = command_path.to_str().unwrap().to_string();
+    let launcher = ModuleLauncher::for_path(&command_path);
+    let Some(description) = describe_module(&uid, launcher, &command_path, limits) else {
+        warn!(
+            "Module {} did not describe itself, so its protocol version is unverified. It is used anyway, but it might not work with this orchestrator; please update it or give it a manifest.",
+            uid
+        );
+        return Some(DiscoveredModule {
+            decision_model_schemas: module_schemas(&uid, launcher, &command_path, limits),
+            unique_identifier: uid,
+            launcher,
+            limits: *limits,
+            command_path,
+            manifest: None,
+        });
+    };
+    if description.kind != kind {
+        debug!(
+            "Ignoring module {} as it describes itself as an {:?} module",
+            uid, description.kind
+        );
+        return None;
+    }
+    if !has_compatible_protocol(
+        &description.unique_identifier,
+        &description.protocol_version,
+    ) {
+        return None;
+    }
+    Some(DiscoveredModule {
+        unique_identifier: description.unique_identifier.to_owned(),
+        launcher,
+        limits: *limits,
+        command_path,
+        manifest: Some(description.to_manifest(launcher)),
+        decision_model_schemas: description.decision_model_schemas,
+    })
+}
+
+/// Finds the modules of `kind` in the given paths.
+///
+/// A module is either described by a manifest, e.g. `idesyde-common.manifest.toml`, or is a file that can
+/// be run without one. Modules whose manifests declare another kind are left out, and so are the files
+/// that are neither manifests, executables of a manifest or runnable. Modules without a manifest are asked
+/// to describe themselves, and modules that speak a protocol version incompatible with the orchestrator's
+/// are left out too. The modules that are not described are asked for their schemas instead.
+///
+/// The limits of a module are the ones its manifest declares, and otherwise `limits`.
+pub fn discover_modules(
+    modules_paths: &[PathBuf],
+    kind: ModuleKind,
+    limits: &ModuleLimits,
+) -> Vec<DiscoveredModule> {
+    let mut discovered = Vec::new();
+    for modules_path in modules_paths {
+        let Ok(read_dir) = modules_path.read_dir() else {
+            debug!("Module path {} cannot be read", modules_path.display());
+            continue;
+        };
+        let mut files: Vec<PathBuf> = read_dir
+            .flatten()
+            .map(|de| de.path())
+            .filter(|p| p.is_file())
+            .collect();
+        files.sort();
+        let is_manifest = |p: &Path| {
+            p.file_name().and_then(|f| f.to_str()).map_or(false, |f| {
+                f.ends_with(".manifest.toml") || f.ends_with(".manifest.json")
+            })
+        };
+        let mut described: HashSet<PathBuf> = HashSet::new();
+        let mut declared_modules: Vec<DiscoveredModule> = Vec::new();
+        for p in files.iter().filter(|p| is_manifest(p)) {
+            let manifest = match read_module_manifest(p) {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!("Ignoring a module manifest: {}", e);
+                    continue;
+                }
+            };
+            let executable = match &manifest.executable {
+                Some(e) => modules_path.join(e),
+                None => p.with_file_name(
+                    p.file_name()
+                        .and_then(|f| f.to_str())
+                        .map(|f| {
+                            f.trim_end_matches(".toml")
+                                .trim_end_matches(".json")
+                                .trim_end_matches(".manifest")
+                        })
+                        .unwrap_or_default(),
+                ),
+            };
+            described.insert(executable.clone());
+            if manifest.kind != kind {
+                continue;
+            }
+            if !has_compatible_protocol(&manifest.unique_identifier, &manifest.protocol_version) {
+                continue;
+            }
+            if !executable.is_file() {
+                warn!(
+                    "Ignoring module {} as its executable {} does not exist",
+                    manifest.unique_identifier,
+                    executable.display()
+                );
+                continue;
+            }
+            declared_modules.push(DiscoveredModule {
+                command_path: executable.read_link().unwrap_or(executable),
+                unique_identifier: manifest.unique_identifier.to_owned(),
+                launcher: manifest.launcher,
+                limits: limits
+                    .overridden_by(manifest.timeout_seconds, manifest.memory_limit_megabytes),
+                manifest: Some(manifest),
+                decision_model_schemas: BTreeSet::new(),
+            });
+        }
+        // manifests do not carry schemas, so the modules they declare are asked for them
+        declared_modules.par_iter_mut().for_each(|m| {
+            m.decision_model_schemas =
+                module_schemas(&m.unique_identifier, m.launcher, &m.command_path, &m.limits);
+        });
+        discovered.extend(declared_modules);
+        let mut undeclared: Vec<PathBuf> = Vec::new();
+        for p in files {
+            if is_manifest(&p) || described.contains(&p) {
+                continue;
+            }
+            if !is_runnable(&p) {
+                debug!("Ignoring {} as it is not a module", p.display());
+                continue;
+            }
+            undeclared.push(p.read_link().unwrap_or(p));
+        }
+        // describing a module means starting it, which can take a while for the JVM ones
+        let described_modules: Vec<DiscoveredModule> = undeclared
+            .into_par_iter()
+            .flat_map(|prog| discover_undeclared_module(prog, kind, limits))
+            .collect();
+        discovered.extend(described_modules);
+    }
+    discovered
+}
+
 pub fn find_and_prepare_identification_modules(
-    modules_path: &Path,
+    modules_paths: &[PathBuf],
     identified_path: &Path,
     inputs_path: &Path,
     solved_path: &Path,
     integration_path: &Path,
     output_path: &Path,
+    limits: &ModuleLimits,
 ) -> Vec<ExternalIdentificationModule> {
-    let mut imodules = Vec::new();
-    if let Ok(read_dir) = modules_path.read_dir() {
-        for e in read_dir {
-            if let Ok(de) = e {
-                let p = de.path();
-                if p.is_file() {
-                    let prog = p.read_link().unwrap_or(p);
-                    imodules.push(ExternalIdentificationModule {
-                        command_path_: prog.clone(),
-                        identified_path_: identified_path.to_path_buf(),
-                        inputs_path_: inputs_path.to_path_buf(),
-                        solved_path_: solved_path.to_path_buf(),
-                        reverse_path_: integration_path.to_path_buf(),
-                        output_path_: output_path.to_path_buf(),
-                    });
-                }
+    discover_modules(modules_paths, ModuleKind::Identification, limits)
+        .into_iter()
+        .map(|m| {
+            let server = ModuleServer::new(
+                &m.unique_identifier,
+                m.launcher,
+                &m.command_path,
+                vec![
+                    "-m".into(),
+                    inputs_path.into(),
+                    "-i".into(),
+                    identified_path.into(),
+                    "-s".into(),
+                    solved_path.into(),
+                    "-r".into(),
+                    integration_path.into(),
+                    "-o".into(),
+                    output_path.into(),
+                ],
+                m.limits,
+                m.manifest.as_ref().is_some_and(|mf| mf.server),
+            );
+            ExternalIdentificationModule {
+                unique_identifier_: m.unique_identifier,
+                launcher: m.launcher,
+                limits: m.limits,
+                command_path_: m.command_path,
+                identified_path_: identified_path.to_path_buf(),
+                inputs_path_: inputs_path.to_path_buf(),
+                solved_path_: solved_path.to_path_buf(),
+                reverse_path_: integration_path.to_path_buf(),
+                output_path_: output_path.to_path_buf(),
+                decision_model_schemas: m.decision_model_schemas,
+                server,
             }
-        }
-    }
-    imodules
+        })
+        .collect()
 }
 
 pub fn find_exploration_modules(
-    modules_path: &Path,
+    modules_paths: &[PathBuf],
     identified_path: &Path,
     solved_path: &Path,
+    limits: &ModuleLimits,
 ) -> Vec<ExternalExplorationModule> {
-    let mut emodules = Vec::new();
-    if let Ok(read_dir) = modules_path.read_dir() {
-        for e in read_dir {
-            if let Ok(de) = e {
-                let p = de.path();
-                if p.is_file() {
-                    let prog = p.read_link().unwrap_or(p);
-                    emodules.push(ExternalExplorationModule {
-                        command_path_: prog.clone(),
-                        identified_path_: identified_path.to_path_buf(),
-                        solved_path_: solved_path.to_path_buf(),
-                    });
-                }
+    discover_modules(modules_paths, ModuleKind::Exploration, limits)
+        .into_iter()
+        .map(|m| {
+            let server = ModuleServer::new(
+                &m.unique_identifier,
+                m.launcher,
+                &m.command_path,
+                vec![
+                    "-i".into(),
+                    identified_path.into(),
+                    "-o".into(),
+                    solved_path.into(),
+                ],
+                m.limits,
+                m.manifest.as_ref().is_some_and(|mf| mf.server),
+            );
+            ExternalExplorationModule {
+                unique_identifier_: m.unique_identifier,
+                launcher: m.launcher,
+                limits: m.limits,
+                decision_model_categories: m
+                    .manifest
+                    .map(|mf| mf.decision_model_categories)
+                    .unwrap_or_default(),
+                command_path_: m.command_path,
+                identified_path_: identified_path.to_path_buf(),
+                solved_path_: solved_path.to_path_buf(),
+                decision_model_schemas: m.decision_model_schemas,
+                server: Arc::new(server),
+                cancellation: ExplorationCancellation::default(),
             }
-        }
-    }
-    emodules
+        })
+        .collect()
 }
 
+/// Identifies decision models until no module identifies new ones, starting from the `pre_identified` ones.
+///
+/// Models whose bodies do not follow the schemas of their categories are left out.
 pub fn identification_procedure(
     imodules: &Vec<Box<dyn IdentificationModule>>,
     design_models: &Vec<Box<dyn DesignModel>>,
     pre_identified: &mut Vec<Box<dyn DecisionModel>>,
     starting_iter: i32,
+    schemas: &SchemaRegistry,
 ) -> Vec<Box<dyn DecisionModel>> {
     let mut step = starting_iter;
     let mut fix_point = false;
     let mut identified: Vec<Box<dyn DecisionModel>> = Vec::new();
-    identified.append(pre_identified);
+    // the headers are kept aside so that each model is only compared by its header, and validated, once
+    let mut identified_headers: HashSet<DecisionModelHeader> = HashSet::new();
+    for m in pre_identified.drain(..) {
+        if identified_headers.insert(m.header()) && schemas.accepts(&m.header()) {
+            identified.push(m);
+        }
+    }
     while !fix_point || step <= 1 {
         // the step condition forces the procedure to go at least one more, fundamental for incrementability
         fix_point = true;
@@ -400,9 +1236,13 @@ pub fn identification_procedure(
         let new_identified: Vec<Box<dyn DecisionModel>> = imodules
             .par_iter()
             .flat_map(|imodule| imodule.identification_step(step, &design_models, &identified))
-            .filter(|potential| !identified.contains(potential))
             .collect();
-        identified.extend(new_identified.into_iter());
+        for potential in new_identified {
+            if identified_headers.insert(potential.header()) && schemas.accepts(&potential.header())
+            {
+                identified.push(potential);
+            }
+        }
         // for imodule in imodules {
         //     let potential = imodule.identification_step(step, &design_models, &identified);
         //     // potential.retain(|m| !identified.contains(m));
@@ -426,15 +1266,17 @@ pub fn identification_procedure(
 pub fn compute_dominant_decision_models<'a>(
     decision_models: &'a Vec<&'a Box<dyn DecisionModel>>,
 ) -> Vec<&'a Box<dyn DecisionModel>> {
+    let headers: Vec<DecisionModelHeader> = decision_models.iter().map(|m| m.header()).collect();
     decision_models
-        .into_iter()
-        .filter(|m| {
-            decision_models.iter().all(|o| match m.partial_cmp(&o) {
+        .iter()
+        .zip(headers.iter())
+        .filter(|(_, h)| {
+            headers.iter().all(|o| match h.partial_cmp(&o) {
                 Some(Ordering::Greater) | Some(Ordering::Equal) | None => true,
                 _ => false,
             })
         })
-        .map(|o| o.to_owned())
+        .map(|(m, _)| m.to_owned())
         .collect()
 }
 
@@ -455,24 +1297,27 @@ pub fn compute_dominant_biddings<'a>(
         })
         .filter(|(_, _, c)| c.can_explore)
         .collect();
+    let headers: Vec<DecisionModelHeader> =
+        combinations.iter().map(|(_, m, _)| m.header()).collect();
+    // ties are only broken between the biddings of the same explorer, so that different explorers
+    // can still be used together for the same decision model
     combinations
         .iter()
         .enumerate()
-        .filter(|(i, (_, m, _))| {
-            combinations
-                .iter()
-                .enumerate()
-                .all(|(j, (_, o, _))| match m.partial_cmp(&o) {
+        .filter(|(i, (e, _, _))| {
+            combinations.iter().enumerate().all(|(j, (oe, _, _))| {
+                match headers[*i].partial_cmp(&headers[j]) {
                     Some(Ordering::Greater) | None => true,
-                    Some(Ordering::Equal) => i <= &j,
+                    Some(Ordering::Equal) => i <= &j || e != oe,
                     _ => false,
-                })
+                }
+            })
         })
-        .filter(|(i, (_, _, comb))| {
-            combinations.iter().enumerate().all(|(j, (_, _, ocomb))| {
+        .filter(|(i, (e, _, comb))| {
+            combinations.iter().enumerate().all(|(j, (oe, _, ocomb))| {
                 match comb.partial_cmp(&ocomb) {
                     Some(Ordering::Greater) | None => true,
-                    Some(Ordering::Equal) => i <= &j,
+                    Some(Ordering::Equal) => i <= &j || e != oe,
                     _ => false,
                 }
             })
@@ -480,3 +1325,857 @@ pub fn compute_dominant_biddings<'a>(
         .map(|(_, (e, m, _))| (*e, *m))
         .collect()
 }
+
+/// An explorer together with a decision model it bid to explore.
+pub type Bidding<'a> = (&'a Box<dyn ExplorationModule>, &'a Box<dyn DecisionModel>);
+
+/// Explores all the given biddings concurrently, as a portfolio that shares the solution and time budgets.
+///
+/// The solutions of all explorers are merged in the order they arrive, and a solution with the same contents as
+/// one already found, by any explorer, is discarded. See [crate::cache::hash_decision_model]. `on_solution` is called for every new solution, together with the
+/// unique identifier of the explorer that found it and the number of solutions found before it, and tells
+/// whether the solution is valid. Invalid solutions are not counted, so they do not use up the budget.
+/// Once the budget is exhausted, every explorer is cancelled, and the ones that cannot be cancelled are stopped
+/// as soon as they yield their next solution or reach their own time-out.
+///
+/// Returns how many of the solutions each explorer found.
+pub fn explore_portfolio<F>(
+    biddings: &[Bidding],
+    max_sols: i64,
+    total_timeout: i64,
+    time_resolution: i64,
+    memory_resolution: i64,
+    mut on_solution: F,
+) -> HashMap<String, usize>
+where
+    F: FnMut(&str, usize, Box<dyn DecisionModel>) -> bool,
+{
+    let deadline = if total_timeout > 0 {
+        Some(Instant::now() + Duration::from_secs(total_timeout as u64))
+    } else {
+        None
+    };
+    let stop = AtomicBool::new(false);
+    let mut found: HashSet<String> = HashSet::new();
+    let mut found_per_explorer: HashMap<String, usize> = HashMap::new();
+    std::thread::scope(|scope| {
+        let (tx, rx) = std::sync::mpsc::channel::<(String, Box<dyn DecisionModel>)>();
+        for (exp, m) in biddings {
+            let tx = tx.clone();
+            let stop = &stop;
+            scope.spawn(move || {
+                let uid = exp.unique_identifier();
+                let solutions = exp.explore(
+                    m,
+                    max_sols,
+                    total_timeout,
+                    time_resolution,
+                    memory_resolution,
+                );
+                // an exploration started after the others were cancelled would not be cancelled otherwise
+                if stop.load(atomic::Ordering::SeqCst) {
+                    exp.stop_exploring();
+                }
+                for sol in solutions {
+                    if stop.load(atomic::Ordering::SeqCst) || tx.send((uid.clone(), sol)).is_err() {
+                        break;
+                    }
+                }
+                debug!("Exploration module {} finished exploring", uid);
+            });
+        }
+        // only the explorers keep the channel open from now on
+        drop(tx);
+        loop {
+            let received = match deadline {
+                Some(d) => rx
+                    .recv_timeout(d.saturating_duration_since(Instant::now()))
+                    .ok(),
+                None => rx.recv().ok(),
+            };
+            let Some((uid, sol)) = received else {
+                break;
+            };
+            // all the solutions of a decision model can have the same header, so their bodies are compared too
+            let h = crate::cache::hash_decision_model(sol.as_ref());
+            if found.contains(&h) {
+                debug!(
+                    "Discarded a solution from {} equivalent to a previous one.",
+                    uid
+                );
+                continue;
+            }
+            if !on_solution(uid.as_str(), found.len(), sol) {
+                continue;
+            }
+            *found_per_explorer.entry(uid).or_insert(0) += 1;
+            found.insert(h);
+            if max_sols > 0 && found.len() as i64 >= max_sols {
+                break;
+            }
+        }
+        stop.store(true, atomic::Ordering::SeqCst);
+        // the explorers still waiting for a solution would otherwise hold the scope open
+        for (exp, _) in biddings {
+            exp.stop_exploring();
+        }
+    });
+    found_per_explorer
+}
+
+/// A solution kept in the [ParetoArchive], together with the explorer that found it and the design models
+/// reverse identified from it, as pairs of the path of the (MsgPack) header kept by the orchestrator and the header.
+#[derive(Debug, Clone)]
+pub struct ParetoSolution {
+    pub explorer: String,
+    pub header: DecisionModelHeader,
+    pub objectives: HashMap<String, f64>,
+    pub reversed: Vec<(PathBuf, DesignModelHeader)>,
+}
+
+/// The solutions found during a run that are not Pareto-dominated by any other solution found.
+///
+/// Solutions without objectives, or with objectives different from the others, are incomparable and
+/// therefore always kept.
+#[derive(Debug, Default)]
+pub struct ParetoArchive {
+    pub solutions: Vec<ParetoSolution>,
+}
+
+impl ParetoArchive {
+    /// Adds the solution to the archive, unless a solution already in the archive dominates it.
+    ///
+    /// Returns whether the solution was kept and the solutions that it dominates, which are removed from
+    /// the archive.
+    pub fn offer(
+        &mut self,
+        explorer: &str,
+        sol: &dyn DecisionModel,
+    ) -> (bool, Vec<ParetoSolution>) {
+        let objectives = sol.objectives();
+        if self
+            .solutions
+            .iter()
+            .any(|s| idesyde_core::pareto_dominates(&s.objectives, &objectives))
+        {
+            return (false, Vec::new());
+        }
+        let (dominated, kept): (Vec<ParetoSolution>, Vec<ParetoSolution>) = self
+            .solutions
+            .drain(..)
+            .partition(|s| idesyde_core::pareto_dominates(&objectives, &s.objectives));
+        self.solutions = kept;
+        self.solutions.push(ParetoSolution {
+            explorer: explorer.to_string(),
+            header: sol.header(),
+            objectives,
+            reversed: Vec::new(),
+        });
+        (true, dominated)
+    }
+
+    /// Keeps the design models reverse identified from the solution `h`, so that they are removed along with it
+    /// if it is dominated later on.
+    pub fn add_reversed(
+        &mut self,
+        h: &DecisionModelHeader,
+        reversed: Vec<(PathBuf, DesignModelHeader)>,
+    ) {
+        if let Some(s) = self
+            .solutions
+            .iter_mut()
+            .find(|s| is_same_decision_model(&s.header, h))
+        {
+            s.reversed.extend(reversed);
+        }
+    }
+
+    /// Removes the files of a solution that was dominated: its header and bodies in `explored_path`, the headers that
+    /// the identification modules wrote for it in `reverse_path`, which are named after its header, and the design models
+    /// reverse identified from it. The files that the modules wrote out for these design models are only removed if no
+    /// solution in the archive has them too, since modules given an output file rather than a directory overwrite it.
+    pub fn remove_dominated_files(
+        &self,
+        dominated: &ParetoSolution,
+        explored_path: &Path,
+        reverse_path: &Path,
+    ) {
+        for (header_path, header) in load_decision_model_headers_from_binary(explored_path) {
+            if !is_same_decision_model(&header, &dominated.header) {
+                continue;
+            }
+            if let Some(stem) = header_path.file_stem().and_then(|f| f.to_str()) {
+                let prefixes = [
+                    format!("{}_", stem),
+                    format!("header_{}_", stem.trim_start_matches("header_")),
+                ];
+                for e in reverse_path.read_dir().into_iter().flatten().flatten() {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    if prefixes.iter().any(|p| name.starts_with(p)) {
+                        let removed = if e.path().is_dir() {
+                            std::fs::remove_dir_all(e.path())
+                        } else {
+                            std::fs::remove_file(e.path())
+                        };
+                        if removed.is_err() {
+                            warn!(
+                                "Failed to remove {}. This is a benign error. Continuing",
+                                e.path().display()
+                            );
+                        }
+                    }
+                }
+            }
+            remove_decision_model_header_files(&header_path, &header);
+        }
+        let kept_models: HashSet<&String> = self
+            .solutions
+            .iter()
+            .flat_map(|s| s.reversed.iter().flat_map(|(_, h)| &h.model_paths))
+            .collect();
+        for (header_path, header) in &dominated.reversed {
+            let to_remove = ["msgpack", "cbor", "json"]
+                .iter()
+                .map(|ext| header_path.with_extension(ext))
+                .chain(
+                    header
+                        .model_paths
+                        .iter()
+                        .filter(|p| !kept_models.contains(p))
+                        .map(PathBuf::from),
+                );
+            for f in to_remove {
+                if f.is_file() && std::fs::remove_file(&f).is_err() {
+                    warn!(
+                        "Failed to remove {}. This is a benign error. Continuing",
+                        f.display()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Finds the paths of the headers of the solutions in `explored_path`, together with the explorer
+    /// of each solution.
+    pub fn solution_paths(&self, explored_path: &Path) -> Vec<(String, Option<PathBuf>)> {
+        let headers = load_decision_model_headers_from_binary(explored_path);
+        self.solutions
+            .iter()
+            .map(|s| {
+                (
+                    s.explorer.to_owned(),
+                    headers
+                        .iter()
+                        .find(|(_, h)| is_same_decision_model(h, &s.header))
+                        .map(|(p, _)| p.to_owned()),
+                )
+            })
+            .collect()
+    }
+
+    /// Writes the archive as a JSON array, where every solution has the explorer that found it,
+    /// its category, the path of its header in `explored_path` (if any) and its objective values.
+    pub fn write_summary(&self, explored_path: &Path, summary_path: &Path) -> bool {
+        let summary: Vec<serde_json::Value> = self
+            .solutions
+            .iter()
+            .zip(self.solution_paths(explored_path))
+            .map(|(s, (_, path))| {
+                serde_json::json!({
+                    "explorer": s.explorer,
+                    "category": s.header.category,
+                    "header_path": path,
+                    "objectives": s.objectives,
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&summary)
+            .ok()
+            .and_then(|j| std::fs::write(summary_path, j).ok())
+            .is_some()
+    }
+}
+
+/// Removes the files of the decision models in `p` that are the same as `h`, i.e. the header
+/// in all its encodings and the bodies it points to. See [is_same_decision_model].
+pub fn remove_decision_model_files(p: &Path, h: &DecisionModelHeader) {
+    for (header_path, header) in load_decision_model_headers_from_binary(p) {
+        if is_same_decision_model(&header, h) {
+            remove_decision_model_header_files(&header_path, &header);
+        }
+    }
+}
+
+/// Removes the header in `header_path`, in all its encodings, and the bodies it points to.
+pub fn remove_decision_model_header_files(header_path: &Path, header: &DecisionModelHeader) {
+    let mut to_remove: Vec<PathBuf> = ["msgpack", "cbor", "json"]
+        .iter()
+        .map(|ext| header_path.with_extension(ext))
+        .collect();
+    if let Some(bp) = &header.body_path {
+        to_remove.extend(
+            ["msgpack", "cbor", "json"]
+                .iter()
+                .map(|ext| Path::new(bp).with_extension(ext)),
+        );
+    }
+    to_remove.extend(header.body_paths.values().map(PathBuf::from));
+    for f in to_remove {
+        if f.exists() && std::fs::remove_file(&f).is_err() {
+            warn!(
+                "Failed to remove {} of a decision model. This is a benign error. Continuing",
+                f.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use idesyde_core::write_decision_model_header_to_path;
+
+    fn test_header(category: &str) -> DecisionModelHeader {
+        DecisionModelHeader {
+            category: category.to_string(),
+            body_path: None,
+            body_paths: HashMap::new(),
+            covered_elements: BTreeSet::from(["a".to_string()]),
+            objectives: HashMap::new(),
+            covered_relations: BTreeSet::new(),
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let p = std::env::temp_dir().join(format!("idesyde-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&p);
+        std::fs::create_dir_all(&p).expect("Failed to create the test directory");
+        p
+    }
+
+    /// Runs the portfolio in another thread, so that a hanging portfolio fails the test instead of blocking it.
+    fn explore_within(
+        explorers: Vec<Box<dyn ExplorationModule>>,
+        m: Box<dyn DecisionModel>,
+        max_sols: i64,
+        limit: Duration,
+    ) -> Option<HashMap<String, usize>> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let biddings: Vec<Bidding> = explorers.iter().map(|e| (e, &m)).collect();
+            let found = explore_portfolio(&biddings, max_sols, 0, -1, -1, |_, _, _| true);
+            let _ = tx.send(found);
+        });
+        rx.recv_timeout(limit).ok()
+    }
+
+    /// Yields a single solution and then waits until it is cancelled, without yielding again.
+    struct StallingExplorer {
+        cancellation: ExplorationCancellation,
+    }
+
+    struct StallingIterator {
+        solution: Option<DecisionModelHeader>,
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl Iterator for StallingIterator {
+        type Item = Box<dyn DecisionModel>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if let Some(h) = self.solution.take() {
+                return Some(Box::new(h));
+            }
+            while !self.cancelled.load(atomic::Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            None
+        }
+    }
+
+    impl ExplorationModule for StallingExplorer {
+        fn unique_identifier(&self) -> String {
+            "StallingExplorer".to_string()
+        }
+
+        fn available_criterias(&self, _m: Box<dyn DecisionModel>) -> HashMap<String, f32> {
+            HashMap::new()
+        }
+
+        fn bid(&self, _m: &Box<dyn DecisionModel>) -> ExplorationBid {
+            ExplorationBid {
+                unique_identifier: self.unique_identifier(),
+                can_explore: true,
+                properties: HashMap::new(),
+            }
+        }
+
+        fn explore(
+            &self,
+            m: &Box<dyn DecisionModel>,
+            _max_sols: i64,
+            _total_timeout: i64,
+            _time_resolution: i64,
+            _memory_resolution: i64,
+        ) -> Box<dyn Iterator<Item = Box<dyn DecisionModel>>> {
+            Box::new(StallingIterator {
+                solution: Some(m.header()),
+                cancelled: self.cancellation.flag(),
+            })
+        }
+
+        fn stop_exploring(&self) {
+            self.cancellation.cancel();
+        }
+    }
+
+    /// Yields the given solutions, one after the other.
+    struct ListExplorer {
+        solutions: Vec<DecisionModelHeader>,
+    }
+
+    impl ExplorationModule for ListExplorer {
+        fn unique_identifier(&self) -> String {
+            "ListExplorer".to_string()
+        }
+
+        fn available_criterias(&self, _m: Box<dyn DecisionModel>) -> HashMap<String, f32> {
+            HashMap::new()
+        }
+
+        fn bid(&self, _m: &Box<dyn DecisionModel>) -> ExplorationBid {
+            ExplorationBid {
+                unique_identifier: self.unique_identifier(),
+                can_explore: true,
+                properties: HashMap::new(),
+            }
+        }
+
+        fn explore(
+            &self,
+            _m: &Box<dyn DecisionModel>,
+            _max_sols: i64,
+            _total_timeout: i64,
+            _time_resolution: i64,
+            _memory_resolution: i64,
+        ) -> Box<dyn Iterator<Item = Box<dyn DecisionModel>>> {
+            Box::new(
+                self.solutions
+                    .clone()
+                    .into_iter()
+                    .map(|h| Box::new(h) as Box<dyn DecisionModel>),
+            )
+        }
+    }
+
+    #[test]
+    fn portfolio_keeps_solutions_that_only_differ_in_their_bodies() {
+        let dir = test_dir("portfolio-bodies");
+        let solution = |name: &str, body: &str, objective: f64| {
+            let bp = dir.join(format!("body_{}.json", name));
+            std::fs::write(&bp, body).unwrap();
+            let mut h = test_header("Solved");
+            h.body_path = Some(bp.display().to_string());
+            h.objectives = HashMap::from([("period".to_string(), objective)]);
+            h
+        };
+        let first = solution("first", r#"{"schedule": ["a", "b"]}"#, 2.0);
+        let better = solution("better", r#"{"schedule": ["b", "a"]}"#, 1.0);
+        let repeated = solution("repeated", r#"{"schedule": ["a", "b"]}"#, 2.0);
+        let explorer: Box<dyn ExplorationModule> = Box::new(ListExplorer {
+            solutions: vec![first.clone(), better.clone(), repeated],
+        });
+        let m: Box<dyn DecisionModel> = Box::new(test_header("Solved"));
+        let mut received = Vec::new();
+        let found = explore_portfolio(&[(&explorer, &m)], 0, 0, -1, -1, |_, _, sol| {
+            received.push(sol.header().body_path);
+            true
+        });
+        assert_eq!(found.get("ListExplorer"), Some(&2));
+        assert_eq!(received, vec![first.body_path, better.body_path]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn portfolio_cancels_explorers_that_never_yield_again() {
+        let explorers: Vec<Box<dyn ExplorationModule>> = vec![Box::new(StallingExplorer {
+            cancellation: ExplorationCancellation::default(),
+        })];
+        let found = explore_within(
+            explorers,
+            Box::new(test_header("Stalling")),
+            1,
+            Duration::from_secs(10),
+        )
+        .expect("The portfolio did not finish after finding all the solutions");
+        assert_eq!(found.get("StallingExplorer"), Some(&1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn portfolio_cancels_external_explorers_that_never_yield_again() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("stalling-explorer");
+        let identified = dir.join("identified");
+        let solved = dir.join("solved");
+        std::fs::create_dir_all(&identified).unwrap();
+        std::fs::create_dir_all(&solved).unwrap();
+        let h = test_header("Stalling");
+        write_decision_model_header_to_path(&h, &identified, "", "Test").unwrap();
+        let solution = identified.join("header__Stalling_Test.json");
+        let script = dir.join("stalling-explorer");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho {}\nsleep 600\n", solution.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let limits = ModuleLimits::default();
+        let explorer = ExternalExplorationModule {
+            unique_identifier_: "StallingExplorer".to_string(),
+            launcher: ModuleLauncher::Native,
+            limits,
+            decision_model_categories: BTreeSet::new(),
+            command_path_: script.clone(),
+            identified_path_: identified,
+            solved_path_: solved,
+            decision_model_schemas: BTreeSet::new(),
+            server: Arc::new(ModuleServer::new(
+                "StallingExplorer",
+                ModuleLauncher::Native,
+                &script,
+                Vec::new(),
+                limits,
+                false,
+            )),
+            cancellation: ExplorationCancellation::default(),
+        };
+        let found = explore_within(
+            vec![Box::new(explorer)],
+            Box::new(h),
+            1,
+            Duration::from_secs(10),
+        )
+        .expect("The portfolio did not finish after finding all the solutions");
+        assert_eq!(found.get("StallingExplorer"), Some(&1));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn streamed_solutions_are_each_reverse_identified_once() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("streamed-reverse");
+        let (inputs, solved, reverse, output) = (
+            dir.join("inputs"),
+            dir.join("solved"),
+            dir.join("reverse"),
+            dir.join("output"),
+        );
+        for p in [&inputs, &solved, &reverse, &output] {
+            std::fs::create_dir_all(p).unwrap();
+        }
+        let design = DesignModelHeader {
+            category: "Design".to_string(),
+            model_paths: Vec::new(),
+            elements: BTreeSet::from(["a".to_string()]),
+            relations: BTreeSet::new(),
+        };
+        idesyde_core::write_design_model_header_to_path(&design, &dir, "", "Test").unwrap();
+        // logs every solution it is given and reverse identifies each into one design model
+        let log = dir.join("reversed.log");
+        let script = dir.join("reversing-module");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nfor h in \"$4\"/header_*.msgpack; do\n  n=$(basename \"$h\" .msgpack)\n  echo $n >> {}\n  cp {} \"$6/$n.msgpack\"\n  echo \"$6/$n.msgpack\"\ndone\n",
+                log.display(),
+                dir.join("header__Design_Test.msgpack").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let limits = ModuleLimits::default();
+        let module = ExternalIdentificationModule {
+            unique_identifier_: "ReversingModule".to_string(),
+            launcher: ModuleLauncher::Native,
+            limits,
+            command_path_: script.clone(),
+            inputs_path_: inputs.clone(),
+            identified_path_: dir.join("identified"),
+            solved_path_: solved.clone(),
+            reverse_path_: reverse.clone(),
+            output_path_: output.clone(),
+            decision_model_schemas: BTreeSet::new(),
+            server: ModuleServer::new(
+                "ReversingModule",
+                ModuleLauncher::Native,
+                &script,
+                Vec::new(),
+                limits,
+                false,
+            ),
+        };
+        // the solutions of one decision model cover the same elements and only differ in their bodies
+        let solutions: Vec<DecisionModelHeader> = (0..2)
+            .map(|i| {
+                let mut h = test_header("Solved");
+                h.body_path = Some(
+                    solved
+                        .join(format!("body_{}.msgpack", i))
+                        .display()
+                        .to_string(),
+                );
+                h
+            })
+            .collect();
+        let mut reversed = 0;
+        for (i, h) in solutions.iter().enumerate() {
+            write_decision_model_header_to_path(h, &solved, &i.to_string(), "Explorer").unwrap();
+            let solution: Vec<Box<dyn DecisionModel>> = vec![Box::new(h.to_owned())];
+            reversed += module.reverse_identification(&solution, &Vec::new()).len();
+        }
+        assert_eq!(reversed, 2);
+        let log = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(
+            log.lines().collect::<Vec<&str>>(),
+            vec!["header_0_Solved_Explorer", "header_1_Solved_Explorer"]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Reads every text file as a design model with a single element, the contents of the file.
+    fn read_text_design_model(p: &Path) -> Option<Box<dyn DesignModel>> {
+        let contents = std::fs::read_to_string(p).ok()?;
+        Some(Box::new(DesignModelHeader {
+            category: "Text".to_string(),
+            model_paths: Vec::new(),
+            elements: BTreeSet::from([contents]),
+            relations: BTreeSet::new(),
+        }))
+    }
+
+    #[test]
+    fn native_modules_keep_the_header_of_every_input() {
+        let dir = test_dir("native-inputs");
+        let (inputs, identified) = (dir.join("inputs"), dir.join("identified"));
+        std::fs::create_dir_all(&inputs).unwrap();
+        std::fs::create_dir_all(&identified).unwrap();
+        std::fs::write(inputs.join("a.txt"), "x").unwrap();
+        std::fs::write(inputs.join("b.txt"), "y").unwrap();
+        let module = NativeIdentificationModule::new(
+            StandaloneIdentificationModule::new(
+                "TextModule",
+                Vec::new(),
+                Vec::new(),
+                read_text_design_model,
+                |_, _| None,
+                |_| None,
+                HashSet::new(),
+            ),
+            &inputs,
+            &identified,
+            &dir.join("output"),
+        );
+        assert_eq!(module.design_models().len(), 2);
+        let kept: BTreeSet<BTreeSet<String>> =
+            idesyde_core::headers::load_design_model_headers_from_binary(&inputs)
+                .into_iter()
+                .flatten()
+                .map(|h| h.elements)
+                .collect();
+        assert_eq!(
+            kept,
+            BTreeSet::from([
+                BTreeSet::from(["x".to_string()]),
+                BTreeSet::from(["y".to_string()])
+            ])
+        );
+        // a change to the first input invalidates what was identified from it
+        write_decision_model_header_to_path(&test_header("FromA"), &identified, "", "Test")
+            .unwrap();
+        let mut from_b = test_header("FromB");
+        from_b.covered_elements = BTreeSet::from(["y".to_string()]);
+        write_decision_model_header_to_path(&from_b, &identified, "", "Test").unwrap();
+        let mut from_x = test_header("FromX");
+        from_x.covered_elements = BTreeSet::from(["x".to_string()]);
+        write_decision_model_header_to_path(&from_x, &identified, "", "Test").unwrap();
+        assert_eq!(
+            crate::cache::invalidate_changed_inputs(&["a.txt".to_string()], &inputs, &identified),
+            1
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// A solution of the decision model `test_header("Solved")` with its own body and the given objectives.
+    fn test_solution(name: &str, objectives: &[(&str, f64)]) -> DecisionModelHeader {
+        let mut h = test_header("Solved");
+        h.body_path = Some(format!("body_{}.json", name));
+        h.objectives = objectives
+            .iter()
+            .map(|(k, v)| (k.to_string(), *v))
+            .collect();
+        h
+    }
+
+    fn archived(archive: &ParetoArchive) -> Vec<Option<String>> {
+        archive
+            .solutions
+            .iter()
+            .map(|s| s.header.body_path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn archives_keep_the_non_dominated_solutions() {
+        let mut archive = ParetoArchive::default();
+        let slow = test_solution("slow", &[("period", 2.0), ("memory", 1.0)]);
+        let (kept, dominated) = archive.offer("E", &slow);
+        assert!(kept && dominated.is_empty());
+        // better in one objective and worse in the other
+        let big = test_solution("big", &[("period", 1.0), ("memory", 2.0)]);
+        let (kept, dominated) = archive.offer("E", &big);
+        assert!(kept && dominated.is_empty());
+        let worse = test_solution("worse", &[("period", 3.0), ("memory", 3.0)]);
+        assert!(!archive.offer("E", &worse).0);
+        let equal = test_solution("equal", &[("period", 2.0), ("memory", 1.0)]);
+        let (kept, dominated) = archive.offer("E", &equal);
+        assert!(kept && dominated.is_empty());
+        let best = test_solution("best", &[("period", 1.0), ("memory", 1.0)]);
+        let (kept, dominated) = archive.offer("F", &best);
+        assert!(kept);
+        assert_eq!(
+            dominated
+                .iter()
+                .map(|s| s.header.body_path.clone())
+                .collect::<Vec<Option<String>>>(),
+            vec![slow.body_path, big.body_path, equal.body_path]
+        );
+        assert_eq!(archived(&archive), vec![best.body_path]);
+    }
+
+    #[test]
+    fn archives_keep_solutions_with_other_or_no_objectives() {
+        let mut archive = ParetoArchive::default();
+        let timed = test_solution("timed", &[("period", 1.0)]);
+        let sized = test_solution("sized", &[("memory", 1.0)]);
+        let none = test_solution("none", &[]);
+        let another = test_solution("another", &[]);
+        for s in [&timed, &sized, &none, &another] {
+            let (kept, dominated) = archive.offer("E", s);
+            assert!(kept && dominated.is_empty());
+        }
+        assert_eq!(archive.solutions.len(), 4);
+    }
+
+    #[test]
+    fn archive_summaries_list_the_kept_solutions() {
+        let dir = test_dir("pareto-summary");
+        let mut archive = ParetoArchive::default();
+        let fast = test_solution("fast", &[("period", 1.0)]);
+        let slow = test_solution("slow", &[("period", 2.0)]);
+        write_decision_model_header_to_path(&fast, &dir, "0", "E").unwrap();
+        archive.offer("E", &slow);
+        archive.offer("F", &fast);
+        let summary_path = dir.join("pareto.json");
+        assert!(archive.write_summary(&dir, &summary_path));
+        let summary: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&summary_path).unwrap()).unwrap();
+        assert_eq!(
+            summary,
+            serde_json::json!([{
+                "explorer": "F",
+                "category": "Solved",
+                "header_path": dir.join("header_0_Solved_E.msgpack"),
+                "objectives": {"period": 1.0},
+            }])
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dominated_solutions_take_what_was_reverse_identified_from_them() {
+        let dir = test_dir("pareto-reversed");
+        let (explored, reverse) = (dir.join("explored"), dir.join("reverse"));
+        std::fs::create_dir_all(&explored).unwrap();
+        std::fs::create_dir_all(&reverse).unwrap();
+        let shared_output = dir.join("out.fiodl");
+        let mut archive = ParetoArchive::default();
+        let solutions = [
+            test_solution("small", &[("memory", 1.0)]),
+            test_solution("slow", &[("period", 2.0)]),
+            test_solution("fast", &[("period", 1.0)]),
+        ];
+        for (i, solution) in solutions.iter().enumerate() {
+            write_decision_model_header_to_path(solution, &explored, &i.to_string(), "E").unwrap();
+            let (_, dominated) = archive.offer("E", solution);
+            for s in &dominated {
+                archive.remove_dominated_files(s, &explored, &reverse);
+            }
+            // the small solution still has the output file that all solutions write to
+            assert!(i == 0 || shared_output.exists());
+            // what a module wrote for this solution, named after its header
+            std::fs::create_dir_all(reverse.join(format!("header_{}_Solved_E_Module", i))).unwrap();
+            let own_output = dir.join(format!("{}.fiodl", i));
+            std::fs::write(&own_output, "").unwrap();
+            std::fs::write(&shared_output, "").unwrap();
+            let design = DesignModelHeader {
+                category: "Design".to_string(),
+                model_paths: vec![
+                    own_output.display().to_string(),
+                    shared_output.display().to_string(),
+                ],
+                elements: BTreeSet::from([i.to_string()]),
+                relations: BTreeSet::new(),
+            };
+            idesyde_core::write_design_model_header_to_path(
+                &design,
+                &reverse,
+                &i.to_string(),
+                "Orchestrator",
+            )
+            .unwrap();
+            archive.add_reversed(
+                solution,
+                vec![(
+                    reverse.join(format!("header_{}_Design_Orchestrator.msgpack", i)),
+                    design,
+                )],
+            );
+        }
+        let mut left: Vec<String> = std::fs::read_dir(&reverse)
+            .unwrap()
+            .chain(std::fs::read_dir(&explored).unwrap())
+            .chain(std::fs::read_dir(&dir).unwrap())
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|f| f.contains('_') || f.ends_with(".fiodl"))
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                "0.fiodl",
+                "2.fiodl",
+                "header_0_Design_Orchestrator.cbor",
+                "header_0_Design_Orchestrator.json",
+                "header_0_Design_Orchestrator.msgpack",
+                "header_0_Solved_E.cbor",
+                "header_0_Solved_E.json",
+                "header_0_Solved_E.msgpack",
+                "header_0_Solved_E_Module",
+                "header_2_Design_Orchestrator.cbor",
+                "header_2_Design_Orchestrator.json",
+                "header_2_Design_Orchestrator.msgpack",
+                "header_2_Solved_E.cbor",
+                "header_2_Solved_E.json",
+                "header_2_Solved_E.msgpack",
+                "header_2_Solved_E_Module",
+                "out.fiodl",
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::process::ExitStatus;
+use std::process::Output;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use log::debug;
+use log::log;
+use log::warn;
+use log::Level;
+
+/// The processes of the modules that are running, so that they can be stopped with the orchestrator.
+static RUNNING_MODULES: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// The directory where the error output of each module is also written, if any.
+static MODULE_LOGS_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// The resources a module can use for each request.
+///
+/// The time limit is a wall-clock limit after which the module is stopped. The memory limit is set as the
+/// address space limit of the module process on Linux, and as the maximum heap of the JVM for `.jar` modules,
+/// as the JVM reserves much more address space than it uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ModuleLimits {
+    pub timeout: Option<Duration>,
+    pub memory_limit_megabytes: Option<u64>,
+}
+
+impl ModuleLimits {
+    /// The limits of a module, taking the ones it declares over the ones given to the orchestrator.
+    pub fn overridden_by(
+        &self,
+        timeout_seconds: Option<u64>,
+        memory_limit: Option<u64>,
+    ) -> ModuleLimits {
+        ModuleLimits {
+            timeout: timeout_seconds.map(Duration::from_secs).or(self.timeout),
+            memory_limit_megabytes: memory_limit.or(self.memory_limit_megabytes),
+        }
+    }
+
+    /// When a request started now has to be answered.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|t| Instant::now() + t)
+    }
+
+    /// The earliest of the deadline of a request started now and `other`.
+    pub fn deadline_before(&self, other: Option<Instant>) -> Option<Instant> {
+        match (self.deadline(), other) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// The JVM options that set the memory limit, for modules run with `java -jar`.
+    pub fn jvm_options(&self) -> Vec<String> {
+        self.memory_limit_megabytes
+            .map(|mb| vec![format!("-Xmx{}m", mb)])
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn apply(&self, cmd: &mut Command, is_jvm: bool) {
+        use std::os::unix::process::CommandExt;
+        if let (Some(mb), false) = (self.memory_limit_megabytes, is_jvm) {
+            let bytes = mb.saturating_mul(1024 * 1024) as libc::rlim_t;
+            // only async-signal-safe calls can be made between fork and exec, which setrlimit is
+            unsafe {
+                cmd.pre_exec(move || {
+                    let limit = libc::rlimit {
+                        rlim_cur: bytes,
+                        rlim_max: bytes,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn apply(&self, _cmd: &mut Command, _is_jvm: bool) {}
+}
+
+/// Spawns a module with its memory limit, keeping track of it until it is reaped with [reap_module].
+///
+/// On unix the module gets its own process group, so that the processes it starts are stopped with it.
+pub fn spawn_module(cmd: &mut Command, limits: &ModuleLimits) -> std::io::Result<Child> {
+    let is_jvm = cmd.get_program() == "java";
+    limits.apply(cmd, is_jvm);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let child = cmd.spawn()?;
+    RUNNING_MODULES
+        .lock()
+        .expect("Failed to lock the running modules")
+        .insert(child.id());
+    Ok(child)
+}
+
+/// Waits for a module to finish, forgetting about its process.
+pub fn reap_module(child: &mut Child) {
+    let _ = child.wait();
+    RUNNING_MODULES
+        .lock()
+        .expect("Failed to lock the running modules")
+        .remove(&child.id());
+}
+
+/// Stops a module if it is still running, and reaps it.
+pub fn stop_module(uid: &str, child: &mut Child) {
+    if let Ok(None) = child.try_wait() {
+        debug!("Stopping module {}", uid);
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+        }
+        if child.kill().is_err() {
+            warn!("Failed to stop module {}", uid);
+        }
+    }
+    reap_module(child);
+}
+
+/// Runs a module to completion, like [Command::output], but stopping it once its time limit is reached.
+///
+/// Both outputs are read while the module runs, so that it never blocks on a full pipe.
+pub fn run_module(uid: &str, cmd: &mut Command, limits: &ModuleLimits) -> Option<Output> {
+    let mut child = match spawn_module(cmd.stdout(Stdio::piped()).stderr(Stdio::piped()), limits) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Failed to run module {}: {}", uid, e);
+            return None;
+        }
+    };
+    let mut out = child
+        .stdout
+        .take()
+        .expect("Failed to acquire module STDOUT");
+    let err = child
+        .stderr
+        .take()
+        .expect("Failed to acquire module STDERR");
+    let out_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = out.read_to_end(&mut buf);
+        buf
+    });
+    let err_reader = forward_module_logs(uid, err);
+    let status = wait_module(uid, &mut child, limits)?;
+    let _ = err_reader.join();
+    Some(Output {
+        status,
+        stdout: out_reader.join().unwrap_or_default(),
+        stderr: Vec::new(),
+    })
+}
+
+/// Runs a module to completion like [run_module], but discarding its error output, for requests that
+/// modules may not understand.
+pub fn run_module_quietly(uid: &str, cmd: &mut Command, limits: &ModuleLimits) -> Option<Output> {
+    let mut child = spawn_module(cmd.stdout(Stdio::piped()).stderr(Stdio::null()), limits)
+        .map_err(|e| debug!("Failed to run module {}: {}", uid, e))
+        .ok()?;
+    let mut out = child
+        .stdout
+        .take()
+        .expect("Failed to acquire module STDOUT");
+    let out_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = out.read_to_end(&mut buf);
+        buf
+    });
+    let status = wait_module(uid, &mut child, limits)?;
+    Some(Output {
+        status,
+        stdout: out_reader.join().unwrap_or_default(),
+        stderr: Vec::new(),
+    })
+}
+
+/// Waits for a module to finish and reaps it, stopping it once its time limit is reached.
+fn wait_module(uid: &str, child: &mut Child, limits: &ModuleLimits) -> Option<ExitStatus> {
+    let deadline = limits.deadline();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if deadline.is_some_and(|d| Instant::now() >= d) => {
+                warn!(
+                    "Module {} did not finish within its time limit of {:?}",
+                    uid,
+                    limits.timeout.unwrap_or_default()
+                );
+                stop_module(uid, child);
+                return None;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(e) => {
+                warn!("Failed to wait for module {}: {}", uid, e);
+                stop_module(uid, child);
+                return None;
+            }
+        }
+    };
+    reap_module(child);
+    Some(status)
+}
+
+/// Sets the directory where the error output of each module is also written, in a file named after it.
+pub fn set_module_logs_path(path: &Path) {
+    if MODULE_LOGS_PATH.set(path.to_path_buf()).is_err() {
+        debug!("Module logs are already written to another directory");
+    }
+}
+
+/// The level and message of a line written by a module, as in `INFO: message`.
+///
+/// Lines without a level are taken as warnings, since modules are only expected to write there when
+/// something is off.
+pub fn module_log_line(line: &str) -> (Level, &str) {
+    if let Some((prefix, message)) = line.split_once(':') {
+        let level = match prefix.trim().to_lowercase().as_str() {
+            "trace" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" | "warning" => Some(Level::Warn),
+            "err" | "error" => Some(Level::Error),
+            _ => None,
+        };
+        if let Some(l) = level {
+            return (l, message.trim_start());
+        }
+    }
+    (Level::Warn, line)
+}
+
+/// The log file of a module, named after its executable for modules identified by their path.
+fn module_log_file_path(logs_path: &Path, uid: &str) -> PathBuf {
+    let name = Path::new(uid)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(uid);
+    let file_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    logs_path.join(format!("{}.log", file_name))
+}
+
+/// Forwards the error output of a module to the log line by line, with the module identifier as the
+/// target, until the output ends. The lines are also appended to the log file of the module, if a logs
+/// directory was set with [set_module_logs_path].
+pub fn forward_module_logs<R: Read + Send + 'static>(uid: &str, stream: R) -> JoinHandle<()> {
+    let uid = uid.to_string();
+    let mut log_file_path = MODULE_LOGS_PATH
+        .get()
+        .map(|p| module_log_file_path(p, &uid));
+    std::thread::spawn(move || {
+        let mut log_file: Option<File> = None;
+        for l in BufReader::new(stream).lines().map_while(Result::ok) {
+            if l.trim().is_empty() {
+                continue;
+            }
+            // the file is only created once the module writes something, and only tried once
+            if let Some(p) = log_file_path.take() {
+                log_file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&p)
+                    .map_err(|e| warn!("Failed to open the log file {}: {}", p.display(), e))
+                    .ok();
+            }
+            if let Some(f) = log_file.as_mut() {
+                let _ = writeln!(f, "{}", l);
+            }
+            let (level, message) = module_log_line(&l);
+            log!(target: uid.as_str(), level, "{}: {}", uid, message);
+        }
+    })
+}
+
+/// Forwards SIGINT and SIGTERM to all the running modules before the orchestrator exits with them, so that
+/// no module is left running on its own.
+#[cfg(unix)]
+pub fn propagate_termination_signals() {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    match signal_hook::iterator::Signals::new([SIGINT, SIGTERM]) {
+        Ok(mut signals) => {
+            std::thread::spawn(move || {
+                if let Some(sig) = signals.forever().next() {
+                    let running = RUNNING_MODULES
+                        .lock()
+                        .map(|r| r.clone())
+                        .unwrap_or_default();
+                    debug!("Forwarding signal {} to {} module(s)", sig, running.len());
+                    for pid in running {
+                        unsafe {
+                            libc::kill(-(pid as libc::pid_t), sig);
+                        }
+                    }
+                    std::process::exit(128 + sig);
+                }
+            });
+        }
+        Err(e) => warn!("Failed to handle termination signals: {}", e),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn propagate_termination_signals() {}
@@ -0,0 +1,354 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use idesyde_core::headers::DecisionModelHeader;
+use idesyde_core::IdesydeError;
+use jsonschema::JSONSchema;
+use log::debug;
+use log::warn;
+use serde_json::Value;
+
+/// The keywords of a schema that document it without changing what it accepts.
+const ANNOTATIONS: [&str; 5] = ["$schema", "$comment", "description", "examples", "default"];
+
+/// The encodings of the bodies that can be validated, in the order they are tried. MsgPack bodies are left
+/// out as they encode structs as arrays, which the schemas do not describe.
+const VALIDATED_BODY_FORMATS: [&str; 2] = ["json", "cbor"];
+
+/// A schema that a module published for the bodies of a category.
+struct PublishedSchema {
+    module: String,
+    schema: Value,
+    validator: JSONSchema,
+}
+
+/// The schemas that the modules publish for the bodies of the decision models, keyed by category, which is
+/// the title of each schema.
+///
+/// A category has more than one schema when modules disagree on it, in which case a body is valid as long as
+/// one of them accepts it, since at least one module can read it.
+#[derive(Default)]
+pub struct SchemaRegistry {
+    schemas: HashMap<String, Vec<PublishedSchema>>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> SchemaRegistry {
+        SchemaRegistry::default()
+    }
+
+    /// Adds the schemas published by `module`, warning about the ones that conflict with the schemas
+    /// other modules published for the same category.
+    pub fn register<'a>(&mut self, module: &str, schemas: impl IntoIterator<Item = &'a String>) {
+        for s in schemas {
+            let schema = match serde_json::from_str::<Value>(s) {
+                Ok(v) => without_annotations(&v),
+                Err(e) => {
+                    warn!("Ignoring a schema of module {}: {}", module, e);
+                    continue;
+                }
+            };
+            let Some(category) = schema
+                .get("title")
+                .and_then(|t| t.as_str())
+                .map(|t| t.to_string())
+            else {
+                debug!("Ignoring a schema of module {} without a title", module);
+                continue;
+            };
+            let published = self.schemas.entry(category.to_owned()).or_default();
+            if published.iter().any(|p| p.schema == schema) {
+                continue;
+            }
+            let validator = match JSONSchema::compile(&schema) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(
+                        "Ignoring the schema of {} from module {}: {}",
+                        category, module, e
+                    );
+                    continue;
+                }
+            };
+            for p in published.iter() {
+                warn!(
+                    "Modules {} and {} publish incompatible schemas for {}: {}",
+                    p.module,
+                    module,
+                    category,
+                    schema_difference(&p.schema, &schema)
+                );
+            }
+            published.push(PublishedSchema {
+                module: module.to_string(),
+                schema,
+                validator,
+            });
+        }
+    }
+
+    /// The categories with at least one schema.
+    pub fn categories(&self) -> BTreeSet<&str> {
+        self.schemas.keys().map(|c| c.as_str()).collect()
+    }
+
+    /// Validates the body of the decision model of `header` against the schemas of its category.
+    ///
+    /// Models of categories without schemas, or without a body that can be validated, are taken as valid.
+    pub fn validate(&self, header: &DecisionModelHeader) -> Result<(), IdesydeError> {
+        let Some(published) = self.schemas.get(&header.category) else {
+            return Ok(());
+        };
+        let Some((path, body)) = read_body(header) else {
+            return Ok(());
+        };
+        if published.iter().any(|p| p.validator.is_valid(&body)) {
+            return Ok(());
+        }
+        let message = published
+            .iter()
+            .map(|p| match p.validator.validate(&body) {
+                Ok(()) => String::new(),
+                Err(errors) => errors
+                    .map(|e| match e.instance_path.to_string().as_str() {
+                        "" => e.to_string(),
+                        at => format!("{} at {}", e, at),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            })
+            .collect::<Vec<String>>()
+            .join("; ");
+        Err(IdesydeError::SchemaMismatch {
+            path,
+            category: header.category.to_owned(),
+            message,
+        })
+    }
+
+    /// Whether the body of the decision model of `header` is valid, warning about it if not.
+    pub fn accepts(&self, header: &DecisionModelHeader) -> bool {
+        match self.validate(header) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}. Ignoring the decision model.", e);
+                false
+            }
+        }
+    }
+}
+
+/// The schema without its annotations, so that schemas that accept the same bodies compare as equal.
+fn without_annotations(schema: &Value) -> Value {
+    match schema {
+        Value::Object(m) => Value::Object(
+            m.iter()
+                .filter(|(k, _)| !ANNOTATIONS.contains(&k.as_str()))
+                .map(|(k, v)| match (k.as_str(), v) {
+                    // the keys of these are names rather than keywords
+                    (
+                        "properties" | "patternProperties" | "definitions" | "$defs",
+                        Value::Object(named),
+                    ) => (
+                        k.to_owned(),
+                        Value::Object(
+                            named
+                                .iter()
+                                .map(|(n, s)| (n.to_owned(), without_annotations(s)))
+                                .collect(),
+                        ),
+                    ),
+                    _ => (k.to_owned(), without_annotations(v)),
+                })
+                .collect(),
+        ),
+        Value::Array(a) => Value::Array(a.iter().map(without_annotations).collect()),
+        v => v.to_owned(),
+    }
+}
+
+/// A short account of how two schemas of the same category differ.
+fn schema_difference(a: &Value, b: &Value) -> String {
+    let names = |v: &Value, keyword: &str| -> BTreeSet<String> {
+        match v.get(keyword) {
+            Some(Value::Object(m)) => m.keys().cloned().collect(),
+            Some(Value::Array(a)) => a
+                .iter()
+                .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                .collect(),
+            _ => BTreeSet::new(),
+        }
+    };
+    for keyword in ["properties", "required"] {
+        let (from_a, from_b) = (names(a, keyword), names(b, keyword));
+        if from_a != from_b {
+            return format!(
+                "their {} differ in {}",
+                keyword,
+                from_a
+                    .symmetric_difference(&from_b)
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        }
+    }
+    "the types of their properties differ".to_string()
+}
+
+/// The body of the decision model of `header` as JSON, with the path it was read from.
+fn read_body(header: &DecisionModelHeader) -> Option<(PathBuf, Value)> {
+    VALIDATED_BODY_FORMATS
+        .iter()
+        .filter_map(|f| header.body_paths.get(*f))
+        .chain(header.body_path.iter())
+        .map(PathBuf::from)
+        .find_map(|p| read_body_from_path(&p).map(|v| (p, v)))
+}
+
+fn read_body_from_path(path: &Path) -> Option<Value> {
+    let f = std::fs::File::open(path).ok()?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_reader(f).ok(),
+        Some("cbor") => ciborium::from_reader(f).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn schema_of(title: &str, required: &[&str]) -> String {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": title,
+            "description": "A test decision model",
+            "type": "object",
+            "properties": {
+                "actors": { "type": "array", "items": { "type": "string" }, "description": "The actors" },
+                "description": { "type": "string" }
+            },
+            "required": required
+        })
+        .to_string()
+    }
+
+    fn header_with_body(name: &str, body: &Value) -> (PathBuf, DecisionModelHeader) {
+        let dir =
+            std::env::temp_dir().join(format!("idesyde-schemas-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let body_path = dir.join("body.json");
+        std::fs::write(&body_path, body.to_string()).unwrap();
+        let header = DecisionModelHeader {
+            category: "TestModel".to_string(),
+            body_path: None,
+            body_paths: HashMap::from([("json".to_string(), body_path.display().to_string())]),
+            covered_elements: BTreeSet::new(),
+            objectives: HashMap::new(),
+            covered_relations: BTreeSet::new(),
+        };
+        (dir, header)
+    }
+
+    #[test]
+    fn without_annotations_keeps_properties_named_as_annotations() {
+        let schema: Value = serde_json::from_str(&schema_of("TestModel", &["actors"])).unwrap();
+        let stripped = without_annotations(&schema);
+        assert_eq!(
+            stripped,
+            json!({
+                "title": "TestModel",
+                "type": "object",
+                "properties": {
+                    "actors": { "type": "array", "items": { "type": "string" } },
+                    "description": { "type": "string" }
+                },
+                "required": ["actors"]
+            })
+        );
+    }
+
+    #[test]
+    fn register_keeps_one_schema_per_category_up_to_annotations() {
+        let mut registry = SchemaRegistry::new();
+        let annotated = schema_of("TestModel", &["actors"]);
+        let mut plain: Value = serde_json::from_str(&annotated).unwrap();
+        plain.as_object_mut().unwrap().remove("description");
+        registry.register("A", [&annotated]);
+        registry.register("B", [&plain.to_string()]);
+        assert_eq!(registry.categories(), BTreeSet::from(["TestModel"]));
+        assert_eq!(registry.schemas["TestModel"].len(), 1);
+    }
+
+    #[test]
+    fn register_keeps_conflicting_schemas_of_a_category() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("A", [&schema_of("TestModel", &["actors"])]);
+        registry.register("B", [&schema_of("TestModel", &[])]);
+        assert_eq!(registry.schemas["TestModel"].len(), 2);
+        let difference = schema_difference(
+            &registry.schemas["TestModel"][0].schema,
+            &registry.schemas["TestModel"][1].schema,
+        );
+        assert_eq!(difference, "their required differ in actors");
+    }
+
+    #[test]
+    fn register_ignores_malformed_and_untitled_schemas() {
+        let mut registry = SchemaRegistry::new();
+        let untitled = json!({ "type": "object" }).to_string();
+        let malformed = "{ \"title\": ".to_string();
+        registry.register("A", [&untitled, &malformed]);
+        assert!(registry.categories().is_empty());
+    }
+
+    #[test]
+    fn validate_checks_bodies_against_their_category() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("A", [&schema_of("TestModel", &["actors"])]);
+        let (dir, valid) = header_with_body("valid", &json!({ "actors": ["a", "b"] }));
+        assert!(registry.validate(&valid).is_ok());
+        let _ = std::fs::remove_dir_all(dir);
+        let (dir, invalid) = header_with_body("invalid", &json!({ "actors": [1] }));
+        assert!(matches!(
+            registry.validate(&invalid),
+            Err(IdesydeError::SchemaMismatch { category, .. }) if category == "TestModel"
+        ));
+        assert!(!registry.accepts(&invalid));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn validate_accepts_a_body_valid_for_any_conflicting_schema() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("A", [&schema_of("TestModel", &["actors"])]);
+        registry.register("B", [&schema_of("TestModel", &[])]);
+        let (dir, header) = header_with_body("conflicting", &json!({}));
+        assert!(registry.validate(&header).is_ok());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn validate_accepts_models_it_cannot_check() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("A", [&schema_of("OtherModel", &["actors"])]);
+        let (dir, other_category) = header_with_body("other", &json!({ "actors": [1] }));
+        assert!(registry.validate(&other_category).is_ok());
+        let _ = std::fs::remove_dir_all(dir);
+        registry.register("A", [&schema_of("TestModel", &["actors"])]);
+        let without_body = DecisionModelHeader {
+            category: "TestModel".to_string(),
+            body_path: None,
+            body_paths: HashMap::new(),
+            covered_elements: BTreeSet::new(),
+            objectives: HashMap::new(),
+            covered_relations: BTreeSet::new(),
+        };
+        assert!(registry.validate(&without_body).is_ok());
+    }
+}
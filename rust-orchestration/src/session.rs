@@ -0,0 +1,366 @@
+use std::ffi::OsString;
+use std::hash::Hash;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::ChildStdin;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use idesyde_core::protocol::read_message;
+use idesyde_core::protocol::write_message;
+use idesyde_core::protocol::ModuleLauncher;
+use idesyde_core::protocol::ModuleRequest;
+use idesyde_core::protocol::ModuleResponse;
+use idesyde_core::protocol::SERVER_ARG;
+use idesyde_core::IdesydeError;
+use log::debug;
+
+use crate::process::forward_module_logs;
+use crate::process::reap_module;
+use crate::process::spawn_module;
+use crate::process::stop_module;
+use crate::process::ModuleLimits;
+
+/// How long a module has to announce that it started as a server.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a wait for a module checks whether it was cancelled.
+pub const CANCELLATION_POLL: Duration = Duration::from_millis(100);
+
+/// The command that runs the module at `command_path` with its launcher.
+pub fn module_command(
+    launcher: ModuleLauncher,
+    command_path: &Path,
+    limits: &ModuleLimits,
+) -> Command {
+    match launcher {
+        ModuleLauncher::Native => Command::new(command_path),
+        ModuleLauncher::Java => {
+            let mut cmd = Command::new("java");
+            cmd.args(limits.jvm_options()).arg("-jar").arg(command_path);
+            cmd
+        }
+        ModuleLauncher::Python => {
+            let mut cmd = Command::new("python3");
+            cmd.arg(command_path);
+            cmd
+        }
+    }
+}
+
+/// A module started once as a server, which answers the requests of the orchestrator over its standard
+/// input and output for as long as the session lives.
+pub struct ModuleSession {
+    uid: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    responses: Receiver<ModuleResponse>,
+}
+
+impl ModuleSession {
+    /// Starts the module at `command_path` as a server with the given arguments.
+    ///
+    /// It fails if the module does not announce itself as ready, which is the case for modules that can
+    /// only be run once per request.
+    pub fn start(
+        uid: &str,
+        launcher: ModuleLauncher,
+        command_path: &Path,
+        args: &[OsString],
+        limits: &ModuleLimits,
+    ) -> Result<ModuleSession, IdesydeError> {
+        let mut child = spawn_module(
+            module_command(launcher, command_path, limits)
+                .arg(SERVER_ARG)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped()),
+            limits,
+        )
+        .map_err(|e| IdesydeError::io(command_path, e))?;
+        let stdin = child.stdin.take();
+        let mut out = child
+            .stdout
+            .take()
+            .expect("Failed to acquire module server STDOUT");
+        let err = child
+            .stderr
+            .take()
+            .expect("Failed to acquire module server STDERR");
+        forward_module_logs(uid, err);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let out_uid = uid.to_string();
+        std::thread::spawn(move || loop {
+            match read_message::<_, ModuleResponse>(&mut out) {
+                Ok(Some(r)) => {
+                    if tx.send(r).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    debug!("Failed to read a response from module {}: {}", out_uid, e);
+                    break;
+                }
+            }
+        });
+        let mut session = ModuleSession {
+            uid: uid.to_string(),
+            child,
+            stdin,
+            responses: rx,
+        };
+        match session.response(Some(Instant::now() + STARTUP_TIMEOUT))? {
+            ModuleResponse::Ready { .. } => Ok(session),
+            r => {
+                session.stop();
+                Err(session.protocol_error(format!(
+                    "it answered {:?} instead of announcing it was ready",
+                    r
+                )))
+            }
+        }
+    }
+
+    fn protocol_error(&self, message: String) -> IdesydeError {
+        IdesydeError::ModuleProtocol {
+            module: self.uid.to_owned(),
+            message,
+        }
+    }
+
+    /// Sends a request without waiting for its responses.
+    pub fn request(&mut self, request: &ModuleRequest) -> Result<(), IdesydeError> {
+        match self.stdin.as_mut() {
+            Some(stdin) => write_message(stdin, request)
+                .map_err(|e| self.protocol_error(format!("it could not take a request: {}", e))),
+            None => Err(self.protocol_error("it was already stopped".to_string())),
+        }
+    }
+
+    /// Waits for the next response, until `deadline` if there is one.
+    pub fn response(&self, deadline: Option<Instant>) -> Result<ModuleResponse, IdesydeError> {
+        let received = match deadline {
+            Some(d) => self
+                .responses
+                .recv_timeout(d.saturating_duration_since(Instant::now())),
+            None => self
+                .responses
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        self.answer(received)
+    }
+
+    /// Waits for the next response like [ModuleSession::response], giving up as soon as `cancelled` is set.
+    pub fn cancellable_response(
+        &self,
+        deadline: Option<Instant>,
+        cancelled: &AtomicBool,
+    ) -> Result<ModuleResponse, IdesydeError> {
+        loop {
+            let poll = Instant::now() + CANCELLATION_POLL;
+            let received = self.responses.recv_timeout(
+                deadline
+                    .map_or(poll, |d| d.min(poll))
+                    .saturating_duration_since(Instant::now()),
+            );
+            match received {
+                Err(RecvTimeoutError::Timeout) if cancelled.load(Ordering::SeqCst) => {
+                    return Err(self.protocol_error("its exploration was cancelled".to_string()));
+                }
+                Err(RecvTimeoutError::Timeout) if deadline.is_none_or(|d| Instant::now() < d) => {}
+                r => return self.answer(r),
+            }
+        }
+    }
+
+    fn answer(
+        &self,
+        received: Result<ModuleResponse, RecvTimeoutError>,
+    ) -> Result<ModuleResponse, IdesydeError> {
+        match received {
+            Ok(ModuleResponse::Error(message)) => Err(self.protocol_error(message)),
+            Ok(r) => Ok(r),
+            Err(RecvTimeoutError::Timeout) => {
+                Err(self.protocol_error("it did not answer in time".to_string()))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(self.protocol_error("it stopped without answering".to_string()))
+            }
+        }
+    }
+
+    /// Sends a request and waits for its single response.
+    pub fn call(&mut self, request: &ModuleRequest) -> Result<ModuleResponse, IdesydeError> {
+        self.request(request)?;
+        self.response(None)
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        self.stdin.is_some() && matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Stops the module right away, e.g. when it is in the middle of an exploration that is not needed anymore.
+    pub fn stop(&mut self) {
+        self.stdin = None;
+        stop_module(&self.uid, &mut self.child);
+    }
+}
+
+impl Drop for ModuleSession {
+    /// Asks the module to shut down, stopping it if it does not in a second.
+    fn drop(&mut self) {
+        if self.stdin.is_some() && self.request(&ModuleRequest::Shutdown).is_ok() {
+            self.stdin = None;
+            let deadline = Instant::now() + Duration::from_secs(1);
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = self.child.try_wait() {
+                    reap_module(&mut self.child);
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+        self.stop();
+    }
+}
+
+enum SessionSlot {
+    NotStarted,
+    Running(ModuleSession),
+    Unsupported,
+}
+
+/// The server of an external module, which is started on its first request and kept for the following ones.
+///
+/// Only modules that declare that they can be servers are started as one. If the module does not, or it
+/// cannot be started as a server after all, it is not tried again and the requests are answered by `None`,
+/// so that the module can be run once per request instead.
+pub struct ModuleServer {
+    uid: String,
+    launcher: ModuleLauncher,
+    command_path: PathBuf,
+    args: Vec<OsString>,
+    limits: ModuleLimits,
+    slot: Mutex<SessionSlot>,
+}
+
+impl ModuleServer {
+    pub fn new(
+        uid: &str,
+        launcher: ModuleLauncher,
+        command_path: &Path,
+        args: Vec<OsString>,
+        limits: ModuleLimits,
+        serves: bool,
+    ) -> ModuleServer {
+        ModuleServer {
+            uid: uid.to_string(),
+            launcher,
+            limits,
+            command_path: command_path.to_path_buf(),
+            args,
+            slot: Mutex::new(if serves {
+                SessionSlot::NotStarted
+            } else {
+                SessionSlot::Unsupported
+            }),
+        }
+    }
+
+    /// Takes the running session out of the server, starting one if there is none, so that it can be used
+    /// exclusively. It should be given back with [ModuleServer::give_back] once it is done.
+    pub fn take(&self) -> Option<ModuleSession> {
+        let mut slot = self.slot.lock().expect("Failed to lock a module server");
+        match std::mem::replace(&mut *slot, SessionSlot::NotStarted) {
+            SessionSlot::Running(session) => Some(session),
+            SessionSlot::Unsupported => {
+                *slot = SessionSlot::Unsupported;
+                None
+            }
+            SessionSlot::NotStarted => {
+                match ModuleSession::start(
+                    &self.uid,
+                    self.launcher,
+                    &self.command_path,
+                    &self.args,
+                    &self.limits,
+                ) {
+                    Ok(session) => {
+                        debug!("Started module {} as a server", self.uid);
+                        Some(session)
+                    }
+                    Err(e) => {
+                        debug!("Running module {} once per request: {}", self.uid, e);
+                        *slot = SessionSlot::Unsupported;
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Keeps `session` for the next requests, unless another one was started in the meantime.
+    pub fn give_back(&self, session: ModuleSession) {
+        let mut slot = self.slot.lock().expect("Failed to lock a module server");
+        if let SessionSlot::NotStarted = *slot {
+            *slot = SessionSlot::Running(session);
+        }
+    }
+
+    /// Sends a request that has a single response, or `None` if the module is not a server.
+    ///
+    /// A session whose module stopped, or did not answer within its time limit, is dropped, and a new one
+    /// is started on the next request.
+    pub fn call(&self, request: &ModuleRequest) -> Option<Result<ModuleResponse, IdesydeError>> {
+        let mut session = self.take()?;
+        let deadline = self.limits.deadline();
+        let response = session
+            .request(request)
+            .and_then(|()| session.response(deadline));
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            session.stop();
+        }
+        if session.is_running() {
+            self.give_back(session);
+        }
+        Some(response)
+    }
+}
+
+impl std::fmt::Debug for ModuleServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleServer")
+            .field("uid", &self.uid)
+            .field("launcher", &self.launcher)
+            .field("limits", &self.limits)
+            .field("command_path", &self.command_path)
+            .field("args", &self.args)
+            .finish()
+    }
+}
+
+impl PartialEq for ModuleServer {
+    fn eq(&self, other: &Self) -> bool {
+        self.command_path == other.command_path && self.args == other.args
+    }
+}
+
+impl Eq for ModuleServer {}
+
+impl Hash for ModuleServer {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.command_path.hash(state);
+        self.args.hash(state);
+    }
+}
@@ -51,7 +51,11 @@ trait CanParseExplorationModuleConfiguration {
           .action((f, mc) => mc.copy(timeResolution = Some(f))),
         builder
           .opt[Long]("memory-resolution")
-          .action((f, mc) => mc.copy(memoryResolution = Some(f)))
+          .action((f, mc) => mc.copy(memoryResolution = Some(f))),
+        builder
+          .opt[Unit]("describe")
+          .text("Prints the description of this module, with the protocol version it speaks.")
+          .action((_, mc) => mc.copy(describe = true))
       ),
       args,
       ExplorationModuleConfiguration()
@@ -72,6 +72,10 @@ trait CanParseIdentificationModuleConfiguration {
         .opt[Boolean]("schemas")
         .text("Schemas for the design models considered in this module.")
         .action((f, mc) => mc.copy(printSchemas = f)),
+      builder
+        .opt[Unit]("describe")
+        .text("Prints the description of this module, with the protocol version it speaks.")
+        .action((_, mc) => mc.copy(describe = true)),
       builder.note(
         """The module needs to be invoked with at least the design and identified paths. 
         If the module is invoked with the integration path as well, 
@@ -9,5 +9,6 @@ final case class ExplorationModuleConfiguration(
     val timeResolution: Option[Long] = None,
     val memoryResolution: Option[Long] = None,
     val maximumSolutions: Long = 0L,
-    val explorationTotalTimeOutInSecs: Long = 0L
+    val explorationTotalTimeOutInSecs: Long = 0L,
+    val describe: Boolean = false
 )
@@ -7,5 +7,6 @@ final case class IdentificationModuleConfiguration(
     val integrationPath: Option[os.Path] = None,
     val outputPath: Option[os.Path] = None,
     val identificationStep: Long = 0,
-    val printSchemas: Boolean = false
+    val printSchemas: Boolean = false,
+    val describe: Boolean = false
 )
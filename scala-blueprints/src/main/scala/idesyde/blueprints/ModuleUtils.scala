@@ -11,6 +11,34 @@ import os.PathError
 
 trait ModuleUtils {
 
+  /** The version of the protocol spoken with the orchestrator, which checks it before registering
+    * the module.
+    */
+  val protocolVersion: String = "0.6.0"
+
+  /** Prints the description of a module, as answered to `--describe`.
+    *
+    * The decision model categories are left empty, so that the module is asked about every decision
+    * model.
+    */
+  def printDescription(
+      uniqueIdentifier: String,
+      kind: String,
+      decisionModelSchemas: Iterable[String]
+  ): Unit = {
+    println(
+      ujson
+        .Obj(
+          "unique_identifier"         -> uniqueIdentifier,
+          "kind"                      -> kind,
+          "protocol_version"          -> protocolVersion,
+          "decision_model_categories" -> ujson.Arr(),
+          "decision_model_schemas"    -> ujson.Arr.from(decisionModelSchemas.map(ujson.Str(_)))
+        )
+        .toString
+    )
+  }
+
   def decodeFromPath[T: ReadWriter](p: String): Option[T] = {
     var decoded: Option[T] = None
     try {
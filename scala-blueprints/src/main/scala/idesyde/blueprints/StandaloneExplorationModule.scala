@@ -53,6 +53,8 @@ trait StandaloneExplorationModule
     parse(args, uniqueIdentifier) match {
       case Some(conf) =>
         conf match {
+          case ExplorationModuleConfiguration(_, _, _, _, _, _, _, _, _, true) =>
+            printDescription(uniqueIdentifier, "exploration", Vector())
           case ExplorationModuleConfiguration(
                 Some(dominantPath),
                 Some(solutionPath),
@@ -62,7 +64,8 @@ trait StandaloneExplorationModule
                 timeResolution,
                 memoryResolution,
                 maximumSolutions,
-                explorationTotalTimeOutInSecs
+                explorationTotalTimeOutInSecs,
+                _
               ) =>
             os.makeDir.all(dominantPath)
             os.makeDir.all(solutionPath)
@@ -91,6 +94,7 @@ trait StandaloneExplorationModule
                 _,
                 _,
                 _,
+                _,
                 _
               ) =>
             val header = readBinary[DecisionModelHeader](os.read.bytes(decisionModelToGetCriterias))
@@ -79,7 +79,9 @@ trait StandaloneIdentificationModule
     parse(args, uniqueIdentifier) match {
       case Right(conf) =>
         conf match {
-          case IdentificationModuleConfiguration(_, _, _, _, _, _, true) =>
+          case IdentificationModuleConfiguration(_, _, _, _, _, _, _, true) =>
+            printDescription(uniqueIdentifier, "identification", decisionModelSchemas)
+          case IdentificationModuleConfiguration(_, _, _, _, _, _, true, _) =>
             for (schema <- decisionModelSchemas) println(schema)
           case IdentificationModuleConfiguration(
                 Some(designPath),
@@ -88,7 +90,8 @@ trait StandaloneIdentificationModule
                 Some(reversePath),
                 outP,
                 _,
-                false
+                false,
+                _
               ) =>
             os.makeDir.all(designPath)
             os.makeDir.all(solvedPath)
@@ -161,7 +164,8 @@ trait StandaloneIdentificationModule
                 _,
                 _,
                 iteration,
-                false
+                false,
+                _
               ) =>
             os.makeDir.all(designPath)
             os.makeDir.all(identifiedPath)